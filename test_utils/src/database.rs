@@ -0,0 +1,78 @@
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    collections::BTreeMap,
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::cell::RefCell;
+
+use wie_backend::{Database, DatabaseError, DatabaseRepository, DatabaseResult, RecordId};
+
+type Records = BTreeMap<RecordId, Vec<u8>>;
+
+/// Keeps databases in memory, record ids are allocated the same way as the file backed repository
+#[derive(Default)]
+pub struct TestDatabaseRepository {
+    databases: RefCell<BTreeMap<String, Rc<RefCell<Records>>>>,
+}
+
+impl DatabaseRepository for TestDatabaseRepository {
+    fn open(&self, name: &str, create: bool) -> DatabaseResult<Box<dyn Database>> {
+        let mut databases = self.databases.borrow_mut();
+
+        let records = match databases.get(name) {
+            Some(x) => x.clone(),
+            None if create => databases.entry(name.to_owned()).or_default().clone(),
+            None => return Err(DatabaseError::NotFound),
+        };
+
+        Ok(Box::new(TestDatabase { records }))
+    }
+
+    fn delete(&self, name: &str) -> DatabaseResult<()> {
+        self.databases.borrow_mut().remove(name).ok_or(DatabaseError::NotFound)?;
+
+        Ok(())
+    }
+
+    fn list(&self) -> DatabaseResult<Vec<String>> {
+        Ok(self.databases.borrow().keys().map(|x| x.to_string()).collect())
+    }
+}
+
+struct TestDatabase {
+    records: Rc<RefCell<Records>>,
+}
+
+impl Database for TestDatabase {
+    fn add(&mut self, data: &[u8]) -> DatabaseResult<RecordId> {
+        let mut records = self.records.borrow_mut();
+
+        let id = (0..).find(|x| !records.contains_key(x)).unwrap();
+        records.insert(id, data.to_vec());
+
+        Ok(id)
+    }
+
+    fn get(&self, id: RecordId) -> DatabaseResult<Vec<u8>> {
+        self.records.borrow().get(&id).cloned().ok_or(DatabaseError::NotFound)
+    }
+
+    fn set(&mut self, id: RecordId, data: &[u8]) -> DatabaseResult<()> {
+        self.records.borrow_mut().insert(id, data.to_vec());
+
+        Ok(())
+    }
+
+    fn delete(&mut self, id: RecordId) -> DatabaseResult<()> {
+        self.records.borrow_mut().remove(&id).ok_or(DatabaseError::NotFound)?;
+
+        Ok(())
+    }
+
+    fn get_record_ids(&self) -> DatabaseResult<Vec<RecordId>> {
+        Ok(self.records.borrow().keys().copied().collect())
+    }
+}
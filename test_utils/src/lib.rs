@@ -1,6 +1,7 @@
 #![no_std]
 extern crate alloc;
 
+mod database;
 mod jvm;
 mod platform;
 mod runtime;
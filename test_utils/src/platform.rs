@@ -1,25 +1,58 @@
-use alloc::boxed::Box;
+use alloc::{boxed::Box, rc::Rc};
+use core::cell::Cell;
 
-use wie_backend::{AudioSink, Platform};
+use wie_backend::{AudioSink, HandsetProfile, Platform};
 
-pub struct TestPlatform;
+use crate::database::TestDatabaseRepository;
+
+pub struct TestPlatform {
+    profile: HandsetProfile,
+    database_repository: TestDatabaseRepository,
+    now: Rc<Cell<u64>>,
+}
+
+impl TestPlatform {
+    pub fn new() -> Self {
+        Self {
+            profile: HandsetProfile::default(),
+            database_repository: TestDatabaseRepository::default(),
+            now: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Current time in epoch millis, which stays where tests set it
+    pub fn clock(&self) -> Rc<Cell<u64>> {
+        self.now.clone()
+    }
+}
+
+impl Default for TestPlatform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Platform for TestPlatform {
     fn screen(&mut self) -> &mut dyn wie_backend::Screen {
         todo!()
     }
 
+    // time doesn't pass in tests unless the clock is moved
     fn now(&self) -> wie_backend::Instant {
-        todo!()
+        wie_backend::Instant::from_epoch_millis(self.now.get())
     }
 
     fn database_repository(&self) -> &dyn wie_backend::DatabaseRepository {
-        todo!()
+        &self.database_repository
     }
 
     fn audio_sink(&self) -> Box<dyn AudioSink> {
         Box::new(TestAudioSink)
     }
+
+    fn profile(&self) -> &HandsetProfile {
+        &self.profile
+    }
 }
 
 struct TestAudioSink;
@@ -1,14 +1,58 @@
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display};
+
 pub type RecordId = u32;
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DatabaseError {
+    NotFound,
+    Full,
+    ReadOnly,
+    /// Name contains path separators or is otherwise unusable as a database name
+    InvalidName,
+    Io(String),
+}
+
+impl Display for DatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Not found"),
+            Self::Full => write!(f, "Storage full"),
+            Self::ReadOnly => write!(f, "Read only"),
+            Self::InvalidName => write!(f, "Invalid name"),
+            Self::Io(x) => write!(f, "I/O error: {}", x),
+        }
+    }
+}
+
+impl std::error::Error for DatabaseError {}
+
+impl From<std::io::Error> for DatabaseError {
+    fn from(err: std::io::Error) -> Self {
+        use std::io::ErrorKind;
+
+        match err.kind() {
+            ErrorKind::NotFound => Self::NotFound,
+            ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem => Self::ReadOnly,
+            ErrorKind::StorageFull | ErrorKind::QuotaExceeded | ErrorKind::FileTooLarge => Self::Full,
+            _ => Self::Io(err.to_string()),
+        }
+    }
+}
+
+pub type DatabaseResult<T> = Result<T, DatabaseError>;
+
 pub trait Database {
-    fn add(&mut self, data: &[u8]) -> RecordId;
-    fn get(&self, id: RecordId) -> Option<Vec<u8>>;
-    fn set(&mut self, id: RecordId, data: &[u8]) -> bool;
-    fn delete(&mut self, id: RecordId) -> bool;
+    fn add(&mut self, data: &[u8]) -> DatabaseResult<RecordId>;
+    fn get(&self, id: RecordId) -> DatabaseResult<Vec<u8>>;
+    fn set(&mut self, id: RecordId, data: &[u8]) -> DatabaseResult<()>;
+    fn delete(&mut self, id: RecordId) -> DatabaseResult<()>;
 
-    fn get_record_ids(&self) -> Vec<RecordId>;
+    fn get_record_ids(&self) -> DatabaseResult<Vec<RecordId>>;
 }
 
 pub trait DatabaseRepository {
-    fn open(&self, name: &str) -> Box<dyn Database>;
+    fn open(&self, name: &str, create: bool) -> DatabaseResult<Box<dyn Database>>;
+    fn delete(&self, name: &str) -> DatabaseResult<()>;
+    fn list(&self) -> DatabaseResult<Vec<String>>;
 }
@@ -0,0 +1,6 @@
+/// Byte stream to a remote debugger, e.g. gdb over tcp
+pub trait DebugConnection {
+    /// Reads bytes which already arrived without blocking, returns 0 if there is nothing to read
+    fn read(&mut self, buf: &mut [u8]) -> anyhow::Result<usize>;
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()>;
+}
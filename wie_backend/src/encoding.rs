@@ -0,0 +1,9 @@
+pub use encoding_rs::{Encoding, EUC_KR, UTF_8, WINDOWS_1252};
+
+pub fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    match label.trim().to_ascii_lowercase().as_str() {
+        // encoding_rs's EUC-KR is the whatwg one, which already includes cp949 (unified hangul code) extensions
+        "cp949" | "ms949" | "uhc" => Some(EUC_KR),
+        x => Encoding::for_label(x.as_bytes()),
+    }
+}
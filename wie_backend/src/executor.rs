@@ -1,24 +1,61 @@
-use alloc::rc::Rc;
+use alloc::{
+    rc::Rc,
+    string::{String, ToString},
+    sync::Arc,
+    task::Wake,
+    vec::Vec,
+};
 use core::{
     cell::RefCell,
-    fmt::Debug,
+    fmt::{self, Debug, Display, Formatter},
     future::Future,
     pin::Pin,
-    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    task::{Context, Poll, Waker},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
 };
-use std::collections::HashMap;
 
-use crate::time::Instant;
+use crate::{sync::Notify, time::Instant};
 
 type Task = Pin<Box<dyn Future<Output = anyhow::Result<()>>>>;
 
+struct TaskInfo {
+    name: Option<String>,
+    // notified when the task completes or is aborted
+    finished: Notify,
+}
+
 pub struct ExecutorInner {
     current_task_id: Option<usize>,
     tasks: HashMap<usize, Task>,
+    // every live task, including the ones taken out of `tasks` while being polled
+    task_infos: HashMap<usize, TaskInfo>,
+    // tasks aborted while `step` holds them
+    aborted_tasks: HashSet<usize>,
     sleeping_tasks: HashMap<usize, Instant>,
+    // tasks which returned pending without sleeping, they are polled again after being woken
+    waiting_tasks: HashSet<usize>,
+    woken_tasks: Arc<Mutex<HashSet<usize>>>,
     last_task_id: usize,
 }
 
+struct TaskWaker {
+    task_id: usize,
+    woken_tasks: Arc<Mutex<HashSet<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken_tasks.lock().unwrap().insert(self.task_id);
+    }
+}
+
 #[async_trait::async_trait(?Send)]
 pub trait AsyncCallable<R, E> {
     async fn call(self) -> Result<R, E>;
@@ -36,7 +73,8 @@ where
     }
 }
 
-// Executor polling every future until it is ready to implement generator using async ecosystem
+// Executor polling futures until they are ready to implement generator using async ecosystem.
+// Pending task is polled again when its sleep ends or its waker is woken.
 #[derive(Clone)]
 pub struct Executor {
     inner: Rc<RefCell<ExecutorInner>>,
@@ -48,20 +86,29 @@ impl Executor {
         let inner = Rc::new(RefCell::new(ExecutorInner {
             current_task_id: None,
             tasks: HashMap::new(),
+            task_infos: HashMap::new(),
+            aborted_tasks: HashSet::new(),
             sleeping_tasks: HashMap::new(),
+            waiting_tasks: HashSet::new(),
+            woken_tasks: Arc::new(Mutex::new(HashSet::new())),
             last_task_id: 0,
         }));
 
         Self { inner }
     }
 
-    pub fn spawn<C, R, E>(&mut self, callable: C) -> usize
+    pub fn spawn<C, R, E>(&mut self, name: Option<&str>, callable: C) -> JoinHandle<R>
     where
         C: AsyncCallable<R, E> + 'static,
+        R: 'static,
         E: Debug,
     {
+        let result = Rc::new(RefCell::new(None));
+
+        let result_cloned = result.clone();
         let fut = async move {
-            callable.call().await.map_err(|x| anyhow::anyhow!("{:?}", x))?;
+            let value = callable.call().await.map_err(|x| anyhow::anyhow!("{:?}", x))?;
+            result_cloned.borrow_mut().replace(value);
 
             anyhow::Ok(())
         };
@@ -72,9 +119,80 @@ impl Executor {
             inner.last_task_id
         };
 
-        self.inner.borrow_mut().tasks.insert(task_id, Box::pin(fut));
+        let info = TaskInfo {
+            name: name.map(|x| x.to_string()),
+            finished: Notify::new(),
+        };
+
+        let mut inner = self.inner.borrow_mut();
+        inner.task_infos.insert(task_id, info);
+        inner.tasks.insert(task_id, Box::pin(fut));
+
+        JoinHandle {
+            abort_handle: AbortHandle {
+                task_id,
+                executor: self.clone(),
+            },
+            result,
+        }
+    }
+
+    /// Drops the task, it won't be polled again. Aborting a finished task does nothing.
+    pub fn abort(&mut self, task_id: usize) {
+        let (info, task) = {
+            let mut inner = self.inner.borrow_mut();
+            let info = if let Some(x) = inner.task_infos.remove(&task_id) {
+                x
+            } else {
+                return;
+            };
+
+            inner.sleeping_tasks.remove(&task_id);
+            inner.waiting_tasks.remove(&task_id);
+            inner.aborted_tasks.insert(task_id);
+
+            (info, inner.tasks.remove(&task_id))
+        };
+
+        tracing::debug!("Aborting task #{}", task_id);
+
+        // task may hold handles to us, so drop it after releasing the borrow
+        drop(task);
+        info.finished.notify();
+    }
+
+    pub fn is_finished(&self, task_id: usize) -> bool {
+        !self.inner.borrow().task_infos.contains_key(&task_id)
+    }
+
+    /// Lists live tasks, for debugging
+    pub fn task_list(&self) -> Vec<TaskStatus> {
+        let inner = self.inner.borrow();
+
+        let mut result = inner
+            .task_infos
+            .iter()
+            .map(|(&id, info)| {
+                let state = if inner.current_task_id == Some(id) {
+                    TaskState::Running
+                } else if let Some(x) = inner.sleeping_tasks.get(&id) {
+                    TaskState::Sleeping(*x)
+                } else if inner.waiting_tasks.contains(&id) {
+                    TaskState::Waiting
+                } else {
+                    TaskState::Ready
+                };
+
+                TaskStatus {
+                    id,
+                    name: info.name.clone(),
+                    state,
+                }
+            })
+            .collect::<Vec<_>>();
+        result.sort_by_key(|x| x.id);
 
-        task_id
+        result
     }
 
     pub fn tick<T>(&mut self, now: T) -> anyhow::Result<()>
@@ -85,30 +203,61 @@ impl Executor {
         loop {
             let now = now();
 
-            if now > end {
+            if now > end || !self.has_runnable_task(now) {
                 break;
             }
 
-            let running_task_count = self.inner.borrow().tasks.len() - self.inner.borrow().sleeping_tasks.len();
-            if running_task_count == 0 && !self.inner.borrow().sleeping_tasks.is_empty() {
-                let next_wakeup = *self.inner.borrow().sleeping_tasks.values().min().unwrap();
-                if now < next_wakeup {
-                    break;
-                }
-            }
-
             self.step(now)?;
         }
 
         Ok(())
     }
 
+    /// Earliest time a task can be polled again, `None` if every task waits for a waker
+    pub fn next_wakeup(&self, now: Instant) -> Option<Instant> {
+        if self.has_runnable_task(now) {
+            return Some(now);
+        }
+
+        self.inner.borrow().sleeping_tasks.values().min().copied()
+    }
+
+    fn has_runnable_task(&self, now: Instant) -> bool {
+        let mut inner = self.inner.borrow_mut();
+
+        let woken_tasks = inner.woken_tasks.lock().unwrap().drain().collect::<Vec<_>>();
+        for task_id in woken_tasks {
+            inner.waiting_tasks.remove(&task_id);
+        }
+
+        inner
+            .tasks
+            .keys()
+            .any(|x| !inner.waiting_tasks.contains(x) && inner.sleeping_tasks.get(x).map_or(true, |x| *x <= now))
+    }
+
     fn step(&mut self, now: Instant) -> anyhow::Result<()> {
         let mut next_tasks = HashMap::new();
         let tasks = self.inner.borrow_mut().tasks.drain().collect::<HashMap<_, _>>();
         let mut sleeping_tasks = self.inner.borrow_mut().sleeping_tasks.drain().collect::<HashMap<_, _>>();
+        let mut error = None;
 
         for (task_id, mut task) in tasks.into_iter() {
+            // keep the remaining tasks for the crash dump
+            if error.is_some() {
+                next_tasks.insert(task_id, task);
+                continue;
+            }
+
+            if self.inner.borrow().aborted_tasks.contains(&task_id) {
+                continue;
+            }
+
+            if self.inner.borrow().waiting_tasks.contains(&task_id) {
+                next_tasks.insert(task_id, task);
+                continue;
+            }
+
             let item = sleeping_tasks.get(&task_id);
             if let Some(item) = item {
                 if *item <= now {
@@ -119,26 +268,50 @@ impl Executor {
                 }
             }
 
-            let waker = self.create_waker();
+            let waker = self.create_waker(task_id);
             let mut context = Context::from_waker(&waker);
             self.inner.borrow_mut().current_task_id = Some(task_id);
 
-            match task.as_mut().poll(&mut context) {
+            let result = task.as_mut().poll(&mut context);
+            self.inner.borrow_mut().current_task_id = None;
+
+            if self.inner.borrow().aborted_tasks.contains(&task_id) {
+                continue;
+            }
+
+            match result {
                 Poll::Ready(x) => {
-                    x?;
+                    let info = self.inner.borrow_mut().task_infos.remove(&task_id);
+                    if let Some(info) = info {
+                        info.finished.notify();
+                    }
+
+                    if let Err(x) = x {
+                        error = Some(x);
+                    }
                 }
                 Poll::Pending => {
+                    // task which went to sleep is woken by time, others by waker
+                    let mut inner = self.inner.borrow_mut();
+                    if !inner.sleeping_tasks.contains_key(&task_id) {
+                        inner.waiting_tasks.insert(task_id);
+                    }
+
                     next_tasks.insert(task_id, task);
                 }
             }
-
-            self.inner.borrow_mut().current_task_id = None;
         }
 
-        self.inner.borrow_mut().sleeping_tasks.extend(sleeping_tasks);
-        self.inner.borrow_mut().tasks.extend(next_tasks);
+        let mut inner = self.inner.borrow_mut();
+        inner.sleeping_tasks.extend(sleeping_tasks);
+        inner.tasks.extend(next_tasks);
 
-        Ok(())
+        // aborted task may have gone to sleep before returning
+        let aborted_tasks = core::mem::take(&mut inner.aborted_tasks);
+        inner.tasks.retain(|x, _| !aborted_tasks.contains(x));
+        inner.sleeping_tasks.retain(|x, _| !aborted_tasks.contains(x));
+
+        error.map_or(Ok(()), Err)
     }
 
     pub(crate) fn sleep(&mut self, until: Instant) {
@@ -147,19 +320,303 @@ impl Executor {
         self.inner.borrow_mut().sleeping_tasks.insert(task_id, until);
     }
 
-    fn create_waker(&self) -> Waker {
-        unsafe fn noop_clone(_data: *const ()) -> RawWaker {
-            noop_raw_waker()
+    fn create_waker(&self, task_id: usize) -> Waker {
+        let woken_tasks = self.inner.borrow().woken_tasks.clone();
+
+        Waker::from(Arc::new(TaskWaker { task_id, woken_tasks }))
+    }
+
+    fn finished_notify(&self, task_id: usize) -> Option<Notify> {
+        self.inner.borrow().task_infos.get(&task_id).map(|x| x.finished.clone())
+    }
+}
+
+/// Aborts a spawned task, can be cloned and stored apart from its `JoinHandle`
+#[derive(Clone)]
+pub struct AbortHandle {
+    task_id: usize,
+    executor: Executor,
+}
+
+impl AbortHandle {
+    pub fn id(&self) -> usize {
+        self.task_id
+    }
+
+    pub fn abort(&self) {
+        self.executor.clone().abort(self.task_id)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.executor.is_finished(self.task_id)
+    }
+}
+
+/// Handle to a spawned task, dropping it detaches the task
+pub struct JoinHandle<R> {
+    abort_handle: AbortHandle,
+    result: Rc<RefCell<Option<R>>>,
+}
+
+impl<R> JoinHandle<R> {
+    pub fn id(&self) -> usize {
+        self.abort_handle.task_id
+    }
+
+    pub fn abort(&self) {
+        self.abort_handle.abort()
+    }
+
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.abort_handle.clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.abort_handle.is_finished()
+    }
+
+    /// Waits until the task completes, returns `None` if it was aborted
+    pub async fn join(self) -> Option<R> {
+        while let Some(finished) = self.abort_handle.executor.finished_notify(self.abort_handle.task_id) {
+            finished.notified().await;
         }
 
-        unsafe fn noop(_data: *const ()) {}
+        self.result.borrow_mut().take()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TaskState {
+    Running,
+    Ready,
+    Sleeping(Instant),
+    Waiting,
+}
+
+#[derive(Clone, Debug)]
+pub struct TaskStatus {
+    pub id: usize,
+    pub name: Option<String>,
+    pub state: TaskState,
+}
 
-        const NOOP_WAKER_VTABLE: RawWakerVTable = RawWakerVTable::new(noop_clone, noop, noop, noop);
+impl Display for TaskStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} {}: ", self.id, self.name.as_deref().unwrap_or("<unnamed>"))?;
 
-        const fn noop_raw_waker() -> RawWaker {
-            RawWaker::new(core::ptr::null(), &NOOP_WAKER_VTABLE)
+        match self.state {
+            TaskState::Running => write!(f, "running"),
+            TaskState::Ready => write!(f, "ready"),
+            TaskState::Sleeping(x) => write!(f, "sleeping until {}", x.raw()),
+            TaskState::Waiting => write!(f, "waiting"),
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{rc::Rc, vec::Vec};
+    use core::cell::{Cell, RefCell};
+
+    use crate::{
+        sync::Notify,
+        task::{SleepFuture, YieldFuture},
+        time::Instant,
+    };
+
+    use super::{AbortHandle, Executor, TaskState};
+
+    #[test]
+    fn test_waker_repolls_task() -> anyhow::Result<()> {
+        let mut executor = Executor::new();
+        let now = Instant::from_epoch_millis(1000);
+
+        let notify = Notify::new();
+        let polled = Rc::new(Cell::new(0));
+
+        let notified = notify.notified();
+        let polled_cloned = polled.clone();
+        let handle = executor.spawn(None, move || async move {
+            polled_cloned.set(polled_cloned.get() + 1);
+            notified.await;
+            polled_cloned.set(polled_cloned.get() + 1);
+
+            anyhow::Ok(())
+        });
+
+        executor.step(now)?;
+        assert_eq!(polled.get(), 1);
+        assert_eq!(executor.task_list()[0].state, TaskState::Waiting);
+        assert_eq!(executor.next_wakeup(now), None);
+
+        // not polled again until woken
+        executor.tick(|| now)?;
+        assert_eq!(polled.get(), 1);
+
+        notify.notify();
+        assert_eq!(executor.next_wakeup(now), Some(now));
+
+        executor.tick(|| now)?;
+        assert_eq!(polled.get(), 2);
+        assert!(handle.is_finished());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_yield_now() -> anyhow::Result<()> {
+        let mut executor = Executor::new();
+        let now = Instant::from_epoch_millis(1000);
+
+        let polled = Rc::new(Cell::new(0));
+
+        let polled_cloned = polled.clone();
+        let handle = executor.spawn(None, move || async move {
+            for _ in 0..3 {
+                polled_cloned.set(polled_cloned.get() + 1);
+                YieldFuture::default().await;
+            }
+
+            anyhow::Ok(())
+        });
+
+        // yielding task is ready again right away, but polled once per step
+        executor.step(now)?;
+        assert_eq!(polled.get(), 1);
+        assert_eq!(executor.next_wakeup(now), Some(now));
+        assert_eq!(executor.task_list()[0].state, TaskState::Ready);
+
+        executor.step(now)?;
+        assert_eq!(polled.get(), 2);
+
+        executor.tick(|| now)?;
+        assert_eq!(polled.get(), 3);
+        assert!(handle.is_finished());
+        assert_eq!(executor.next_wakeup(now), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_next_wakeup_sleeping() -> anyhow::Result<()> {
+        let mut executor = Executor::new();
+        let now = Instant::from_epoch_millis(1000);
 
-        unsafe { Waker::from_raw(noop_raw_waker()) }
+        let mut executor_cloned = executor.clone();
+        executor.spawn(None, move || async move {
+            SleepFuture::new(now + 100, &mut executor_cloned).await;
+
+            anyhow::Ok(())
+        });
+
+        executor.step(now)?;
+        assert_eq!(executor.next_wakeup(now), Some(now + 100));
+
+        executor.tick(|| now + 100)?;
+        assert_eq!(executor.next_wakeup(now + 100), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_abort_sleeping_task() -> anyhow::Result<()> {
+        let mut executor = Executor::new();
+        let now = Instant::from_epoch_millis(1000);
+
+        let woke = Rc::new(Cell::new(false));
+
+        let woke_cloned = woke.clone();
+        let mut executor_cloned = executor.clone();
+        let handle = executor.spawn(Some("sleeper"), move || async move {
+            SleepFuture::new(now + 100, &mut executor_cloned).await;
+            woke_cloned.set(true);
+
+            anyhow::Ok(())
+        });
+
+        executor.step(now)?;
+        let tasks = executor.task_list();
+        assert_eq!(tasks[0].name.as_deref(), Some("sleeper"));
+        assert_eq!(tasks[0].state, TaskState::Sleeping(now + 100));
+
+        handle.abort();
+        assert!(handle.is_finished());
+        assert!(executor.task_list().is_empty());
+
+        executor.tick(|| now + 100)?;
+        assert!(!woke.get());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_abort_running_task() -> anyhow::Result<()> {
+        let mut executor = Executor::new();
+        let now = Instant::from_epoch_millis(1000);
+
+        let abort_handle = Rc::new(RefCell::new(None::<AbortHandle>));
+        let polled = Rc::new(Cell::new(0));
+        let state = Rc::new(Cell::new(None));
+
+        let (abort_handle_cloned, polled_cloned, state_cloned) = (abort_handle.clone(), polled.clone(), state.clone());
+        let executor_cloned = executor.clone();
+        let handle = executor.spawn(None, move || async move {
+            polled_cloned.set(polled_cloned.get() + 1);
+            state_cloned.set(Some(executor_cloned.task_list()[0].state));
+
+            // aborting itself, the task is dropped when it returns to the executor
+            abort_handle_cloned.borrow().as_ref().unwrap().abort();
+            YieldFuture::default().await;
+            polled_cloned.set(polled_cloned.get() + 1);
+
+            anyhow::Ok(())
+        });
+        abort_handle.replace(Some(handle.abort_handle()));
+
+        executor.tick(|| now)?;
+
+        assert_eq!(state.get(), Some(TaskState::Running));
+        assert_eq!(polled.get(), 1);
+        assert!(handle.is_finished());
+        assert!(executor.task_list().is_empty());
+        assert_eq!(executor.next_wakeup(now), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_join() -> anyhow::Result<()> {
+        let mut executor = Executor::new();
+        let now = Instant::from_epoch_millis(1000);
+
+        let mut executor_cloned = executor.clone();
+        let sleeper = executor.spawn(None, move || async move {
+            SleepFuture::new(now + 100, &mut executor_cloned).await;
+
+            anyhow::Ok(1)
+        });
+        let sleeper_abort_handle = sleeper.abort_handle();
+        let returner = executor.spawn(None, move || async move { anyhow::Ok(2) });
+
+        let results = Rc::new(RefCell::new(Vec::new()));
+
+        let results_cloned = results.clone();
+        executor.spawn(None, move || async move {
+            results_cloned.borrow_mut().push(returner.join().await);
+            results_cloned.borrow_mut().push(sleeper.join().await);
+
+            anyhow::Ok(())
+        });
+
+        executor.tick(|| now)?;
+        assert_eq!(*results.borrow(), [Some(2)]);
+
+        // join after abort returns None
+        sleeper_abort_handle.abort();
+        executor.tick(|| now)?;
+        assert_eq!(*results.borrow(), [Some(2), None]);
+        assert!(executor.task_list().is_empty());
+
+        Ok(())
     }
 }
@@ -3,19 +3,30 @@ extern crate alloc;
 mod audio_sink;
 pub mod canvas;
 mod database;
+mod debug_connection;
+pub mod encoding;
 mod executor;
+mod loader;
+mod metadata;
 mod platform;
+mod profile;
 mod screen;
+mod sync;
 mod system;
 mod task;
 mod time;
 
 pub use self::{
     audio_sink::AudioSink,
-    database::{Database, DatabaseRepository, RecordId},
-    executor::AsyncCallable,
-    platform::Platform,
+    database::{Database, DatabaseError, DatabaseRepository, DatabaseResult, RecordId},
+    debug_connection::DebugConnection,
+    executor::{AbortHandle, AsyncCallable, JoinHandle, TaskState, TaskStatus},
+    loader::{load_archive, ArchiveFiles, ArchiveLoader},
+    metadata::{ArchiveMetadata, ArchivePlatform},
+    platform::{NativeEngine, Platform},
+    profile::HandsetProfile,
     screen::Screen,
+    sync::{Channel, Notified, Notify},
     system::{System, SystemHandle},
     time::Instant,
 };
@@ -26,12 +37,25 @@ pub trait App {
     fn start(&mut self) -> anyhow::Result<()>;
     fn on_event(&mut self, event: wie_common::Event);
     fn tick(&mut self) -> anyhow::Result<()>;
+    /// Time `tick` should be called next if no event arrives, `None` to wait for the next event
+    fn next_wakeup(&self) -> Option<Instant>;
+    /// True if the app asked to exit, or finished handling `Event::Exit`
+    fn exit_requested(&self) -> bool;
     fn crash_dump(&self) -> String;
+    /// Heap usage and allocations still live, for apps with an emulated heap
+    fn leak_report(&self) -> Option<String> {
+        None
+    }
 }
 
 pub trait Archive {
-    fn id(&self) -> String;
+    fn metadata(&self) -> &ArchiveMetadata;
+    fn metadata_mut(&mut self) -> &mut ArchiveMetadata;
     fn load_app(self: Box<Self>, platform: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>>;
+
+    fn id(&self) -> String {
+        self.metadata().id.clone()
+    }
 }
 
 pub fn extract_zip(zip: &[u8]) -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
@@ -51,3 +75,37 @@ pub fn extract_zip(zip: &[u8]) -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
         })
         .collect::<anyhow::Result<_>>()
 }
+
+// entries are stored without compression
+pub fn create_zip(files: &BTreeMap<String, Vec<u8>>) -> anyhow::Result<Vec<u8>> {
+    use std::io::{Cursor, Write};
+    use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+
+    for (name, data) in files {
+        writer.start_file(name, options)?;
+        writer.write_all(data)?;
+    }
+
+    Ok(writer.finish()?.into_inner())
+}
+
+pub fn read_zip_file(zip: &[u8], name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    use std::io::{Cursor, Read};
+    use zip::{result::ZipError, ZipArchive};
+
+    let mut archive = ZipArchive::new(Cursor::new(zip))?;
+
+    let mut file = match archive.by_name(name) {
+        Ok(x) => x,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(x) => return Err(x.into()),
+    };
+
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+
+    Ok(Some(data))
+}
@@ -0,0 +1,66 @@
+use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec::Vec};
+
+use crate::{extract_zip, Archive};
+
+pub type ArchiveFiles = BTreeMap<String, Vec<u8>>;
+
+pub trait ArchiveLoader {
+    fn name(&self) -> &'static str;
+    /// Describes what `probe` looks for, used in diagnostics
+    fn description(&self) -> &'static str;
+    fn probe(&self, files: &ArchiveFiles) -> bool;
+    fn load(&self, files: ArchiveFiles) -> anyhow::Result<Box<dyn Archive>>;
+}
+
+// if nothing matches, zip files inside `files` are probed too, as apps are often distributed wrapped in another zip
+pub fn load_archive(loaders: &[&dyn ArchiveLoader], files: ArchiveFiles) -> anyhow::Result<Box<dyn Archive>> {
+    let matched = loaders.iter().filter(|x| x.probe(&files)).collect::<Vec<_>>();
+
+    match matched.as_slice() {
+        [loader] => {
+            tracing::info!("Loading {} archive", loader.name());
+
+            return loader.load(files);
+        }
+        [] => {}
+        _ => {
+            let names = matched.iter().map(|x| x.name()).collect::<Vec<_>>();
+
+            anyhow::bail!(
+                "Ambiguous archive, it matches multiple formats: {}\nFiles: {}",
+                names.join(", "),
+                file_list(&files)
+            );
+        }
+    }
+
+    let nested_zips = files.iter().filter(|x| x.0.to_ascii_lowercase().ends_with(".zip")).collect::<Vec<_>>();
+    match nested_zips.as_slice() {
+        [(name, data)] => {
+            tracing::info!("Probing nested archive {}", name);
+
+            return load_archive(loaders, extract_zip(data)?);
+        }
+        [] => {}
+        _ => {
+            let names = nested_zips.iter().map(|x| x.0.as_str()).collect::<Vec<_>>();
+
+            anyhow::bail!("Archive contains multiple nested archives, extract the one to run: {}", names.join(", "));
+        }
+    }
+
+    let tried = loaders.iter().map(|x| format!("  {}: {}", x.name(), x.description())).collect::<Vec<_>>();
+
+    anyhow::bail!("Unrecognized archive format, tried:\n{}\nFiles: {}", tried.join("\n"), file_list(&files))
+}
+
+fn file_list(files: &ArchiveFiles) -> String {
+    const MAX_FILES: usize = 20;
+
+    let mut names = files.keys().take(MAX_FILES).map(|x| x.as_str()).collect::<Vec<_>>();
+    if files.len() > MAX_FILES {
+        names.push("...");
+    }
+
+    names.join(", ")
+}
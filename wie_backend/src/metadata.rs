@@ -0,0 +1,217 @@
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Display, Formatter};
+
+use encoding_rs::Encoding;
+
+use crate::HandsetProfile;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArchivePlatform {
+    Ktf,
+    Lgt,
+    Skt,
+    J2me,
+}
+
+impl Display for ArchivePlatform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Ktf => "KTF",
+            Self::Lgt => "LGT",
+            Self::Skt => "SKT",
+            Self::J2me => "J2ME",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArchiveMetadata {
+    pub id: String,
+    pub platform: ArchivePlatform,
+    pub main_class: String,
+    pub name: Option<String>,
+    pub vendor: Option<String>,
+    pub version: Option<String>,
+    /// Raw image file data, as stored in the archive
+    pub icon: Option<Vec<u8>>,
+    pub heap_size: Option<u32>,
+    pub screen_size: Option<(u32, u32)>,
+    /// All key/value pairs of the archive's descriptor, decoded with the platform's encoding
+    pub properties: BTreeMap<String, String>,
+    /// Raw descriptor, empty if the archive has none
+    pub descriptor: Vec<u8>,
+    /// Encoding of the app's strings, overriding the profile's
+    pub encoding: Option<&'static Encoding>,
+}
+
+impl ArchiveMetadata {
+    pub fn new(id: &str, main_class: &str, platform: ArchivePlatform) -> Self {
+        Self {
+            id: id.into(),
+            platform,
+            main_class: main_class.into(),
+            name: None,
+            vendor: None,
+            version: None,
+            icon: None,
+            heap_size: None,
+            screen_size: None,
+            properties: BTreeMap::new(),
+            descriptor: Vec::new(),
+            encoding: None,
+        }
+    }
+
+    // adf, app_info, msd, jad and jar manifest are all lists of `key: value` lines.
+    pub fn parse_properties(data: &[u8], encoding: &'static Encoding) -> BTreeMap<String, String> {
+        // bom takes precedence over `encoding`
+        let data = encoding.decode(data).0;
+
+        let mut result = BTreeMap::new();
+        for line in data.lines() {
+            let (key, value) = if let Some(x) = line.split_once(':') {
+                x
+            } else {
+                continue;
+            };
+
+            let key = key.trim();
+            if key.is_empty() {
+                continue;
+            }
+
+            result.insert(key.to_string(), value.trim().to_string());
+        }
+
+        result
+    }
+
+    /// Parses jad or jar manifest, where a line starting with a space continues the value of the previous line
+    pub fn parse_manifest(data: &[u8], encoding: &'static Encoding) -> BTreeMap<String, String> {
+        // wrapping may split a multibyte character, so lines are joined before decoding.
+        Self::parse_properties(&Self::join_continuation_lines(data), encoding)
+    }
+
+    /// Removes line breaks followed by a space, so wrapped values become single lines
+    pub fn join_continuation_lines(data: &[u8]) -> Vec<u8> {
+        let mut result = Vec::with_capacity(data.len());
+
+        let mut i = 0;
+        while i < data.len() {
+            let line_break = match &data[i..] {
+                [b'\r', b'\n', b' ', ..] => 3,
+                [b'\r' | b'\n', b' ', ..] => 2,
+                _ => 0,
+            };
+
+            if line_break != 0 {
+                i += line_break;
+            } else {
+                result.push(data[i]);
+                i += 1;
+            }
+        }
+
+        result
+    }
+
+    /// Returns the value of the first key in `keys` which exists in properties
+    pub fn property(&self, keys: &[&str]) -> Option<&str> {
+        keys.iter().find_map(|x| self.properties.get(*x)).map(|x| x.as_str())
+    }
+
+    /// Encoding of the app's strings: the archive's override, then the profile's, then `default`
+    pub fn app_encoding(&self, profile: &HandsetProfile, default: &'static Encoding) -> &'static Encoding {
+        self.encoding.unwrap_or_else(|| profile.encoding(default))
+    }
+
+    /// Properties given to the app. The descriptor is decoded again, as `encoding` may differ from the one used when the archive was loaded
+    pub fn app_properties(&self, encoding: &'static Encoding) -> BTreeMap<String, String> {
+        if self.descriptor.is_empty() {
+            return self.properties.clone();
+        }
+
+        if self.platform == ArchivePlatform::J2me {
+            Self::parse_manifest(&self.descriptor, encoding)
+        } else {
+            Self::parse_properties(&self.descriptor, encoding)
+        }
+    }
+
+    /// Heap size for native (KTF, LGT) apps, the archive may ask for more than the profile has
+    pub fn native_heap_size(&self, profile: &HandsetProfile) -> u32 {
+        self.heap_size.unwrap_or(0).max(profile.heap_size)
+    }
+
+    /// Fills name, vendor, version, heap size and screen size from properties, using the first existing key of each list
+    pub fn fill_from_properties(&mut self, name: &[&str], vendor: &[&str], version: &[&str], heap_size: &[&str], screen_size: &[&str]) {
+        self.name = self.property(name).map(|x| x.to_string());
+        self.vendor = self.property(vendor).map(|x| x.to_string());
+        self.version = self.property(version).map(|x| x.to_string());
+        self.heap_size = self.property(heap_size).and_then(parse_size);
+        self.screen_size = self.property(screen_size).and_then(parse_dimension);
+    }
+}
+
+// `524288`, `512K`, `1M`
+fn parse_size(value: &str) -> Option<u32> {
+    let value = value.trim().to_ascii_uppercase();
+    let value = value.trim_end_matches('B');
+
+    let (number, multiplier) = if let Some(x) = value.strip_suffix('K') {
+        (x, 1024)
+    } else if let Some(x) = value.strip_suffix('M') {
+        (x, 1024 * 1024)
+    } else {
+        (value, 1)
+    };
+
+    number.trim().parse::<u32>().ok()?.checked_mul(multiplier)
+}
+
+// `240x320`, `240*320`, `240,320`
+fn parse_dimension(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.split_once(|x| matches!(x, 'x' | 'X' | '*' | ','))?;
+
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_dimension, parse_size};
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("524288"), Some(524288));
+        assert_eq!(parse_size(" 512K "), Some(512 * 1024));
+        assert_eq!(parse_size("512kb"), Some(512 * 1024));
+        assert_eq!(parse_size("1M"), Some(1024 * 1024));
+
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("K"), None);
+        assert_eq!(parse_size("-1"), None);
+        assert_eq!(parse_size("1.5M"), None);
+        assert_eq!(parse_size("large"), None);
+        assert_eq!(parse_size("8192M"), None); // overflows u32
+    }
+
+    #[test]
+    fn test_parse_dimension() {
+        assert_eq!(parse_dimension("240x320"), Some((240, 320)));
+        assert_eq!(parse_dimension("176*220"), Some((176, 220)));
+        assert_eq!(parse_dimension(" 240 , 320 "), Some((240, 320)));
+        assert_eq!(parse_dimension("240X320"), Some((240, 320)));
+
+        assert_eq!(parse_dimension(""), None);
+        assert_eq!(parse_dimension("240"), None);
+        assert_eq!(parse_dimension("240x"), None);
+        assert_eq!(parse_dimension("ax320"), None);
+        assert_eq!(parse_dimension("240x320x16"), None);
+    }
+}
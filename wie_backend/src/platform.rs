@@ -1,8 +1,56 @@
-use crate::{audio_sink::AudioSink, database::DatabaseRepository, screen::Screen, time::Instant};
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::{fmt, str::FromStr};
+
+use crate::{
+    audio_sink::AudioSink, database::DatabaseRepository, debug_connection::DebugConnection, profile::HandsetProfile, screen::Screen, time::Instant,
+};
+
+/// Cpu emulator running native (KTF, LGT) apps
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NativeEngine {
+    Armv4tEmu,
+    Unicorn,
+    /// Runs both engines side by side, failing at the first instruction where they diverge
+    Lockstep,
+}
+
+impl FromStr for NativeEngine {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "armv4t_emu" => Ok(Self::Armv4tEmu),
+            "unicorn" => Ok(Self::Unicorn),
+            "lockstep" => Ok(Self::Lockstep),
+            _ => anyhow::bail!("Unknown engine {}, expected armv4t_emu, unicorn or lockstep", s),
+        }
+    }
+}
 
 pub trait Platform {
     fn screen(&mut self) -> &mut dyn Screen;
     fn now(&self) -> Instant;
     fn database_repository(&self) -> &dyn DatabaseRepository;
     fn audio_sink(&self) -> Box<dyn AudioSink>;
+    fn profile(&self) -> &HandsetProfile;
+
+    /// Files which replace or add to the app's resources, keyed by resource path
+    fn resource_overlay(&self) -> BTreeMap<String, Vec<u8>> {
+        BTreeMap::new()
+    }
+
+    /// Debugger connection for the native core, taken once when the core is created
+    fn debug_connection(&mut self) -> Option<Box<dyn DebugConnection>> {
+        None
+    }
+
+    /// Output for disassembled instructions executed by the native core, taken once when the core is created
+    fn instruction_trace(&mut self) -> Option<Box<dyn fmt::Write>> {
+        None
+    }
+
+    /// Cpu engine for the native core, `None` for the one the core is built with by default
+    fn native_engine(&self) -> Option<NativeEngine> {
+        None
+    }
 }
@@ -0,0 +1,118 @@
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+};
+
+use encoding_rs::Encoding;
+use serde::Deserialize;
+
+use crate::encoding::encoding_for_label;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct HandsetProfile {
+    pub name: String,
+    pub screen_width: u32,
+    pub screen_height: u32,
+    pub color_depth: u32,
+    /// Bytes of heap for native (KTF, LGT) apps, the archive may ask for more
+    pub heap_size: u32,
+    /// Bytes of stack for each task of native apps
+    pub stack_size: u32,
+    pub model: String,
+    pub carrier: String,
+    pub min: String,
+    pub platform_version: String,
+    /// Milliseconds a key should be held down before it starts repeating
+    pub key_repeat_delay: u64,
+    /// Milliseconds between key repeat events, 0 disables key repeat
+    pub key_repeat_interval: u64,
+    /// Text encoding label (e.g. `euc-kr`, `cp949`, `utf-8`), overrides the default of the app's platform
+    pub encoding: Option<String>,
+    /// Additional system properties, takes precedence over the well-known ones
+    pub properties: BTreeMap<String, String>,
+}
+
+impl Default for HandsetProfile {
+    fn default() -> Self {
+        Self::preset("ktf").unwrap()
+    }
+}
+
+impl HandsetProfile {
+    pub const PRESETS: [&'static str; 5] = ["ktf", "ktf-176x220", "skt", "lgt", "j2me"];
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let profile = match name {
+            "ktf" => Self::new(name, (240, 320), 0x100000, "KTF_QVGA", "KTF", "1.2.1"),
+            "ktf-176x220" => Self::new(name, (176, 220), 0x80000, "KTF_QCIF", "KTF", "1.2.0"),
+            "skt" => Self::new(name, (240, 320), 0x100000, "SKT_QVGA", "SKT", "2.0.1"),
+            "lgt" => Self::new(name, (240, 320), 0x100000, "LGT_QVGA", "LGT", "1.2.1"),
+            "j2me" => Self {
+                color_depth: 24,
+                ..Self::new(name, (240, 320), 0x200000, "J2ME", "", "MIDP-2.0")
+            },
+            _ => return None,
+        };
+
+        Some(profile)
+    }
+
+    fn new(name: &str, (screen_width, screen_height): (u32, u32), heap_size: u32, model: &str, carrier: &str, platform_version: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            screen_width,
+            screen_height,
+            color_depth: 16,
+            heap_size,
+            stack_size: 0x4000,
+            model: model.to_string(),
+            carrier: carrier.to_string(),
+            min: "01012345678".to_string(),
+            platform_version: platform_version.to_string(),
+            key_repeat_delay: 500,
+            key_repeat_interval: 100,
+            encoding: None,
+            properties: BTreeMap::new(),
+        }
+    }
+
+    pub fn encoding(&self, default: &'static Encoding) -> &'static Encoding {
+        let label = if let Some(x) = &self.encoding {
+            x
+        } else {
+            return default;
+        };
+
+        encoding_for_label(label).unwrap_or_else(|| {
+            tracing::warn!("Unknown encoding {}, falling back to {}", label, default.name());
+
+            default
+        })
+    }
+
+    /// Bits per pixel of native apps' framebuffers, 16 (RGB565) up to 16 bit color depth and 32 (ARGB) above
+    pub fn framebuffer_bpp(&self) -> u32 {
+        if self.color_depth <= 16 {
+            16
+        } else {
+            32
+        }
+    }
+
+    pub fn property(&self, name: &str) -> Option<String> {
+        if let Some(x) = self.properties.get(name) {
+            return Some(x.clone());
+        }
+
+        let value = match name {
+            "MODEL" | "microedition.platform" => &self.model,
+            "CARRIER" => &self.carrier,
+            "MIN" | "PHONE_NUMBER" => &self.min,
+            "PLATFORM_VERSION" | "WIPI_VERSION" => &self.platform_version,
+            _ => return None,
+        };
+
+        Some(value.clone())
+    }
+}
@@ -0,0 +1,126 @@
+use alloc::{collections::VecDeque, rc::Rc, vec::Vec};
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+struct NotifyInner {
+    generation: u64,
+    wakers: Vec<Waker>,
+}
+
+/// Wakes every task waiting on `notified` when `notify` is called
+#[derive(Clone)]
+pub struct Notify {
+    inner: Rc<RefCell<NotifyInner>>,
+}
+
+impl Default for Notify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Notify {
+    pub fn new() -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(NotifyInner {
+                generation: 0,
+                wakers: Vec::new(),
+            })),
+        }
+    }
+
+    pub fn notify(&self) {
+        let wakers = {
+            let mut inner = self.inner.borrow_mut();
+            inner.generation += 1;
+
+            core::mem::take(&mut inner.wakers)
+        };
+
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    // notifications made after this call complete the returned future, even if it is not polled yet
+    pub fn notified(&self) -> Notified {
+        Notified {
+            inner: self.inner.clone(),
+            generation: self.inner.borrow().generation,
+        }
+    }
+}
+
+pub struct Notified {
+    inner: Rc<RefCell<NotifyInner>>,
+    generation: u64,
+}
+
+impl Future for Notified {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.generation != self.generation {
+            return Poll::Ready(());
+        }
+
+        inner.wakers.push(cx.waker().clone());
+
+        Poll::Pending
+    }
+}
+
+/// Unbounded queue, `recv` waits until an item is sent
+pub struct Channel<T> {
+    items: Rc<RefCell<VecDeque<T>>>,
+    notify: Notify,
+}
+
+impl<T> Clone for Channel<T> {
+    fn clone(&self) -> Self {
+        Self {
+            items: self.items.clone(),
+            notify: self.notify.clone(),
+        }
+    }
+}
+
+impl<T> Default for Channel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Channel<T> {
+    pub fn new() -> Self {
+        Self {
+            items: Rc::new(RefCell::new(VecDeque::new())),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn send(&self, item: T) {
+        self.items.borrow_mut().push_back(item);
+        self.notify.notify();
+    }
+
+    pub fn try_recv(&self) -> Option<T> {
+        self.items.borrow_mut().pop_front()
+    }
+
+    pub async fn recv(&self) -> T {
+        loop {
+            let notified = self.notify.notified();
+            if let Some(x) = self.try_recv() {
+                return x;
+            }
+
+            notified.await;
+        }
+    }
+}
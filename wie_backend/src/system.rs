@@ -3,15 +3,19 @@ mod event_queue;
 mod random;
 mod resource;
 
-use alloc::rc::Rc;
+use alloc::{collections::BTreeMap, format, rc::Rc, string::String, vec::Vec};
 use core::{
     any::Any,
     cell::{Ref, RefCell, RefMut},
     fmt::Debug,
 };
 
+use encoding_rs::Encoding;
+
+use wie_common::Event;
+
 use crate::{
-    executor::Executor,
+    executor::{Executor, JoinHandle, TaskStatus},
     platform::Platform,
     task::{SleepFuture, YieldFuture},
     AsyncCallable, Instant,
@@ -25,33 +29,57 @@ pub struct SystemInner {
     event_queue: EventQueue,
     audio: Audio,
     random: Random,
+    app_properties: BTreeMap<String, String>,
+    exit_requested: bool,
     context: Box<dyn Any>,
 }
 
 pub struct System {
     executor: Executor,
+    encoding: &'static Encoding,
     inner: Rc<RefCell<SystemInner>>,
 }
 
 impl System {
-    pub fn new(platform: Box<dyn Platform>, context: Box<dyn Any>) -> Self {
+    pub fn new(platform: Box<dyn Platform>, context: Box<dyn Any>, encoding: &'static Encoding) -> Self {
         let audio_sink = platform.audio_sink();
         let seed = 12341234; // TODO get seed from outside
 
+        let mut resource = Resource::new();
+        resource.mount_overlay(platform.resource_overlay());
+
+        let profile = platform.profile();
+        let event_queue = EventQueue::new(profile.key_repeat_delay, profile.key_repeat_interval);
+
         Self {
             executor: Executor::new(),
+            encoding,
             inner: Rc::new(RefCell::new(SystemInner {
                 platform,
-                resource: Resource::new(),
-                event_queue: EventQueue::new(),
+                resource,
+                event_queue,
                 audio: Audio::new(audio_sink),
                 random: Random::new(seed),
+                app_properties: BTreeMap::new(),
+                exit_requested: false,
                 context,
             })),
         }
     }
 
     pub fn tick(&mut self) -> anyhow::Result<()> {
+        // tasks left behind, like timers, must not run after the app stopped
+        if self.exit_requested() {
+            return Ok(());
+        }
+
+        {
+            let mut inner = self.inner.borrow_mut();
+            let now = inner.platform.now();
+
+            inner.event_queue.generate_key_repeat(now);
+        }
+
         let inner = self.inner.clone();
         self.executor.tick(move || {
             let inner = inner.borrow();
@@ -60,9 +88,40 @@ impl System {
         })
     }
 
+    pub fn exit_requested(&self) -> bool {
+        self.inner.borrow().exit_requested
+    }
+
+    /// Time `tick` should be called next if no event arrives, `None` to wait for the next event
+    pub fn next_wakeup(&self) -> Option<Instant> {
+        if self.exit_requested() {
+            return None;
+        }
+
+        let inner = self.inner.borrow();
+        let now = inner.platform.now();
+
+        let task_wakeup = self.executor.next_wakeup(now);
+        let key_repeat = inner.event_queue.next_key_repeat();
+
+        task_wakeup.into_iter().chain(key_repeat).min()
+    }
+
+    pub fn task_list(&self) -> Vec<TaskStatus> {
+        self.executor.task_list()
+    }
+
+    /// Task list formatted for crash dumps
+    pub fn dump_tasks(&self) -> String {
+        let tasks = self.task_list().iter().map(|x| format!("  {}", x)).collect::<Vec<_>>();
+
+        format!("Tasks:\n{}", tasks.join("\n"))
+    }
+
     pub fn handle(&self) -> SystemHandle {
         SystemHandle {
             executor: self.executor.clone(),
+            encoding: self.encoding,
             system_inner: self.inner.clone(),
         }
     }
@@ -71,16 +130,32 @@ impl System {
 #[derive(Clone)]
 pub struct SystemHandle {
     executor: Executor,
+    encoding: &'static Encoding,
     system_inner: Rc<RefCell<SystemInner>>,
 }
 
 impl SystemHandle {
-    pub fn spawn<C, R, E>(&mut self, callable: C)
+    pub fn spawn<C, R, E>(&mut self, callable: C) -> JoinHandle<R>
+    where
+        C: AsyncCallable<R, E> + 'static,
+        R: 'static,
+        E: Debug,
+    {
+        self.executor.spawn(None, callable)
+    }
+
+    /// Same as `spawn`, `name` is shown in the task list
+    pub fn spawn_named<C, R, E>(&mut self, name: &str, callable: C) -> JoinHandle<R>
     where
         C: AsyncCallable<R, E> + 'static,
+        R: 'static,
         E: Debug,
     {
-        self.executor.spawn(callable);
+        self.executor.spawn(Some(name), callable)
+    }
+
+    pub fn task_list(&self) -> Vec<TaskStatus> {
+        self.executor.task_list()
     }
 
     pub fn sleep(&mut self, until: Instant) -> SleepFuture {
@@ -88,20 +163,19 @@ impl SystemHandle {
     }
 
     pub fn yield_now(&self) -> YieldFuture {
-        YieldFuture {}
+        YieldFuture::default()
     }
 
-    // TODO add encoding configuration..
-    pub fn encode_str(&self, string: &str) -> Vec<u8> {
-        use encoding_rs::EUC_KR;
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
 
-        EUC_KR.encode(string).0.to_vec()
+    pub fn encode_str(&self, string: &str) -> Vec<u8> {
+        self.encoding.encode(string).0.to_vec()
     }
 
     pub fn decode_str(&self, bytes: &[u8]) -> String {
-        use encoding_rs::EUC_KR;
-
-        EUC_KR.decode(bytes).0.to_string()
+        self.encoding.decode(bytes).0.to_string()
     }
 
     pub fn resource(&self) -> Ref<'_, Resource> {
@@ -124,10 +198,47 @@ impl SystemHandle {
         RefMut::map(self.system_inner.borrow_mut(), |s| &mut s.event_queue)
     }
 
+    /// Queues `event` as happening now
+    pub fn push_event(&self, event: Event) {
+        let now = self.platform().now();
+
+        self.event_queue().push(event, now);
+    }
+
+    /// Waits until an event arrives, without polling
+    pub async fn next_event(&self) -> Event {
+        loop {
+            let notified = {
+                let mut event_queue = self.event_queue();
+                if let Some(x) = event_queue.pop() {
+                    return x;
+                }
+
+                event_queue.notified()
+            };
+
+            notified.await;
+        }
+    }
+
     pub fn random(&self) -> RefMut<'_, Random> {
         RefMut::map(self.system_inner.borrow_mut(), |s| &mut s.random)
     }
 
+    /// Properties from the app's descriptor (adf, msd, jad or manifest)
+    pub fn app_properties(&self) -> Ref<'_, BTreeMap<String, String>> {
+        Ref::map(self.system_inner.borrow(), |s| &s.app_properties)
+    }
+
+    pub fn app_properties_mut(&self) -> RefMut<'_, BTreeMap<String, String>> {
+        RefMut::map(self.system_inner.borrow_mut(), |s| &mut s.app_properties)
+    }
+
+    /// Requests the host to stop the app, it is checked after each tick
+    pub fn exit(&self) {
+        self.system_inner.borrow_mut().exit_requested = true;
+    }
+
     pub fn context(&self) -> RefMut<'_, Box<dyn Any>> {
         RefMut::map(self.system_inner.borrow_mut(), |s| &mut s.context)
     }
@@ -1,22 +1,135 @@
-use alloc::collections::VecDeque;
+use alloc::{collections::VecDeque, vec::Vec};
 
-use wie_common::Event;
+use wie_common::{Event, KeyCode};
+
+use crate::{Instant, Notified, Notify};
+
+struct PressedKey {
+    key: KeyCode,
+    next_repeat: Instant,
+}
 
-#[derive(Default)]
 pub struct EventQueue {
     events: VecDeque<Event>,
+    notify: Notify,
+    pressed_keys: Vec<PressedKey>,
+    key_repeat_delay: u64,
+    key_repeat_interval: u64,
 }
 
 impl EventQueue {
-    pub fn new() -> Self {
-        Self { events: VecDeque::new() }
+    pub fn new(key_repeat_delay: u64, key_repeat_interval: u64) -> Self {
+        Self {
+            events: VecDeque::new(),
+            notify: Notify::new(),
+            pressed_keys: Vec::new(),
+            key_repeat_delay,
+            key_repeat_interval,
+        }
     }
 
-    pub fn push(&mut self, event: Event) {
+    // `now` is when the event happened, key repeat delay counts from the keydown
+    pub fn push(&mut self, event: Event, now: Instant) {
+        match event {
+            Event::Keydown(key) => {
+                if !self.pressed_keys.iter().any(|x| x.key == key) {
+                    let next_repeat = now + self.key_repeat_delay;
+                    self.pressed_keys.push(PressedKey { key, next_repeat });
+                }
+            }
+            Event::Keyup(key) => self.pressed_keys.retain(|x| x.key != key),
+            _ => {}
+        }
+
         self.events.push_back(event);
+        self.notify.notify();
     }
 
     pub fn pop(&mut self) -> Option<Event> {
         self.events.pop_front()
     }
+
+    // completes when an event is pushed after this call
+    pub fn notified(&self) -> Notified {
+        self.notify.notified()
+    }
+
+    // at most one repeat event is generated per key on each call
+    pub fn generate_key_repeat(&mut self, now: Instant) {
+        if self.key_repeat_interval == 0 {
+            return;
+        }
+
+        for pressed_key in &mut self.pressed_keys {
+            if pressed_key.next_repeat <= now {
+                self.events.push_back(Event::Keyrepeat(pressed_key.key));
+                self.notify.notify();
+                pressed_key.next_repeat = now + self.key_repeat_interval;
+            }
+        }
+    }
+
+    /// Time of the earliest pending key repeat
+    pub fn next_key_repeat(&self) -> Option<Instant> {
+        if self.key_repeat_interval == 0 {
+            return None;
+        }
+
+        self.pressed_keys.iter().map(|x| x.next_repeat).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wie_common::{Event, KeyCode};
+
+    use crate::Instant;
+
+    use super::EventQueue;
+
+    #[test]
+    fn test_key_repeat() {
+        let start = Instant::from_epoch_millis(1000);
+        let mut queue = EventQueue::new(500, 100);
+
+        queue.push(Event::Keydown(KeyCode::UP), start);
+        assert!(matches!(queue.pop(), Some(Event::Keydown(KeyCode::UP))));
+        assert_eq!(queue.next_key_repeat(), Some(start + 500));
+
+        // delay counts from keydown, not from the first call after it
+        queue.generate_key_repeat(start + 300);
+        queue.generate_key_repeat(start + 499);
+        assert!(queue.pop().is_none());
+
+        queue.generate_key_repeat(start + 500);
+        assert!(matches!(queue.pop(), Some(Event::Keyrepeat(KeyCode::UP))));
+
+        queue.generate_key_repeat(start + 599);
+        assert!(queue.pop().is_none());
+
+        // one event per call even if several intervals passed
+        queue.generate_key_repeat(start + 900);
+        assert!(matches!(queue.pop(), Some(Event::Keyrepeat(KeyCode::UP))));
+        assert!(queue.pop().is_none());
+
+        // keyup cancels repeats
+        queue.push(Event::Keyup(KeyCode::UP), start + 950);
+        assert!(matches!(queue.pop(), Some(Event::Keyup(KeyCode::UP))));
+
+        queue.generate_key_repeat(start + 10000);
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn test_key_repeat_disabled() {
+        let start = Instant::from_epoch_millis(1000);
+        let mut queue = EventQueue::new(500, 0);
+
+        queue.push(Event::Keydown(KeyCode::OK), start);
+        queue.pop();
+
+        queue.generate_key_repeat(start);
+        queue.generate_key_repeat(start + 10000);
+        assert!(queue.pop().is_none());
+    }
 }
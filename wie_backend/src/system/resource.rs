@@ -1,9 +1,10 @@
-use alloc::string::String;
+use alloc::{collections::BTreeMap, string::String};
 
-use crate::extract_zip;
+use crate::{create_zip, extract_zip};
 
 pub struct Resource {
     files: Vec<(String, Vec<u8>)>,
+    overlay: BTreeMap<String, Vec<u8>>,
 }
 
 impl Default for Resource {
@@ -14,15 +15,59 @@ impl Default for Resource {
 
 impl Resource {
     pub fn new() -> Self {
-        Self { files: Vec::new() }
+        Self {
+            files: Vec::new(),
+            overlay: BTreeMap::new(),
+        }
     }
 
     pub fn add(&mut self, path: &str, data: Vec<u8>) {
+        if self.overlay.contains_key(path) {
+            tracing::debug!("Resource {} is overridden by overlay", path);
+
+            return;
+        }
+
         tracing::trace!("Adding resource {}, {}b", path, data.len());
 
         self.files.push((path.to_string(), data));
     }
 
+    // overlay files take precedence over files added before or after
+    pub fn mount_overlay(&mut self, files: BTreeMap<String, Vec<u8>>) {
+        for (path, data) in files {
+            if let Some(file) = self.files.iter_mut().find(|x| x.0 == path) {
+                tracing::debug!("Overriding resource {} with overlay, {}b", path, data.len());
+
+                file.1 = data.clone();
+            } else {
+                tracing::debug!("Adding resource {} from overlay, {}b", path, data.len());
+
+                self.files.push((path.clone(), data.clone()));
+            }
+
+            self.overlay.insert(path, data);
+        }
+    }
+
+    // jars given to the jvm are read by its class loader, not through `Resource`, so the overlay is applied to the jar itself
+    pub fn overlay_zip(&self, zip: &[u8]) -> anyhow::Result<Vec<u8>> {
+        if self.overlay.is_empty() {
+            return Ok(zip.to_vec());
+        }
+
+        let mut files = extract_zip(zip)?;
+        for (path, data) in &self.overlay {
+            if files.insert(path.clone(), data.clone()).is_some() {
+                tracing::debug!("Overriding jar entry {} with overlay, {}b", path, data.len());
+            } else {
+                tracing::debug!("Adding jar entry {} from overlay, {}b", path, data.len());
+            }
+        }
+
+        create_zip(&files)
+    }
+
     pub fn id(&self, path: &str) -> Option<u32> {
         tracing::trace!("Looking for resource {}", path);
 
@@ -63,3 +108,53 @@ impl Resource {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeMap;
+
+    use crate::{create_zip, extract_zip};
+
+    use super::Resource;
+
+    fn files(files: &[(&str, &str)]) -> BTreeMap<String, Vec<u8>> {
+        files.iter().map(|(path, data)| (path.to_string(), data.as_bytes().to_vec())).collect()
+    }
+
+    #[test]
+    fn test_mount_overlay() {
+        let mut resource = Resource::new();
+        resource.add("a.txt", b"a".to_vec());
+
+        resource.mount_overlay(files(&[("a.txt", "overlay a"), ("b.txt", "overlay b"), ("c.txt", "overlay c")]));
+
+        // files added after mounting don't replace overlay ones
+        resource.add("b.txt", b"b".to_vec());
+        resource.add("d.txt", b"d".to_vec());
+
+        let data = |path| resource.id(path).map(|x| resource.data(x));
+        assert_eq!(data("a.txt"), Some(b"overlay a".as_slice()));
+        assert_eq!(data("/b.txt"), Some(b"overlay b".as_slice()));
+        assert_eq!(data("c.txt"), Some(b"overlay c".as_slice()));
+        assert_eq!(data("d.txt"), Some(b"d".as_slice()));
+        assert_eq!(resource.files().count(), 4);
+    }
+
+    #[test]
+    fn test_overlay_zip() -> anyhow::Result<()> {
+        let jar = create_zip(&files(&[("Main.class", "class"), ("res/a.txt", "a")]))?;
+
+        let mut resource = Resource::new();
+        assert_eq!(resource.overlay_zip(&jar)?, jar);
+
+        resource.mount_overlay(files(&[("res/a.txt", "overlay a"), ("res/b.txt", "overlay b")]));
+
+        let result = extract_zip(&resource.overlay_zip(&jar)?)?;
+        assert_eq!(
+            result,
+            files(&[("Main.class", "class"), ("res/a.txt", "overlay a"), ("res/b.txt", "overlay b")])
+        );
+
+        Ok(())
+    }
+}
@@ -6,13 +6,23 @@ use core::{
 
 use crate::{executor::Executor, time::Instant};
 
-pub struct YieldFuture {}
+#[derive(Default)]
+pub struct YieldFuture {
+    yielded: bool,
+}
 
 impl Future for YieldFuture {
     type Output = ();
 
-    fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
-        Poll::Ready(())
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if !self.yielded {
+            self.yielded = true;
+            cx.waker().wake_by_ref(); // let other tasks run, and poll us again on next step
+
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
     }
 }
 
@@ -0,0 +1,103 @@
+use std::io::{Cursor, Write};
+
+use zip::{write::FileOptions, ZipWriter};
+
+use wie_backend::{load_archive, App, Archive, ArchiveFiles, ArchiveLoader, ArchiveMetadata, ArchivePlatform, Platform};
+
+// matches archives containing a file named `marker`
+struct TestLoader {
+    name: &'static str,
+    marker: &'static str,
+}
+
+impl ArchiveLoader for TestLoader {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.marker
+    }
+
+    fn probe(&self, files: &ArchiveFiles) -> bool {
+        files.contains_key(self.marker)
+    }
+
+    fn load(&self, _: ArchiveFiles) -> anyhow::Result<Box<dyn Archive>> {
+        Ok(Box::new(TestArchive(ArchiveMetadata::new(self.name, "Main", ArchivePlatform::Ktf))))
+    }
+}
+
+struct TestArchive(ArchiveMetadata);
+
+impl Archive for TestArchive {
+    fn metadata(&self) -> &ArchiveMetadata {
+        &self.0
+    }
+
+    fn metadata_mut(&mut self) -> &mut ArchiveMetadata {
+        &mut self.0
+    }
+
+    fn load_app(self: Box<Self>, _: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>> {
+        anyhow::bail!("Not supported")
+    }
+}
+
+const LOADERS: [&dyn ArchiveLoader; 2] = [&TestLoader { name: "A", marker: "a" }, &TestLoader { name: "B", marker: "b" }];
+
+fn files(names: &[&str]) -> ArchiveFiles {
+    names.iter().map(|x| (x.to_string(), Vec::new())).collect()
+}
+
+fn zip(names: &[&str]) -> anyhow::Result<Vec<u8>> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for name in names {
+        writer.start_file(*name, FileOptions::default())?;
+        writer.write_all(b"test")?;
+    }
+
+    Ok(writer.finish()?.into_inner())
+}
+
+#[test]
+fn test_load_archive() -> anyhow::Result<()> {
+    assert_eq!(load_archive(&LOADERS, files(&["a", "c"]))?.id(), "A");
+    assert_eq!(load_archive(&LOADERS, files(&["b"]))?.id(), "B");
+
+    let error = load_archive(&LOADERS, files(&["c"])).err().unwrap().to_string();
+    assert!(error.contains("Unrecognized archive format"));
+    assert!(error.contains("A: a") && error.contains("B: b"));
+    assert!(error.contains("Files: c"));
+
+    Ok(())
+}
+
+#[test]
+fn test_load_archive_ambiguous() {
+    let error = load_archive(&LOADERS, files(&["a", "b"])).err().unwrap().to_string();
+
+    assert!(error.contains("Ambiguous archive"));
+    assert!(error.contains("A, B"));
+    assert!(error.contains("Files: a, b"));
+}
+
+#[test]
+fn test_load_archive_nested() -> anyhow::Result<()> {
+    let mut outer = files(&["readme.txt"]);
+    outer.insert("game.zip".into(), zip(&["b", "c"])?);
+
+    assert_eq!(load_archive(&LOADERS, outer.clone())?.id(), "B");
+
+    // files of the outer archive are probed first
+    outer.insert("a".into(), Vec::new());
+    assert_eq!(load_archive(&LOADERS, outer.clone())?.id(), "A");
+
+    outer.remove("a");
+    outer.insert("other.zip".into(), zip(&["a"])?);
+    let error = load_archive(&LOADERS, outer).err().unwrap().to_string();
+    assert!(error.contains("multiple nested archives"));
+    assert!(error.contains("game.zip, other.zip"));
+
+    Ok(())
+}
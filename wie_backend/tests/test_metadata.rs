@@ -0,0 +1,88 @@
+use wie_backend::{
+    encoding::{EUC_KR, UTF_8},
+    ArchiveMetadata, ArchivePlatform, HandsetProfile,
+};
+
+#[test]
+fn test_parse_manifest() {
+    let data = b"Manifest-Version: 1.0\r\nMIDlet-1: Game, /icon.png, com.example.\r\n GameMIDlet\r\nMIDlet-Jar-URL: http://example.com/game.jar\r\n";
+
+    let properties = ArchiveMetadata::parse_manifest(data, UTF_8);
+
+    assert_eq!(properties.len(), 3);
+    assert_eq!(properties["MIDlet-1"], "Game, /icon.png, com.example.GameMIDlet");
+    assert_eq!(properties["MIDlet-Jar-URL"], "http://example.com/game.jar");
+}
+
+#[test]
+fn test_parse_properties_encoding() {
+    let (data, _, _) = EUC_KR.encode("AID:1234\nName:테스트\n");
+
+    let properties = ArchiveMetadata::parse_properties(&data, EUC_KR);
+
+    assert_eq!(properties["AID"], "1234");
+    assert_eq!(properties["Name"], "테스트");
+
+    // bom overrides given encoding
+    let properties = ArchiveMetadata::parse_properties("\u{feff}Name:테스트".as_bytes(), EUC_KR);
+
+    assert_eq!(properties["Name"], "테스트");
+}
+
+#[test]
+fn test_parse_manifest_wrapped_multibyte() {
+    // wrapped in the middle of a character
+    let mut data = b"Name: \xed\x85".to_vec();
+    data.extend_from_slice(b"\r\n \x8c\xec\x8a\xa4\xed\x8a\xb8\r\nVendor: a\n b\n");
+
+    let properties = ArchiveMetadata::parse_manifest(&data, UTF_8);
+
+    assert_eq!(properties["Name"], "테스트");
+    assert_eq!(properties["Vendor"], "ab");
+
+    let (encoded, _, _) = EUC_KR.encode("테스트");
+    let mut data = b"Name:".to_vec();
+    data.extend_from_slice(&encoded[..1]);
+    data.extend_from_slice(b"\n ");
+    data.extend_from_slice(&encoded[1..]);
+
+    let properties = ArchiveMetadata::parse_manifest(&data, EUC_KR);
+
+    assert_eq!(properties["Name"], "테스트");
+}
+
+#[test]
+fn test_parse_properties_leading_space() {
+    // adf lines may start with a space, which is not a continuation as in jar manifest
+    let data = b"AID:1234\r\n PID:5678\r\nName:Test\n";
+
+    let properties = ArchiveMetadata::parse_properties(data, EUC_KR);
+
+    assert_eq!(properties["AID"], "1234");
+    assert_eq!(properties["PID"], "5678");
+    assert_eq!(properties["Name"], "Test");
+}
+
+#[test]
+fn test_app_properties_encoding() {
+    let (data, _, _) = EUC_KR.encode("AID:1234\nName:테스트\n");
+
+    let mut metadata = ArchiveMetadata::new("1234", "Main", ArchivePlatform::Ktf);
+    metadata.properties = ArchiveMetadata::parse_properties(&data, UTF_8);
+    metadata.descriptor = data.to_vec();
+
+    let mut profile = HandsetProfile::preset("ktf").unwrap();
+    profile.encoding = Some("utf-8".into());
+
+    // the profile's encoding, unless the archive overrides it
+    assert_eq!(metadata.app_encoding(&profile, EUC_KR), UTF_8);
+    metadata.encoding = Some(EUC_KR);
+    let encoding = metadata.app_encoding(&profile, UTF_8);
+    assert_eq!(encoding, EUC_KR);
+
+    assert_eq!(metadata.app_properties(encoding)["Name"], "테스트");
+
+    // without a descriptor, properties are given as they are
+    metadata.descriptor.clear();
+    assert_eq!(metadata.app_properties(encoding)["AID"], "1234");
+}
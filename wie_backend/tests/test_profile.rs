@@ -0,0 +1,52 @@
+use wie_backend::{
+    encoding::{EUC_KR, UTF_8},
+    HandsetProfile,
+};
+
+#[test]
+fn test_presets() {
+    for name in HandsetProfile::PRESETS {
+        let profile = HandsetProfile::preset(name).unwrap();
+
+        assert_eq!(profile.name, name);
+    }
+
+    let profile = HandsetProfile::preset("ktf-176x220").unwrap();
+    assert_eq!((profile.screen_width, profile.screen_height), (176, 220));
+    assert_eq!(profile.property("MODEL").as_deref(), Some("KTF_QCIF"));
+
+    assert!(HandsetProfile::preset("unknown").is_none());
+}
+
+#[test]
+fn test_framebuffer_bpp() {
+    let mut profile = HandsetProfile::preset("ktf").unwrap();
+    assert_eq!(profile.framebuffer_bpp(), 16);
+
+    profile.color_depth = 24;
+    assert_eq!(profile.framebuffer_bpp(), 32);
+}
+
+#[test]
+fn test_property() {
+    let mut profile = HandsetProfile::preset("skt").unwrap();
+
+    assert_eq!(profile.property("CARRIER").as_deref(), Some("SKT"));
+    assert_eq!(profile.property("PHONE_NUMBER"), profile.property("MIN"));
+    assert_eq!(profile.property("UNKNOWN"), None);
+
+    profile.properties.insert("CARRIER".into(), "Test".into());
+    assert_eq!(profile.property("CARRIER").as_deref(), Some("Test"));
+}
+
+#[test]
+fn test_encoding() {
+    let mut profile = HandsetProfile::default();
+    assert_eq!(profile.encoding(EUC_KR), EUC_KR);
+
+    profile.encoding = Some("utf-8".into());
+    assert_eq!(profile.encoding(EUC_KR), UTF_8);
+
+    profile.encoding = Some("unknown".into());
+    assert_eq!(profile.encoding(EUC_KR), EUC_KR);
+}
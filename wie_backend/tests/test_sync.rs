@@ -0,0 +1,39 @@
+use core::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll},
+};
+
+use futures_test::task::new_count_waker;
+
+use wie_backend::Channel;
+
+#[test]
+fn test_channel_wakes_receiver() {
+    let (waker, count) = new_count_waker();
+    let mut context = Context::from_waker(&waker);
+
+    let channel = Channel::new();
+    let receiver = channel.clone();
+    let mut recv = pin!(receiver.recv());
+
+    assert_eq!(recv.as_mut().poll(&mut context), Poll::Pending);
+    assert_eq!(count.get(), 0);
+
+    channel.send(1);
+    assert_eq!(count.get(), 1);
+
+    assert_eq!(recv.as_mut().poll(&mut context), Poll::Ready(1));
+}
+
+#[test]
+fn test_channel_keeps_order() {
+    let channel = Channel::new();
+
+    channel.send(1);
+    channel.send(2);
+
+    assert_eq!(channel.try_recv(), Some(1));
+    assert_eq!(channel.try_recv(), Some(2));
+    assert_eq!(channel.try_recv(), None);
+}
@@ -0,0 +1,88 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+
+use wie_backend::{extract_zip, load_archive, Archive, ArchiveFiles, ArchiveLoader};
+use wie_j2me::{J2MEArchive, J2MEArchiveLoader};
+use wie_ktf::KtfArchiveLoader;
+use wie_lgt::LgtArchiveLoader;
+use wie_skt::SktArchiveLoader;
+
+const LOADERS: [&dyn ArchiveLoader; 4] = [&KtfArchiveLoader, &LgtArchiveLoader, &SktArchiveLoader, &J2MEArchiveLoader];
+
+// format is detected by content, not by extension
+pub fn open_archive(path: &str) -> anyhow::Result<Box<dyn Archive>> {
+    let path = Path::new(path);
+    let files = read_files(path).with_context(|| format!("Can't read {}", path.display()))?;
+
+    load_archive(&LOADERS, files).with_context(|| format!("Can't load {}", path.display()))
+}
+
+fn read_files(path: &Path) -> anyhow::Result<ArchiveFiles> {
+    if path.is_dir() {
+        return read_directory(path);
+    }
+
+    let data = fs::read(path)?;
+    let filename = path.file_name().context("Invalid path")?.to_string_lossy().into_owned();
+
+    if data.starts_with(b"PK\x03\x04") {
+        let files = extract_zip(&data)?;
+
+        // jar is a zip with manifest, pass it as a whole
+        if files.contains_key("META-INF/MANIFEST.MF") {
+            let name = if filename.to_ascii_lowercase().ends_with(".jar") {
+                filename
+            } else {
+                format!("{}.jar", filename)
+            };
+
+            return Ok([(name, data)].into());
+        }
+
+        return Ok(files);
+    }
+
+    // otherwise it should be a jad
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let jar_path = J2MEArchive::jar_path(&data)
+        .map(|x| base_dir.join(x))
+        .filter(|x| x.is_file())
+        .unwrap_or_else(|| path.with_extension("jar"));
+    let jar = fs::read(&jar_path).with_context(|| format!("Can't find jar of {} at {}", filename, jar_path.display()))?;
+
+    Ok([(filename, data), (relative_name(base_dir, &jar_path)?, jar)].into())
+}
+
+// file names are relative to `path`
+pub fn read_directory(path: &Path) -> anyhow::Result<ArchiveFiles> {
+    let mut files = ArchiveFiles::new();
+    read_dir(path, path, &mut files)?;
+
+    Ok(files)
+}
+
+fn read_dir(base: &Path, dir: &Path, files: &mut ArchiveFiles) -> anyhow::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            read_dir(base, &path, files)?;
+        } else {
+            files.insert(relative_name(base, &path)?, fs::read(&path)?);
+        }
+    }
+
+    Ok(())
+}
+
+// archive file names are always separated by `/`
+fn relative_name(base: &Path, path: &Path) -> anyhow::Result<String> {
+    let components = path
+        .strip_prefix(base)?
+        .components()
+        .map(|x| x.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+
+    Ok(components.join("/"))
+}
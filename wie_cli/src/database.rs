@@ -1,32 +1,101 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
 
 use directories::ProjectDirs;
 
-use wie_backend::RecordId;
+use wie_backend::{DatabaseError, DatabaseResult, RecordId};
+
+pub fn data_dir() -> PathBuf {
+    let base_dir = ProjectDirs::from("net", "dlunch", "wie").unwrap();
+
+    base_dir.data_dir().to_owned()
+}
+
+/// Rejects names that would resolve outside the directory they are joined to
+pub fn validate_name(name: &str) -> DatabaseResult<()> {
+    let mut components = Path::new(name).components();
+    let single_component = matches!((components.next(), components.next()), (Some(Component::Normal(_)), None));
+
+    // `\` and `:` aren't separators on every host, so check them explicitly
+    if !single_component || name.contains(['/', '\\', ':']) || name.contains("..") {
+        return Err(DatabaseError::InvalidName);
+    }
+
+    Ok(())
+}
 
 pub struct DatabaseRepository {
+    app_id: String,
     base_path: PathBuf,
 }
 
 impl DatabaseRepository {
     pub fn new(app_id: &str) -> Self {
-        let base_dir = ProjectDirs::from("net", "dlunch", "wie").unwrap();
+        Self::with_data_dir(&data_dir(), app_id)
+    }
+
+    pub fn with_data_dir(data_dir: &Path, app_id: &str) -> Self {
+        Self {
+            app_id: app_id.to_owned(),
+            base_path: data_dir.join(app_id),
+        }
+    }
 
-        let base_path = base_dir.data_dir().join(app_id);
+    pub fn path(&self) -> DatabaseResult<&Path> {
+        validate_name(&self.app_id)?;
 
-        Self { base_path }
+        Ok(&self.base_path)
     }
 
-    fn get_path_for_database(&self, name: &str) -> PathBuf {
-        self.base_path.join(name)
+    fn get_path_for_database(&self, name: &str) -> DatabaseResult<PathBuf> {
+        validate_name(name)?;
+
+        Ok(self.path()?.join(name))
     }
 }
 
 impl wie_backend::DatabaseRepository for DatabaseRepository {
-    fn open(&self, name: &str) -> Box<dyn wie_backend::Database> {
-        let path = self.get_path_for_database(name);
+    fn open(&self, name: &str, create: bool) -> DatabaseResult<Box<dyn wie_backend::Database>> {
+        let path = self.get_path_for_database(name)?;
+
+        if !create && !path.is_dir() {
+            return Err(DatabaseError::NotFound);
+        }
+
+        Ok(Box::new(Database::new(path)?))
+    }
+
+    fn delete(&self, name: &str) -> DatabaseResult<()> {
+        let path = self.get_path_for_database(name)?;
+
+        tracing::trace!("Deleting database at {:?}", path);
+
+        Ok(fs::remove_dir_all(path)?)
+    }
+
+    fn list(&self) -> DatabaseResult<Vec<String>> {
+        let base_path = self.path()?;
+        if !base_path.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut names = Vec::new();
+        for entry in fs::read_dir(base_path)? {
+            let entry = entry?;
+            if !entry.path().is_dir() {
+                continue;
+            }
+
+            if let Some(name) = entry.file_name().to_str() {
+                names.push(name.to_owned());
+            }
+        }
 
-        Box::new(Database::new(path).unwrap())
+        names.sort();
+
+        Ok(names)
     }
 }
 
@@ -35,7 +104,7 @@ pub struct Database {
 }
 
 impl Database {
-    pub fn new(base_path: PathBuf) -> anyhow::Result<Self> {
+    pub fn new(base_path: PathBuf) -> DatabaseResult<Self> {
         tracing::trace!("Opening database at {:?}", base_path);
 
         fs::create_dir_all(&base_path)?;
@@ -43,65 +112,118 @@ impl Database {
         Ok(Self { base_path })
     }
 
-    fn find_empty_record_id(&mut self) -> RecordId {
-        let mut record_id = 0;
+    fn find_empty_record_id(&mut self) -> DatabaseResult<RecordId> {
+        let mut record_id: RecordId = 0;
 
         loop {
             let path = self.base_path.join(record_id.to_string());
 
             if !path.exists() {
-                return record_id;
+                return Ok(record_id);
             }
 
-            record_id += 1;
+            record_id = record_id.checked_add(1).ok_or(DatabaseError::Full)?;
         }
     }
+
     fn get_path_for_record(&self, id: RecordId) -> PathBuf {
         self.base_path.join(id.to_string())
     }
 }
 
 impl wie_backend::Database for Database {
-    fn add(&mut self, data: &[u8]) -> RecordId {
-        let id = self.find_empty_record_id();
+    fn add(&mut self, data: &[u8]) -> DatabaseResult<RecordId> {
+        let id = self.find_empty_record_id()?;
 
         tracing::trace!("Adding record {} to database {:?}", id, &self.base_path);
 
         let path = self.get_path_for_record(id);
-        fs::write(path, data).unwrap();
+        fs::write(path, data)?;
 
-        id
+        Ok(id)
     }
 
-    fn get(&self, id: RecordId) -> Option<Vec<u8>> {
+    fn get(&self, id: RecordId) -> DatabaseResult<Vec<u8>> {
         let path = self.get_path_for_record(id);
 
         tracing::trace!("Read record {} from database {:?}", id, &self.base_path);
 
-        fs::read(path).ok()
+        Ok(fs::read(path)?)
     }
 
-    fn set(&mut self, id: RecordId, data: &[u8]) -> bool {
+    fn set(&mut self, id: RecordId, data: &[u8]) -> DatabaseResult<()> {
         let path = self.get_path_for_record(id);
 
         tracing::trace!("Set record {} to database {:?}", id, &self.base_path);
 
-        fs::write(path, data).is_ok()
+        Ok(fs::write(path, data)?)
     }
 
-    fn delete(&mut self, id: RecordId) -> bool {
+    fn delete(&mut self, id: RecordId) -> DatabaseResult<()> {
         let path = self.get_path_for_record(id);
 
         tracing::trace!("Delete record {} from database {:?}", id, &self.base_path);
 
-        fs::remove_file(path).is_ok()
+        Ok(fs::remove_file(path)?)
+    }
+
+    fn get_record_ids(&self) -> DatabaseResult<Vec<RecordId>> {
+        let mut ids = Vec::new();
+
+        for entry in fs::read_dir(&self.base_path)? {
+            let entry = entry?;
+            if !entry.path().is_file() {
+                continue;
+            }
+
+            if let Some(id) = entry.file_name().to_str().and_then(|x| x.parse().ok()) {
+                ids.push(id);
+            }
+        }
+
+        ids.sort();
+
+        Ok(ids)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use wie_backend::{DatabaseError, DatabaseRepository as _};
+
+    use super::{validate_name, DatabaseRepository};
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("save").is_ok());
+        assert!(validate_name("a.b").is_ok());
+
+        for name in ["", ".", "..", "../..", "a/b", "/etc", "a\\b", "C:", "C:save", "a..b"] {
+            assert_eq!(validate_name(name), Err(DatabaseError::InvalidName), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_repository_rejects_invalid_names() -> anyhow::Result<()> {
+        let data_dir = env::temp_dir().join(format!("wie_test_database_{}", process::id()));
+        let repository = DatabaseRepository::with_data_dir(&data_dir, "app");
+
+        assert_eq!(repository.open("../escape", true).err(), Some(DatabaseError::InvalidName));
+        assert_eq!(repository.delete("..").err(), Some(DatabaseError::InvalidName));
+        assert!(!data_dir.join("escape").exists());
+
+        let repository = DatabaseRepository::with_data_dir(&data_dir, "..");
+        assert_eq!(repository.list().err(), Some(DatabaseError::InvalidName));
+        assert_eq!(repository.open("save", true).err(), Some(DatabaseError::InvalidName));
+
+        let repository = DatabaseRepository::with_data_dir(&data_dir, "app");
+        repository.open("save", true)?.add(&[1, 2, 3])?;
+        assert_eq!(repository.list()?, ["save"]);
+
+        fs::remove_dir_all(data_dir)?;
 
-    fn get_record_ids(&self) -> Vec<RecordId> {
-        fs::read_dir(&self.base_path)
-            .unwrap()
-            .filter(|x| x.as_ref().unwrap().path().is_file())
-            .map(|x| x.unwrap().file_name().to_str().unwrap().parse().unwrap())
-            .collect()
+        Ok(())
     }
 }
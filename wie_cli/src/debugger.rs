@@ -0,0 +1,66 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{ErrorKind, LineWriter, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use wie_backend::DebugConnection;
+
+pub struct TcpDebugConnection {
+    stream: TcpStream,
+}
+
+impl TcpDebugConnection {
+    // blocks until a debugger connects, so it can see the app from its first instruction
+    pub fn listen(port: u16) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        tracing::info!("Waiting for debugger on 127.0.0.1:{}", port);
+
+        let (stream, address) = listener.accept()?;
+        tracing::info!("Debugger connected from {}", address);
+
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+
+        Ok(Self { stream })
+    }
+}
+
+impl DebugConnection for TcpDebugConnection {
+    fn read(&mut self, buf: &mut [u8]) -> anyhow::Result<usize> {
+        match self.stream.read(buf) {
+            Ok(0) => anyhow::bail!("Connection closed"),
+            Ok(x) => Ok(x),
+            Err(x) if x.kind() == ErrorKind::WouldBlock => Ok(0),
+            Err(x) => Err(x.into()),
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        self.stream.set_nonblocking(false)?;
+        let result = self.stream.write_all(data);
+        self.stream.set_nonblocking(true)?;
+
+        Ok(result?)
+    }
+}
+
+// flushed per line, as the window loop may exit the process without dropping it
+pub struct InstructionTraceFile {
+    writer: LineWriter<File>,
+}
+
+impl InstructionTraceFile {
+    pub fn create(path: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            writer: LineWriter::new(File::create(path)?),
+        })
+    }
+}
+
+impl fmt::Write for InstructionTraceFile {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.writer.write_all(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
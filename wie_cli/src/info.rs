@@ -0,0 +1,59 @@
+use std::fs;
+
+use wie_backend::Archive;
+
+pub fn run(archive: &dyn Archive, json: bool, icon_output: Option<&str>) -> anyhow::Result<()> {
+    let metadata = archive.metadata();
+
+    if let Some(output) = icon_output {
+        let icon = metadata.icon.as_ref().ok_or_else(|| anyhow::anyhow!("{} has no icon", metadata.id))?;
+
+        fs::write(output, icon)?;
+    }
+
+    if json {
+        let value = serde_json::json!({
+            "id": metadata.id,
+            "platform": metadata.platform.to_string(),
+            "main_class": metadata.main_class,
+            "name": metadata.name,
+            "vendor": metadata.vendor,
+            "version": metadata.version,
+            "icon_size": metadata.icon.as_ref().map(|x| x.len()),
+            "heap_size": metadata.heap_size,
+            "screen_size": metadata.screen_size,
+            "properties": metadata.properties,
+        });
+
+        println!("{}", serde_json::to_string_pretty(&value)?);
+
+        return Ok(());
+    }
+
+    let unknown = || "-".to_owned();
+
+    println!("ID:          {}", metadata.id);
+    println!("Platform:    {}", metadata.platform);
+    println!("Main class:  {}", metadata.main_class);
+    println!("Name:        {}", metadata.name.clone().unwrap_or_else(unknown));
+    println!("Vendor:      {}", metadata.vendor.clone().unwrap_or_else(unknown));
+    println!("Version:     {}", metadata.version.clone().unwrap_or_else(unknown));
+    println!(
+        "Icon:        {}",
+        metadata.icon.as_ref().map(|x| format!("{} bytes", x.len())).unwrap_or_else(unknown)
+    );
+    println!("Heap size:   {}", metadata.heap_size.map(|x| x.to_string()).unwrap_or_else(unknown));
+    println!(
+        "Screen size: {}",
+        metadata.screen_size.map(|(w, h)| format!("{}x{}", w, h)).unwrap_or_else(unknown)
+    );
+
+    if !metadata.properties.is_empty() {
+        println!("Properties:");
+        for (key, value) in &metadata.properties {
+            println!("  {}: {}", key, value);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use anyhow::Context;
+use directories::ProjectDirs;
+use winit::keyboard::{KeyCode as WinitKeyCode, PhysicalKey};
+
+use wie_common::KeyCode;
+
+pub struct KeyMap {
+    bindings: HashMap<WinitKeyCode, KeyCode>,
+}
+
+impl KeyMap {
+    // Key map is loaded from, in order: `path` given on the command line and `<config dir>/keys.toml`.
+    // The file maps phone keys to lists of keyboard keys (e.g. `LSK = ["F1", "Minus"]`), replacing the default bindings of listed phone keys.
+    pub fn load(path: Option<&str>) -> anyhow::Result<Self> {
+        let mut keymap = Self::default();
+
+        let path = if let Some(path) = path {
+            let path = Path::new(path).to_owned();
+            anyhow::ensure!(path.is_file(), "Key map {:?} does not exist", path);
+
+            path
+        } else {
+            let base_dir = ProjectDirs::from("net", "dlunch", "wie").unwrap();
+            let path = base_dir.config_dir().join("keys.toml");
+            if !path.is_file() {
+                return Ok(keymap);
+            }
+
+            path
+        };
+
+        tracing::info!("Using key map {:?}", path);
+
+        let data = fs::read_to_string(&path)?;
+        let bindings: BTreeMap<String, Vec<WinitKeyCode>> = toml::from_str(&data).with_context(|| format!("Invalid key map {:?}", path))?;
+
+        for (name, keys) in bindings {
+            let keycode =
+                KeyCode::parse(&name).with_context(|| format!("Unknown phone key {} in {:?}, available keys: {:?}", name, path, KeyCode::ALL))?;
+
+            keymap.bindings.retain(|_, x| *x != keycode);
+            for key in keys {
+                keymap.bindings.insert(key, keycode);
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    pub fn convert(&self, key: PhysicalKey) -> Option<KeyCode> {
+        match key {
+            PhysicalKey::Code(x) => self.bindings.get(&x).copied(),
+            PhysicalKey::Unidentified(_) => None,
+        }
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let bindings = [
+            (WinitKeyCode::Digit1, KeyCode::NUM1),
+            (WinitKeyCode::Digit2, KeyCode::NUM2),
+            (WinitKeyCode::Digit3, KeyCode::NUM3),
+            (WinitKeyCode::KeyQ, KeyCode::NUM4),
+            (WinitKeyCode::KeyW, KeyCode::NUM5),
+            (WinitKeyCode::KeyE, KeyCode::NUM6),
+            (WinitKeyCode::KeyA, KeyCode::NUM7),
+            (WinitKeyCode::KeyS, KeyCode::NUM8),
+            (WinitKeyCode::KeyD, KeyCode::NUM9),
+            (WinitKeyCode::KeyZ, KeyCode::STAR),
+            (WinitKeyCode::KeyX, KeyCode::NUM0),
+            (WinitKeyCode::KeyC, KeyCode::HASH),
+            (WinitKeyCode::Space, KeyCode::OK),
+            (WinitKeyCode::ArrowUp, KeyCode::UP),
+            (WinitKeyCode::ArrowDown, KeyCode::DOWN),
+            (WinitKeyCode::ArrowLeft, KeyCode::LEFT),
+            (WinitKeyCode::ArrowRight, KeyCode::RIGHT),
+            (WinitKeyCode::F1, KeyCode::LSK),
+            (WinitKeyCode::F2, KeyCode::RSK),
+            (WinitKeyCode::Backspace, KeyCode::CLEAR),
+            (WinitKeyCode::F3, KeyCode::SEND),
+            (WinitKeyCode::Escape, KeyCode::END),
+            (WinitKeyCode::PageUp, KeyCode::VOLUMEUP),
+            (WinitKeyCode::PageDown, KeyCode::VOLUMEDOWN),
+            (WinitKeyCode::F4, KeyCode::SIDE),
+        ];
+
+        Self {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use winit::keyboard::{KeyCode as WinitKeyCode, PhysicalKey};
+
+    use wie_common::KeyCode;
+
+    use super::KeyMap;
+
+    #[test]
+    fn test_load() -> anyhow::Result<()> {
+        let dir = env::temp_dir().join(format!("wie_test_keymap_{}", process::id()));
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join("keys.toml");
+        fs::write(&path, "LSK = [\"F5\", \"Minus\"]\n5 = [\"KeyK\"]\n")?;
+        let keymap = KeyMap::load(path.to_str())?;
+
+        let convert = |x| keymap.convert(PhysicalKey::Code(x));
+        assert_eq!(convert(WinitKeyCode::F5), Some(KeyCode::LSK));
+        assert_eq!(convert(WinitKeyCode::Minus), Some(KeyCode::LSK));
+        assert_eq!(convert(WinitKeyCode::KeyK), Some(KeyCode::NUM5));
+        // listed keys lose their default bindings, others keep them
+        assert_eq!(convert(WinitKeyCode::F1), None);
+        assert_eq!(convert(WinitKeyCode::KeyW), None);
+        assert_eq!(convert(WinitKeyCode::F2), Some(KeyCode::RSK));
+
+        fs::write(&path, "MENU = [\"F5\"]\n")?;
+        assert!(KeyMap::load(path.to_str()).is_err());
+
+        fs::write(&path, "LSK = [\"NoSuchKey\"]\n")?;
+        assert!(KeyMap::load(path.to_str()).is_err());
+
+        assert!(KeyMap::load(dir.join("missing.toml").to_str()).is_err());
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+}
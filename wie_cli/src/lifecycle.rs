@@ -0,0 +1,172 @@
+use std::time::{Duration, Instant};
+
+use winit::keyboard::{KeyCode as WinitKeyCode, PhysicalKey};
+
+use wie_backend::App;
+use wie_common::Event;
+
+// toggles a simulated incoming call, which pauses the app like on a real handset
+const INCOMING_CALL_KEY: PhysicalKey = PhysicalKey::Code(WinitKeyCode::F12);
+// time given to the app to save its state after closing the window
+const EXIT_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Default)]
+pub struct Lifecycle {
+    focus_lost: bool,
+    incoming_call: bool,
+    incoming_call_key_down: bool,
+    paused: bool,
+    exit_deadline: Option<Instant>,
+}
+
+impl Lifecycle {
+    pub fn on_focus(&mut self, app: &mut dyn App, focused: bool) {
+        self.focus_lost = !focused;
+        self.update(app);
+    }
+
+    // returns true if the key is consumed. other keys are dropped while the app is paused, as the call has the keypad
+    pub fn on_key(&mut self, app: &mut dyn App, key: PhysicalKey, pressed: bool) -> bool {
+        if key != INCOMING_CALL_KEY {
+            return self.paused;
+        }
+
+        if pressed && !self.incoming_call_key_down {
+            self.incoming_call = !self.incoming_call;
+            tracing::info!("Incoming call {}", if self.incoming_call { "started" } else { "ended" });
+
+            self.update(app);
+        }
+        self.incoming_call_key_down = pressed;
+
+        true
+    }
+
+    // returns true if the window should be closed without waiting for the app, which is the case on second close request
+    pub fn on_close_requested(&mut self, app: &mut dyn App) -> bool {
+        if self.exit_deadline.is_some() {
+            return true;
+        }
+
+        app.on_event(Event::Exit);
+        self.exit_deadline = Some(Instant::now() + EXIT_TIMEOUT);
+
+        false
+    }
+
+    pub fn should_exit(&self, app: &dyn App) -> bool {
+        if app.exit_requested() {
+            return true;
+        }
+
+        if self.exit_deadline.is_some_and(|x| Instant::now() >= x) {
+            tracing::warn!("App didn't exit in {:?}, closing", EXIT_TIMEOUT);
+
+            return true;
+        }
+
+        false
+    }
+
+    fn update(&mut self, app: &mut dyn App) {
+        let paused = self.focus_lost || self.incoming_call;
+        if paused == self.paused {
+            return;
+        }
+
+        self.paused = paused;
+        app.on_event(if paused { Event::Pause } else { Event::Resume });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::{KeyCode as WinitKeyCode, PhysicalKey};
+
+    use wie_backend::{App, Instant};
+    use wie_common::Event;
+
+    use super::{Lifecycle, INCOMING_CALL_KEY};
+
+    #[derive(Default)]
+    struct TestApp {
+        events: Vec<Event>,
+        exit_requested: bool,
+    }
+
+    impl App for TestApp {
+        fn start(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn on_event(&mut self, event: Event) {
+            self.events.push(event);
+        }
+
+        fn tick(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn next_wakeup(&self) -> Option<Instant> {
+            None
+        }
+
+        fn exit_requested(&self) -> bool {
+            self.exit_requested
+        }
+
+        fn crash_dump(&self) -> String {
+            String::new()
+        }
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let (mut lifecycle, mut app) = (Lifecycle::default(), TestApp::default());
+
+        // a call while unfocused doesn't pause again, and ending it doesn't resume
+        lifecycle.on_focus(&mut app, false);
+        assert!(lifecycle.on_key(&mut app, INCOMING_CALL_KEY, true));
+        assert!(lifecycle.on_key(&mut app, INCOMING_CALL_KEY, false));
+        lifecycle.on_focus(&mut app, true);
+        assert!(matches!(app.events[..], [Event::Pause]));
+
+        assert!(lifecycle.on_key(&mut app, INCOMING_CALL_KEY, true));
+        assert!(matches!(app.events[..], [Event::Pause, Event::Resume]));
+
+        // key repeat of the call key doesn't toggle the call
+        assert!(lifecycle.on_key(&mut app, INCOMING_CALL_KEY, true));
+        assert_eq!(app.events.len(), 2);
+    }
+
+    #[test]
+    fn test_keys_dropped_while_paused() {
+        let (mut lifecycle, mut app) = (Lifecycle::default(), TestApp::default());
+        let key = PhysicalKey::Code(WinitKeyCode::Digit1);
+
+        assert!(!lifecycle.on_key(&mut app, key, true));
+
+        lifecycle.on_key(&mut app, INCOMING_CALL_KEY, true);
+        lifecycle.on_key(&mut app, INCOMING_CALL_KEY, false);
+        assert!(lifecycle.on_key(&mut app, key, true));
+        assert!(lifecycle.on_key(&mut app, key, false));
+
+        lifecycle.on_key(&mut app, INCOMING_CALL_KEY, true);
+        assert!(!lifecycle.on_key(&mut app, key, true));
+    }
+
+    #[test]
+    fn test_exit() {
+        let (mut lifecycle, mut app) = (Lifecycle::default(), TestApp::default());
+
+        assert!(!lifecycle.on_close_requested(&mut app));
+        assert!(matches!(app.events[..], [Event::Exit]));
+        assert!(!lifecycle.should_exit(&app));
+
+        app.exit_requested = true;
+        assert!(lifecycle.should_exit(&app));
+
+        // second request closes without waiting
+        assert!(lifecycle.on_close_requested(&mut app));
+    }
+}
@@ -1,42 +1,73 @@
 extern crate alloc;
 
+mod archive;
 mod audio_sink;
 mod database;
+mod debugger;
+mod info;
+mod keymap;
+mod lifecycle;
+mod overlay;
+mod profile;
+mod saves;
 mod window;
 
 use std::{
     collections::HashSet,
-    fs,
+    fmt,
     io::stderr,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use clap::Parser;
-use winit::keyboard::{KeyCode as WinitKeyCode, PhysicalKey};
+use anyhow::Context;
+use clap::{CommandFactory, Parser, Subcommand};
 
-use wie_backend::{extract_zip, Archive, Instant, Platform, Screen};
-use wie_common::{Event, KeyCode};
-use wie_j2me::J2MEArchive;
-use wie_ktf::KtfArchive;
-use wie_lgt::LgtArchive;
-use wie_skt::SktArchive;
+use wie_backend::{
+    encoding::encoding_for_label, App, ArchiveFiles, ArchivePlatform, DebugConnection, HandsetProfile, Instant, NativeEngine, Platform, Screen,
+};
+use wie_common::Event;
 
 use self::{
+    archive::open_archive,
     audio_sink::AudioSink,
     database::DatabaseRepository,
+    debugger::{InstructionTraceFile, TcpDebugConnection},
+    keymap::KeyMap,
+    lifecycle::Lifecycle,
+    overlay::load_overlay,
+    profile::select_profile,
+    saves::SavesCommand,
     window::{WindowCallbackEvent, WindowImpl},
 };
 
 struct WieCliPlatform {
     database_repository: DatabaseRepository,
     window: Box<dyn Screen>,
+    profile: HandsetProfile,
+    resource_overlay: ArchiveFiles,
+    debug_connection: Option<Box<dyn DebugConnection>>,
+    instruction_trace: Option<Box<dyn fmt::Write>>,
+    native_engine: Option<NativeEngine>,
 }
 
 impl WieCliPlatform {
-    fn new(app_id: &str, window: Box<dyn Screen>) -> Self {
+    fn new(
+        app_id: &str,
+        window: Box<dyn Screen>,
+        profile: HandsetProfile,
+        resource_overlay: ArchiveFiles,
+        debug_connection: Option<Box<dyn DebugConnection>>,
+        instruction_trace: Option<Box<dyn fmt::Write>>,
+        native_engine: Option<NativeEngine>,
+    ) -> Self {
         Self {
             database_repository: DatabaseRepository::new(app_id),
             window,
+            profile,
+            resource_overlay,
+            debug_connection,
+            instruction_trace,
+            native_engine,
         }
     }
 }
@@ -60,11 +91,114 @@ impl Platform for WieCliPlatform {
     fn audio_sink(&self) -> Box<dyn wie_backend::AudioSink> {
         Box::new(AudioSink)
     }
+
+    fn profile(&self) -> &HandsetProfile {
+        &self.profile
+    }
+
+    fn resource_overlay(&self) -> ArchiveFiles {
+        self.resource_overlay.clone()
+    }
+
+    fn debug_connection(&mut self) -> Option<Box<dyn DebugConnection>> {
+        self.debug_connection.take()
+    }
+
+    fn instruction_trace(&mut self) -> Option<Box<dyn fmt::Write>> {
+        self.instruction_trace.take()
+    }
+
+    fn native_engine(&self) -> Option<NativeEngine> {
+        self.native_engine
+    }
 }
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
-    filename: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    filename: Option<String>,
+
+    /// Handset profile preset name or path to a profile file (toml or json)
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Directory with files replacing or adding to the app's resources
+    #[arg(long)]
+    overlay: Option<String>,
+
+    /// Key binding file (toml), defaults to `keys.toml` in the config directory
+    #[arg(long)]
+    keymap: Option<String>,
+
+    /// Wait for a gdb remote debugger on this localhost port before starting a native (KTF, LGT) app
+    #[arg(long)]
+    gdb: Option<u16>,
+
+    /// Write every instruction executed by a native (KTF, LGT) app, disassembled, to this file
+    #[arg(long)]
+    trace: Option<String>,
+
+    /// Cpu engine for a native (KTF, LGT) app: armv4t_emu, unicorn, or lockstep to run both and stop where they diverge
+    #[arg(long)]
+    engine: Option<String>,
+
+    /// Encoding of the app's strings (e.g. euc-kr, utf-8), overriding the profile's
+    #[arg(long)]
+    encoding: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run an app
+    Run {
+        filename: String,
+
+        /// Handset profile preset name or path to a profile file (toml or json)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Directory with files replacing or adding to the app's resources
+        #[arg(long)]
+        overlay: Option<String>,
+
+        /// Key binding file (toml), defaults to `keys.toml` in the config directory
+        #[arg(long)]
+        keymap: Option<String>,
+
+        /// Wait for a gdb remote debugger on this localhost port before starting a native (KTF, LGT) app
+        #[arg(long)]
+        gdb: Option<u16>,
+
+        /// Write every instruction executed by a native (KTF, LGT) app, disassembled, to this file
+        #[arg(long)]
+        trace: Option<String>,
+
+        /// Cpu engine for a native (KTF, LGT) app: armv4t_emu, unicorn, or lockstep to run both and stop where they diverge
+        #[arg(long)]
+        engine: Option<String>,
+
+        /// Encoding of the app's strings (e.g. euc-kr, utf-8), overriding the profile's
+        #[arg(long)]
+        encoding: Option<String>,
+    },
+    /// Print metadata of an app
+    Info {
+        filename: String,
+
+        /// Print as json
+        #[arg(long)]
+        json: bool,
+
+        /// Save the app's icon to this path
+        #[arg(long)]
+        icon: Option<String>,
+    },
+    /// Manage save data of apps
+    #[command(subcommand)]
+    Saves(SavesCommand),
 }
 
 fn main() -> anyhow::Result<()> {
@@ -73,49 +207,151 @@ fn main() -> anyhow::Result<()> {
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    start(&Args::parse().filename)
+    let args = Args::parse();
+
+    match (args.command, args.filename) {
+        (
+            Some(Command::Run {
+                filename,
+                profile,
+                overlay,
+                keymap,
+                gdb,
+                trace,
+                engine,
+                encoding,
+            }),
+            _,
+        ) => start(
+            &filename,
+            profile.as_deref(),
+            overlay.as_deref(),
+            keymap.as_deref(),
+            gdb,
+            trace.as_deref(),
+            engine.as_deref(),
+            encoding.as_deref(),
+        ),
+        (None, Some(filename)) => start(
+            &filename,
+            args.profile.as_deref(),
+            args.overlay.as_deref(),
+            args.keymap.as_deref(),
+            args.gdb,
+            args.trace.as_deref(),
+            args.engine.as_deref(),
+            args.encoding.as_deref(),
+        ),
+        (Some(Command::Info { filename, json, icon }), _) => info::run(open_archive(&filename)?.as_ref(), json, icon.as_deref()),
+        (Some(Command::Saves(command)), _) => saves::run(command),
+        (None, None) => {
+            Args::command().print_help()?;
+
+            Ok(())
+        }
+    }
 }
 
-pub fn start(filename: &str) -> anyhow::Result<()> {
-    let archive: Box<dyn Archive> = if filename.ends_with("zip") {
-        let buf = fs::read(filename)?;
+#[allow(clippy::too_many_arguments)]
+pub fn start(
+    filename: &str,
+    profile: Option<&str>,
+    overlay: Option<&str>,
+    keymap: Option<&str>,
+    gdb: Option<u16>,
+    trace: Option<&str>,
+    engine: Option<&str>,
+    encoding: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut archive = open_archive(filename)?;
+    if let Some(label) = encoding {
+        archive.metadata_mut().encoding = Some(encoding_for_label(label).with_context(|| format!("Unknown encoding {}", label))?);
+    }
+    let keymap = KeyMap::load(keymap)?;
+
+    let default_profile = match archive.metadata().platform {
+        ArchivePlatform::Ktf => "ktf",
+        ArchivePlatform::Lgt => "lgt",
+        ArchivePlatform::Skt => "skt",
+        ArchivePlatform::J2me => "j2me",
+    };
 
-        let files = extract_zip(&buf)?;
+    let profile = select_profile(&archive.id(), profile, default_profile)?;
+    tracing::info!("Using handset profile {}", profile.name);
 
-        if KtfArchive::is_ktf_archive(&files) {
-            Box::new(KtfArchive::from_zip(files)?)
-        } else if LgtArchive::is_lgt_archive(&files) {
-            Box::new(LgtArchive::from_zip(files)?)
-        } else if SktArchive::is_skt_archive(&files) {
-            Box::new(SktArchive::from_zip(files)?)
-        } else {
-            anyhow::bail!("Unknown archive format");
-        }
-    } else if filename.ends_with("jad") {
-        let jad = fs::read(filename)?;
+    let resource_overlay = load_overlay(&archive.id(), overlay)?;
+
+    let native = matches!(archive.metadata().platform, ArchivePlatform::Ktf | ArchivePlatform::Lgt);
+
+    let debug_connection = if let Some(port) = gdb {
+        anyhow::ensure!(native, "--gdb is only supported for native (KTF, LGT) apps");
+
+        Some(Box::new(TcpDebugConnection::listen(port)?) as Box<dyn DebugConnection>)
+    } else {
+        None
+    };
+
+    let instruction_trace = if let Some(path) = trace {
+        anyhow::ensure!(native, "--trace is only supported for native (KTF, LGT) apps");
+
+        Some(Box::new(InstructionTraceFile::create(path)?) as Box<dyn fmt::Write>)
+    } else {
+        None
+    };
 
-        let jar_filename = filename.replace(".jad", ".jar");
-        let jar = fs::read(jar_filename)?;
+    let native_engine = if let Some(engine) = engine {
+        anyhow::ensure!(native, "--engine is only supported for native (KTF, LGT) apps");
 
-        Box::new(J2MEArchive::from_jad_jar(jad, jar))
+        Some(engine.parse::<NativeEngine>()?)
     } else {
-        anyhow::bail!("Unknown file format");
+        None
     };
 
-    let window = WindowImpl::new(240, 320).unwrap(); // TODO hardcoded size
-    let platform = WieCliPlatform::new(&archive.id(), Box::new(window.handle()));
+    let window = WindowImpl::new(profile.screen_width, profile.screen_height)?;
+    let platform = WieCliPlatform::new(
+        &archive.id(),
+        Box::new(window.handle()),
+        profile,
+        resource_overlay,
+        debug_connection,
+        instruction_trace,
+        native_engine,
+    );
 
     let mut app = archive.load_app(Box::new(platform))?;
 
     app.start()?;
 
+    let window_handle = window.handle();
+    let next_update = window.next_update();
+    let mut lifecycle = Lifecycle::default();
+
     let mut key_events = HashSet::new();
     window.run(move |event| {
         match event {
-            WindowCallbackEvent::Update => app.tick().map_err(|x| anyhow::anyhow!("{}\n{}", x, app.crash_dump()))?,
+            WindowCallbackEvent::Update => {
+                app.tick().map_err(|x| anyhow::anyhow!("{}\n{}", x, app.crash_dump()))?;
+                next_update.set(app.next_wakeup().map(to_host_instant));
+
+                if lifecycle.should_exit(app.as_ref()) {
+                    log_leak_report(app.as_ref());
+                    window_handle.exit()?;
+                }
+            }
             WindowCallbackEvent::Redraw => app.on_event(Event::Redraw),
+            WindowCallbackEvent::CloseRequested => {
+                if lifecycle.on_close_requested(app.as_mut()) {
+                    log_leak_report(app.as_ref());
+                    window_handle.exit()?;
+                }
+            }
+            WindowCallbackEvent::Focused(x) => lifecycle.on_focus(app.as_mut(), x),
             WindowCallbackEvent::Keydown(x) => {
-                if let Some(keycode) = convert_key(x) {
+                if lifecycle.on_key(app.as_mut(), x, true) {
+                    return Ok(());
+                }
+
+                if let Some(keycode) = keymap.convert(x) {
                     if !key_events.contains(&keycode) {
                         app.on_event(Event::Keydown(keycode));
                         key_events.insert(keycode);
@@ -123,38 +359,35 @@ pub fn start(filename: &str) -> anyhow::Result<()> {
                 }
             }
             WindowCallbackEvent::Keyup(x) => {
-                if let Some(keycode) = convert_key(x) {
+                if lifecycle.on_key(app.as_mut(), x, false) {
+                    return Ok(());
+                }
+
+                if let Some(keycode) = keymap.convert(x) {
                     if key_events.contains(&keycode) {
                         key_events.remove(&keycode);
                     }
                     app.on_event(Event::Keyup(keycode));
                 }
             }
+            WindowCallbackEvent::Pointerdown(x, y) => app.on_event(Event::Pointerdown(x, y)),
+            WindowCallbackEvent::Pointerdrag(x, y) => app.on_event(Event::Pointerdrag(x, y)),
+            WindowCallbackEvent::Pointerup(x, y) => app.on_event(Event::Pointerup(x, y)),
         }
 
         anyhow::Ok(())
     })
 }
 
-fn convert_key(key: PhysicalKey) -> Option<KeyCode> {
-    match key {
-        PhysicalKey::Code(WinitKeyCode::Digit1) => Some(KeyCode::NUM1),
-        PhysicalKey::Code(WinitKeyCode::Digit2) => Some(KeyCode::NUM2),
-        PhysicalKey::Code(WinitKeyCode::Digit3) => Some(KeyCode::NUM3),
-        PhysicalKey::Code(WinitKeyCode::KeyQ) => Some(KeyCode::NUM4),
-        PhysicalKey::Code(WinitKeyCode::KeyW) => Some(KeyCode::NUM5),
-        PhysicalKey::Code(WinitKeyCode::KeyE) => Some(KeyCode::NUM6),
-        PhysicalKey::Code(WinitKeyCode::KeyA) => Some(KeyCode::NUM7),
-        PhysicalKey::Code(WinitKeyCode::KeyS) => Some(KeyCode::NUM8),
-        PhysicalKey::Code(WinitKeyCode::KeyD) => Some(KeyCode::NUM9),
-        PhysicalKey::Code(WinitKeyCode::KeyZ) => Some(KeyCode::STAR),
-        PhysicalKey::Code(WinitKeyCode::KeyX) => Some(KeyCode::NUM0),
-        PhysicalKey::Code(WinitKeyCode::KeyC) => Some(KeyCode::HASH),
-        PhysicalKey::Code(WinitKeyCode::Space) => Some(KeyCode::OK),
-        PhysicalKey::Code(WinitKeyCode::ArrowUp) => Some(KeyCode::UP),
-        PhysicalKey::Code(WinitKeyCode::ArrowDown) => Some(KeyCode::DOWN),
-        PhysicalKey::Code(WinitKeyCode::ArrowLeft) => Some(KeyCode::LEFT),
-        PhysicalKey::Code(WinitKeyCode::ArrowRight) => Some(KeyCode::RIGHT),
-        _ => None,
+// backend instants are epoch milliseconds, see WieCliPlatform::now
+fn to_host_instant(instant: Instant) -> std::time::Instant {
+    let time = UNIX_EPOCH + Duration::from_millis(instant.raw());
+
+    std::time::Instant::now() + time.duration_since(SystemTime::now()).unwrap_or_default()
+}
+
+fn log_leak_report(app: &dyn App) {
+    if let Some(report) = app.leak_report() {
+        tracing::info!("{}", report);
     }
 }
@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use directories::ProjectDirs;
+
+use wie_backend::ArchiveFiles;
+
+use crate::archive::read_directory;
+
+// Overlay is selected by, in order: `dir` given on the command line and `<config dir>/overlays/<app id>/`.
+// Files in it replace or add to the app's resources, using the path relative to the overlay directory.
+pub fn load_overlay(app_id: &str, dir: Option<&str>) -> anyhow::Result<ArchiveFiles> {
+    let path = if let Some(dir) = dir {
+        let path = Path::new(dir).to_owned();
+        anyhow::ensure!(path.is_dir(), "Overlay {} is not a directory", dir);
+
+        path
+    } else {
+        let base_dir = ProjectDirs::from("net", "dlunch", "wie").unwrap();
+        let path = base_dir.config_dir().join("overlays").join(app_id);
+        if !path.is_dir() {
+            return Ok(ArchiveFiles::new());
+        }
+
+        path
+    };
+
+    let files = read_directory(&path)?;
+
+    tracing::info!("Using overlay {:?} with {} files", path, files.len());
+
+    Ok(files)
+}
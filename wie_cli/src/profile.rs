@@ -0,0 +1,107 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use directories::ProjectDirs;
+
+use wie_backend::HandsetProfile;
+
+// `spec` is either a preset name or a path to a toml or json profile file
+pub fn load_profile(spec: &str) -> anyhow::Result<HandsetProfile> {
+    if let Some(x) = HandsetProfile::preset(spec) {
+        return Ok(x);
+    }
+
+    let path = Path::new(spec);
+    anyhow::ensure!(
+        path.is_file(),
+        "Unknown profile {}, available presets: {}",
+        spec,
+        HandsetProfile::PRESETS.join(", ")
+    );
+
+    load_profile_file(path)
+}
+
+// Profile is selected by, in order: `spec` given on the command line,
+// `<config dir>/profiles/<app id>.toml` (or `.json`), and the preset for the app's platform.
+pub fn select_profile(app_id: &str, spec: Option<&str>, default_preset: &str) -> anyhow::Result<HandsetProfile> {
+    if let Some(spec) = spec {
+        return load_profile(spec);
+    }
+
+    let base_dir = ProjectDirs::from("net", "dlunch", "wie").unwrap();
+    let profile_dir = base_dir.config_dir().join("profiles");
+    for extension in ["toml", "json"] {
+        let path = profile_dir.join(format!("{}.{}", app_id, extension));
+        if path.is_file() {
+            tracing::info!("Using profile {:?} for {}", path, app_id);
+
+            return load_profile_file(&path);
+        }
+    }
+
+    Ok(HandsetProfile::preset(default_preset).unwrap())
+}
+
+fn load_profile_file(path: &Path) -> anyhow::Result<HandsetProfile> {
+    let data = fs::read_to_string(path)?;
+
+    let profile = if path.extension().is_some_and(|x| x == "json") {
+        serde_json::from_str(&data).with_context(|| format!("Invalid profile {:?}", path))?
+    } else {
+        toml::from_str(&data).with_context(|| format!("Invalid profile {:?}", path))?
+    };
+
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::load_profile;
+
+    #[test]
+    fn test_load_preset() -> anyhow::Result<()> {
+        let profile = load_profile("lgt")?;
+
+        assert_eq!(profile.name, "lgt");
+        assert!(load_profile("unknown").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_profile_file() -> anyhow::Result<()> {
+        let dir = env::temp_dir().join(format!("wie_test_profile_{}", process::id()));
+        fs::create_dir_all(&dir)?;
+
+        let toml_path = dir.join("test.toml");
+        fs::write(
+            &toml_path,
+            "name = \"test\"\nscreen_width = 128\nscreen_height = 160\nencoding = \"utf-8\"\n\n[properties]\nCARRIER = \"Test\"\n",
+        )?;
+        let profile = load_profile(toml_path.to_str().unwrap())?;
+
+        assert_eq!(profile.name, "test");
+        assert_eq!((profile.screen_width, profile.screen_height), (128, 160));
+        assert_eq!(profile.encoding.as_deref(), Some("utf-8"));
+        assert_eq!(profile.property("CARRIER").as_deref(), Some("Test"));
+        // missing fields fall back to the default preset
+        assert_eq!(profile.key_repeat_delay, 500);
+
+        let json_path = dir.join("test.json");
+        fs::write(&json_path, r#"{"name": "test", "heap_size": 4096}"#)?;
+        let profile = load_profile(json_path.to_str().unwrap())?;
+
+        assert_eq!(profile.heap_size, 4096);
+
+        let invalid_path = dir.join("invalid.toml");
+        fs::write(&invalid_path, "screen_width = \"wide\"")?;
+        assert!(load_profile(invalid_path.to_str().unwrap()).is_err());
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+}
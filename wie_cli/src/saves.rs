@@ -0,0 +1,301 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+use clap::{Subcommand, ValueEnum};
+use zip::{write::FileOptions, ZipWriter};
+
+use wie_backend::{extract_zip, Database, DatabaseRepository as _, RecordId};
+
+use crate::database::{data_dir, validate_name, DatabaseRepository};
+
+#[derive(Subcommand)]
+pub enum SavesCommand {
+    /// List apps which have save data
+    List,
+    /// Print records of an app's database, or all of its databases
+    Dump {
+        app_id: String,
+        database: Option<String>,
+        #[arg(long, value_enum, default_value_t = DumpFormat::Hex)]
+        format: DumpFormat,
+    },
+    /// Export all save data of an app into a single zip file
+    Export { app_id: String, output: String },
+    /// Import save data exported by `export`
+    Import {
+        input: String,
+        /// Import into this app id instead of the one stored in the file
+        #[arg(long)]
+        app_id: Option<String>,
+    },
+    /// Delete all save data of an app
+    Wipe { app_id: String },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DumpFormat {
+    Hex,
+    Json,
+}
+
+pub fn run(command: SavesCommand) -> anyhow::Result<()> {
+    let data_dir = data_dir();
+
+    match command {
+        SavesCommand::List => list(&data_dir),
+        SavesCommand::Dump { app_id, database, format } => dump(&data_dir, &app_id, database.as_deref(), format),
+        SavesCommand::Export { app_id, output } => export(&data_dir, &app_id, &output),
+        SavesCommand::Import { input, app_id } => import(&data_dir, &input, app_id.as_deref()),
+        SavesCommand::Wipe { app_id } => wipe(&data_dir, &app_id),
+    }
+}
+
+fn list(data_dir: &Path) -> anyhow::Result<()> {
+    if !data_dir.is_dir() {
+        return Ok(());
+    }
+
+    let mut app_ids = fs::read_dir(data_dir)?
+        .filter_map(|x| x.ok())
+        .filter(|x| x.path().is_dir())
+        .filter_map(|x| x.file_name().to_str().map(|x| x.to_owned()))
+        .collect::<Vec<_>>();
+    app_ids.sort();
+
+    for app_id in app_ids {
+        let databases = DatabaseRepository::with_data_dir(data_dir, &app_id).list()?;
+
+        println!("{}: {}", app_id, databases.join(", "));
+    }
+
+    Ok(())
+}
+
+fn dump(data_dir: &Path, app_id: &str, database: Option<&str>, format: DumpFormat) -> anyhow::Result<()> {
+    let repository = DatabaseRepository::with_data_dir(data_dir, app_id);
+
+    let names = if let Some(database) = database {
+        vec![database.to_owned()]
+    } else {
+        repository.list()?
+    };
+
+    let mut databases = Vec::with_capacity(names.len());
+    for name in names {
+        let database = repository.open(&name, false)?;
+        let records = read_records(&*database)?;
+
+        databases.push((name, records));
+    }
+
+    match format {
+        DumpFormat::Hex => {
+            for (name, records) in databases {
+                println!("{}:", name);
+
+                for (id, data) in records {
+                    println!("  record {} ({} bytes)", id, data.len());
+                    for (i, line) in data.chunks(16).enumerate() {
+                        let hex = line.iter().map(|x| format!("{:02x}", x)).collect::<Vec<_>>().join(" ");
+                        let ascii = line
+                            .iter()
+                            .map(|&x| if x.is_ascii_graphic() || x == b' ' { x as char } else { '.' })
+                            .collect::<String>();
+
+                        println!("    {:08x}: {:<47}  {}", i * 16, hex, ascii);
+                    }
+                }
+            }
+        }
+        DumpFormat::Json => {
+            let value = databases
+                .into_iter()
+                .map(|(name, records)| {
+                    let records = records
+                        .into_iter()
+                        .map(|(id, data)| serde_json::json!({ "id": id, "data": to_hex(&data) }))
+                        .collect::<Vec<_>>();
+
+                    (name, serde_json::Value::Array(records))
+                })
+                .collect::<serde_json::Map<_, _>>();
+
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        }
+    }
+
+    Ok(())
+}
+
+// archive layout: `<app_id>/<database name>/<record id>`
+fn export(data_dir: &Path, app_id: &str, output: &str) -> anyhow::Result<()> {
+    let repository = DatabaseRepository::with_data_dir(data_dir, app_id);
+    anyhow::ensure!(repository.path()?.is_dir(), "No save data for {}", app_id);
+
+    let mut writer = ZipWriter::new(File::create(output)?);
+
+    let mut count = 0;
+    for name in repository.list()? {
+        let database = repository.open(&name, false)?;
+
+        for (id, data) in read_records(&*database)? {
+            writer.start_file(format!("{}/{}/{}", app_id, name, id), FileOptions::default())?;
+            writer.write_all(&data)?;
+
+            count += 1;
+        }
+    }
+
+    writer.finish()?;
+
+    println!("Exported {} records of {} to {}", count, app_id, output);
+
+    Ok(())
+}
+
+fn import(data_dir: &Path, input: &str, app_id_override: Option<&str>) -> anyhow::Result<()> {
+    let files = extract_zip(&fs::read(input)?)?;
+
+    // entry paths come from the file, so check all of them before writing anything
+    let mut records = Vec::with_capacity(files.len());
+    for (path, data) in &files {
+        let parts = path.split('/').collect::<Vec<_>>();
+        if parts.len() != 3 || parts[2].is_empty() {
+            continue;
+        }
+
+        let record_id: RecordId = validate_name(parts[0])
+            .and(validate_name(parts[1]))
+            .ok()
+            .and_then(|_| parts[2].parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("Invalid record path {}", path))?;
+        let app_id = app_id_override.unwrap_or(parts[0]);
+
+        records.push((app_id, parts[1], record_id, data));
+    }
+
+    for &(app_id, name, record_id, data) in &records {
+        let mut database = DatabaseRepository::with_data_dir(data_dir, app_id).open(name, true)?;
+        database.set(record_id, data)?;
+    }
+
+    println!("Imported {} records from {}", records.len(), input);
+
+    Ok(())
+}
+
+fn wipe(data_dir: &Path, app_id: &str) -> anyhow::Result<()> {
+    let repository = DatabaseRepository::with_data_dir(data_dir, app_id);
+    if !repository.path()?.is_dir() {
+        println!("No save data for {}", app_id);
+
+        return Ok(());
+    }
+
+    fs::remove_dir_all(repository.path()?)?;
+
+    println!("Deleted save data of {}", app_id);
+
+    Ok(())
+}
+
+fn read_records(database: &dyn Database) -> anyhow::Result<Vec<(RecordId, Vec<u8>)>> {
+    database.get_record_ids()?.into_iter().map(|id| Ok((id, database.get(id)?))).collect()
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        fs::{self, File},
+        io::Write,
+        path::PathBuf,
+        process,
+    };
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use wie_backend::{Database as _, DatabaseRepository as _};
+
+    use crate::database::DatabaseRepository;
+
+    use super::{export, import, read_records};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("wie_test_saves_{}_{}", name, process::id()));
+        fs::create_dir_all(&path).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn test_export_import() -> anyhow::Result<()> {
+        let (source, target, files) = (temp_dir("source"), temp_dir("target"), temp_dir("files"));
+        let output = files.join("saves.zip");
+        let output = output.to_str().unwrap();
+
+        let repository = DatabaseRepository::with_data_dir(&source, "app");
+        let mut database = repository.open("save", true)?;
+        database.add(&[1, 2, 3])?;
+        database.add(&[4, 5])?;
+        repository.open("settings", true)?.add(&[6])?;
+
+        export(&source, "app", output)?;
+        import(&target, output, None)?;
+        import(&target, output, Some("other"))?;
+
+        for app_id in ["app", "other"] {
+            let repository = DatabaseRepository::with_data_dir(&target, app_id);
+
+            assert_eq!(repository.list()?, ["save", "settings"]);
+            assert_eq!(read_records(&*repository.open("save", false)?)?, [(0, vec![1, 2, 3]), (1, vec![4, 5])]);
+            assert_eq!(read_records(&*repository.open("settings", false)?)?, [(0, vec![6])]);
+        }
+
+        for path in [source, target, files] {
+            fs::remove_dir_all(path)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_rejects_escaping_paths() -> anyhow::Result<()> {
+        let (target, files) = (temp_dir("escape_target"), temp_dir("escape_files"));
+
+        for (i, entry) in ["../save/1", "app/../1", "app/..\\x/1", "C:/save/1"].into_iter().enumerate() {
+            let input = files.join(format!("{}.zip", i));
+
+            let mut writer = ZipWriter::new(File::create(&input)?);
+            writer.start_file(entry, FileOptions::default())?;
+            writer.write_all(&[1])?;
+            writer.finish()?;
+
+            assert!(import(&target, input.to_str().unwrap(), None).is_err(), "{}", entry);
+        }
+
+        // not `<app_id>/<database>/<record>`, so skipped
+        let input = files.join("skipped.zip");
+        let mut writer = ZipWriter::new(File::create(&input)?);
+        writer.start_file("../../x/1", FileOptions::default())?;
+        writer.write_all(&[1])?;
+        writer.finish()?;
+        import(&target, input.to_str().unwrap(), None)?;
+
+        assert_eq!(fs::read_dir(&target)?.count(), 0);
+        assert!(!files.parent().unwrap().join("x").exists());
+
+        for path in [target, files] {
+            fs::remove_dir_all(path)?;
+        }
+
+        Ok(())
+    }
+}
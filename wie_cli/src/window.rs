@@ -1,10 +1,10 @@
 use alloc::rc::Rc;
-use core::{fmt::Debug, num::NonZeroU32};
+use core::{cell::Cell, fmt::Debug, num::NonZeroU32};
 
 use softbuffer::{Context, Surface};
 use winit::{
-    dpi::PhysicalSize,
-    event::{ElementState, Event, KeyEvent, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, Event, KeyEvent, MouseButton, WindowEvent},
     event_loop::{ControlFlow, EventLoop, EventLoopBuilder, EventLoopProxy, EventLoopWindowTarget},
     keyboard::PhysicalKey,
     window::{Window as WinitWindow, WindowBuilder},
@@ -16,13 +16,19 @@ use wie_backend::{canvas::Image, Screen};
 pub enum WindowInternalEvent {
     RequestRedraw,
     Paint(Vec<u32>),
+    Exit,
 }
 
 pub enum WindowCallbackEvent {
     Update,
     Redraw,
+    CloseRequested,
+    Focused(bool),
     Keydown(PhysicalKey),
     Keyup(PhysicalKey),
+    Pointerdown(i32, i32),
+    Pointerdrag(i32, i32),
+    Pointerup(i32, i32),
 }
 
 pub struct WindowHandle {
@@ -37,6 +43,10 @@ impl WindowHandle {
 
         Ok(())
     }
+
+    pub fn exit(&self) -> anyhow::Result<()> {
+        self.send_event(WindowInternalEvent::Exit)
+    }
 }
 
 impl Screen for WindowHandle {
@@ -64,8 +74,12 @@ impl Screen for WindowHandle {
 }
 
 pub struct WindowImpl {
+    width: u32,
+    height: u32,
     window: Rc<WinitWindow>,
     event_loop: EventLoop<WindowInternalEvent>,
+    #[cfg(not(target_arch = "wasm32"))]
+    next_update: Rc<Cell<Option<std::time::Instant>>>,
 }
 
 impl WindowImpl {
@@ -79,8 +93,12 @@ impl WindowImpl {
         let window = builder.build(&event_loop)?;
 
         Ok(Self {
+            width,
+            height,
             window: Rc::new(window),
             event_loop,
+            #[cfg(not(target_arch = "wasm32"))]
+            next_update: Rc::new(Cell::new(None)),
         })
     }
 
@@ -92,6 +110,12 @@ impl WindowImpl {
         }
     }
 
+    /// Set by the `Update` callback to when it should be called again without any event, `None` to wait for the next event
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn next_update(&self) -> Rc<Cell<Option<std::time::Instant>>> {
+        self.next_update.clone()
+    }
+
     fn callback<C, E>(event: WindowCallbackEvent, elwt: &EventLoopWindowTarget<WindowInternalEvent>, callback: &mut C)
     where
         C: FnMut(WindowCallbackEvent) -> Result<(), E> + 'static,
@@ -105,6 +129,16 @@ impl WindowImpl {
         }
     }
 
+    // window can be resized or scaled, so we convert window coordinates into screen coordinates
+    fn to_screen_position(window: &WinitWindow, (width, height): (u32, u32), position: PhysicalPosition<f64>) -> (i32, i32) {
+        let size = window.inner_size();
+
+        let x = position.x * width as f64 / size.width.max(1) as f64;
+        let y = position.y * height as f64 / size.height.max(1) as f64;
+
+        (x as i32, y as i32)
+    }
+
     pub fn run<C, E>(self, mut callback: C) -> anyhow::Result<()>
     where
         C: FnMut(WindowCallbackEvent) -> Result<(), E> + 'static,
@@ -119,8 +153,9 @@ impl WindowImpl {
             .resize(NonZeroU32::new(size.width).unwrap(), NonZeroU32::new(size.height).unwrap())
             .unwrap();
 
-        #[cfg(not(target_arch = "wasm32"))]
-        let mut last_update = std::time::Instant::now();
+        let screen_size = (self.width, self.height);
+        let mut cursor_position = PhysicalPosition::new(0.0, 0.0);
+        let mut pointer_pressed = false;
 
         self.event_loop.run(move |event, elwt| match event {
             Event::UserEvent(x) => match x {
@@ -133,10 +168,16 @@ impl WindowImpl {
 
                     buffer.present().unwrap();
                 }
+                WindowInternalEvent::Exit => elwt.exit(),
             },
 
             Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => elwt.exit(),
+                WindowEvent::CloseRequested => {
+                    Self::callback(WindowCallbackEvent::CloseRequested, elwt, &mut callback);
+                }
+                WindowEvent::Focused(x) => {
+                    Self::callback(WindowCallbackEvent::Focused(x), elwt, &mut callback);
+                }
                 WindowEvent::KeyboardInput {
                     event:
                         KeyEvent {
@@ -159,6 +200,30 @@ impl WindowImpl {
                 } => {
                     Self::callback(WindowCallbackEvent::Keyup(physical_key), elwt, &mut callback);
                 }
+                WindowEvent::CursorMoved { position, .. } => {
+                    cursor_position = position;
+
+                    if pointer_pressed {
+                        let (x, y) = Self::to_screen_position(&self.window, screen_size, cursor_position);
+                        Self::callback(WindowCallbackEvent::Pointerdrag(x, y), elwt, &mut callback);
+                    }
+                }
+                WindowEvent::MouseInput {
+                    state,
+                    button: MouseButton::Left,
+                    ..
+                } => {
+                    let (x, y) = Self::to_screen_position(&self.window, screen_size, cursor_position);
+
+                    pointer_pressed = state == ElementState::Pressed;
+                    let event = if pointer_pressed {
+                        WindowCallbackEvent::Pointerdown(x, y)
+                    } else {
+                        WindowCallbackEvent::Pointerup(x, y)
+                    };
+
+                    Self::callback(event, elwt, &mut callback);
+                }
                 WindowEvent::RedrawRequested => {
                     Self::callback(WindowCallbackEvent::Redraw, elwt, &mut callback);
                 }
@@ -170,19 +235,16 @@ impl WindowImpl {
                     Self::callback(WindowCallbackEvent::Update, elwt, &mut callback);
                     elwt.set_control_flow(ControlFlow::Wait);
                 }
+                // sent after each batch of events, so the app handles them in the same iteration
                 #[cfg(not(target_arch = "wasm32"))]
                 {
-                    let now = std::time::Instant::now();
-                    let next_update = last_update + std::time::Duration::from_millis(16);
-                    if now < next_update {
-                        elwt.set_control_flow(ControlFlow::WaitUntil(next_update));
-                    } else {
-                        Self::callback(WindowCallbackEvent::Update, elwt, &mut callback);
+                    Self::callback(WindowCallbackEvent::Update, elwt, &mut callback);
 
-                        last_update = now;
-                        let next_update = last_update + std::time::Duration::from_millis(16);
-                        elwt.set_control_flow(ControlFlow::WaitUntil(next_update));
-                    }
+                    let control_flow = match self.next_update.get() {
+                        Some(x) => ControlFlow::WaitUntil(x),
+                        None => ControlFlow::Wait,
+                    };
+                    elwt.set_control_flow(control_flow);
                 }
             }
             _ => {}
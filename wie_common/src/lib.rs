@@ -4,51 +4,92 @@ extern crate alloc;
 
 pub mod util;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-pub enum KeyCode {
-    UP,
-    DOWN,
-    LEFT,
-    RIGHT,
-    OK,
-
-    NUM0,
-    NUM1,
-    NUM2,
-    NUM3,
-    NUM4,
-    NUM5,
-    NUM6,
-    NUM7,
-    NUM8,
-    NUM9,
-    HASH,
-    STAR,
+// defines `KeyCode` with `ALL` and `raw` generated from one list of variants
+macro_rules! key_codes {
+    ($($name:ident = $raw:literal,)*) => {
+        #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+        pub enum KeyCode {
+            $($name,)*
+        }
+
+        impl KeyCode {
+            pub const ALL: &'static [KeyCode] = &[$(KeyCode::$name,)*];
+
+            /// Key code given to apps. Values are `MH_KEY_*` of WIPI C, which WIPI Java and most MIDP handsets share
+            pub const fn raw(self) -> i32 {
+                match self {
+                    $(KeyCode::$name => $raw,)*
+                }
+            }
+        }
+    };
+}
+
+key_codes! {
+    UP = -1,
+    DOWN = -2,
+    LEFT = -3,
+    RIGHT = -4,
+    OK = -5,
+
+    NUM0 = 48,
+    NUM1 = 49,
+    NUM2 = 50,
+    NUM3 = 51,
+    NUM4 = 52,
+    NUM5 = 53,
+    NUM6 = 54,
+    NUM7 = 55,
+    NUM8 = 56,
+    NUM9 = 57,
+    HASH = 35,
+    STAR = 42,
+
+    LSK = -6, // left soft key
+    RSK = -7, // right soft key
+    CLEAR = -8,
+    SEND = -10,
+    END = -11,
+    VOLUMEUP = -13,
+    VOLUMEDOWN = -14,
+    SIDE = -15,
 }
 
 impl KeyCode {
+    pub fn from_raw(raw: i32) -> Option<KeyCode> {
+        Self::ALL.iter().copied().find(|x| x.raw() == raw)
+    }
+
     // TODO we can use libraries like strum
-    pub fn parse(string: &str) -> KeyCode {
-        match string {
+    pub fn parse(string: &str) -> Option<KeyCode> {
+        Some(match string {
             "UP" => KeyCode::UP,
             "DOWN" => KeyCode::DOWN,
             "LEFT" => KeyCode::LEFT,
             "RIGHT" => KeyCode::RIGHT,
             "OK" => KeyCode::OK,
-            "0" => KeyCode::NUM0,
-            "1" => KeyCode::NUM1,
-            "2" => KeyCode::NUM2,
-            "3" => KeyCode::NUM3,
-            "4" => KeyCode::NUM4,
-            "5" => KeyCode::NUM5,
-            "6" => KeyCode::NUM6,
-            "7" => KeyCode::NUM7,
-            "8" => KeyCode::NUM8,
-            "9" => KeyCode::NUM9,
-            "#" => KeyCode::HASH,
-            "*" => KeyCode::STAR,
-            _ => panic!("Unknown key: {}", string),
-        }
+            "0" | "NUM0" => KeyCode::NUM0,
+            "1" | "NUM1" => KeyCode::NUM1,
+            "2" | "NUM2" => KeyCode::NUM2,
+            "3" | "NUM3" => KeyCode::NUM3,
+            "4" | "NUM4" => KeyCode::NUM4,
+            "5" | "NUM5" => KeyCode::NUM5,
+            "6" | "NUM6" => KeyCode::NUM6,
+            "7" | "NUM7" => KeyCode::NUM7,
+            "8" | "NUM8" => KeyCode::NUM8,
+            "9" | "NUM9" => KeyCode::NUM9,
+            "#" | "HASH" => KeyCode::HASH,
+            "*" | "STAR" => KeyCode::STAR,
+            "LSK" => KeyCode::LSK,
+            "RSK" => KeyCode::RSK,
+            "CLEAR" | "CLR" => KeyCode::CLEAR,
+            "SEND" => KeyCode::SEND,
+            "END" => KeyCode::END,
+            "VOLUMEUP" | "VOLUME_UP" => KeyCode::VOLUMEUP,
+            "VOLUMEDOWN" | "VOLUME_DOWN" => KeyCode::VOLUMEDOWN,
+            "SIDE" => KeyCode::SIDE,
+            _ => return None,
+        })
     }
 }
 
@@ -57,4 +98,47 @@ pub enum Event {
     Redraw,
     Keydown(KeyCode),
     Keyup(KeyCode),
+    Keyrepeat(KeyCode), // generated by backend while a key is held down
+    // pointer events are in screen coordinates
+    Pointerdown(i32, i32),
+    Pointerdrag(i32, i32),
+    Pointerup(i32, i32),
+    // lifecycle events, app should save its state on `Pause` and `Exit`
+    Pause,
+    Resume,
+    Exit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyCode;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(KeyCode::parse("LSK"), Some(KeyCode::LSK));
+        assert_eq!(KeyCode::parse("5"), Some(KeyCode::NUM5));
+        assert_eq!(KeyCode::parse("#"), Some(KeyCode::HASH));
+        assert_eq!(KeyCode::parse("CLR"), Some(KeyCode::CLEAR));
+        assert_eq!(KeyCode::parse("VOLUME_UP"), Some(KeyCode::VOLUMEUP));
+
+        assert_eq!(KeyCode::parse("lsk"), None);
+        assert_eq!(KeyCode::parse("10"), None);
+        assert_eq!(KeyCode::parse(""), None);
+
+        // every key can be parsed by its name
+        for &key in KeyCode::ALL {
+            assert_eq!(KeyCode::parse(&alloc::format!("{:?}", key)), Some(key));
+        }
+    }
+
+    #[test]
+    fn test_raw() {
+        for &key in KeyCode::ALL {
+            assert_eq!(KeyCode::from_raw(key.raw()), Some(key));
+        }
+
+        assert_eq!(KeyCode::OK.raw(), -5);
+        assert_eq!(KeyCode::STAR.raw(), '*' as i32);
+        assert_eq!(KeyCode::from_raw(0), None);
+    }
 }
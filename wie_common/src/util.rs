@@ -35,6 +35,15 @@ where
 }
 
 pub fn read_null_terminated_string<R>(reader: &R, address: u32) -> anyhow::Result<String>
+where
+    R: ?Sized + ByteRead,
+{
+    let result = read_null_terminated_bytes(reader, address)?;
+
+    Ok(String::from_utf8(result)?)
+}
+
+pub fn read_null_terminated_bytes<R>(reader: &R, address: u32) -> anyhow::Result<Vec<u8>>
 where
     R: ?Sized + ByteRead,
 {
@@ -55,7 +64,7 @@ where
 
     // tracing::trace!("Read address: {:#x}, data: {:02x?}", address, result);
 
-    Ok(String::from_utf8(result)?)
+    Ok(result)
 }
 
 pub fn write_null_terminated_string<W>(writer: &mut W, address: u32, string: &str) -> anyhow::Result<()>
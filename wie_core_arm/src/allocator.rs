@@ -1,102 +1,279 @@
-use alloc::format;
-use core::mem::size_of;
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::String,
+    vec::Vec,
+};
+use core::{cell::RefMut, fmt::Write};
 
 use anyhow::Context;
-use bytemuck::{Pod, Zeroable};
 
-use wie_common::util::{read_generic, round_up, write_generic};
+use wie_common::util::{round_up, ByteRead, ByteWrite};
 
 use crate::core::{ArmCore, HEAP_BASE};
 
-const HEAP_SIZE: u32 = 0x1000000;
+const ALIGNMENT: usize = 8;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AllocatorStats {
+    /// Size of the heap in bytes
+    pub total: u32,
+    /// Bytes in live allocations
+    pub used: u32,
+    /// Highest `used` so far
+    pub peak: u32,
+    /// Number of live allocations
+    pub allocations: u32,
+}
+
+struct Allocation {
+    size: u32,
+    call_site: u32,
+}
 
-#[derive(Clone, Copy, Pod, Zeroable)]
-#[repr(C)]
-struct AllocationHeader {
-    data: u32,
+#[derive(Default)]
+struct CallSite {
+    count: u32,
+    live_count: u32,
+    live_bytes: u32,
 }
 
-impl AllocationHeader {
-    pub fn new(size: u32, in_use: bool) -> Self {
-        Self {
-            data: size | (in_use as u32) << 31,
+// heap metadata is kept on the host, so emulated memory only holds allocated data
+pub(crate) struct Heap {
+    size: u32,
+    // address -> size
+    free_by_address: BTreeMap<u32, u32>,
+    // (size, address), for best fit
+    free_by_size: BTreeSet<(u32, u32)>,
+    allocations: BTreeMap<u32, Allocation>,
+    // keyed by LR at allocation
+    call_sites: BTreeMap<u32, CallSite>,
+    used: u32,
+    peak: u32,
+}
+
+impl Heap {
+    fn new(base: u32, size: u32) -> Self {
+        let mut heap = Self {
+            size,
+            free_by_address: BTreeMap::new(),
+            free_by_size: BTreeSet::new(),
+            allocations: BTreeMap::new(),
+            call_sites: BTreeMap::new(),
+            used: 0,
+            peak: 0,
+        };
+        heap.insert_free(base, size);
+
+        heap
+    }
+
+    fn alloc(&mut self, size: u32, call_site: u32) -> Option<u32> {
+        let size = Self::aligned_size(size);
+
+        let &(free_size, address) = self.free_by_size.range((size, 0)..).next()?;
+        self.remove_free(address, free_size);
+        if free_size > size {
+            self.insert_free(address + size, free_size - size);
         }
+
+        self.allocations.insert(address, Allocation { size, call_site });
+        self.update_usage(call_site, 0, size);
+
+        let call_site = self.call_sites.entry(call_site).or_default();
+        call_site.count += 1;
+        call_site.live_count += 1;
+
+        Some(address)
+    }
+
+    fn free(&mut self, address: u32) -> Option<()> {
+        let allocation = self.allocations.remove(&address)?;
+
+        self.update_usage(allocation.call_site, allocation.size, 0);
+        self.call_sites.get_mut(&allocation.call_site).unwrap().live_count -= 1;
+
+        // merge with adjacent free blocks
+        let mut start = address;
+        let mut size = allocation.size;
+
+        if let Some((&previous, &previous_size)) = self.free_by_address.range(..address).next_back() {
+            if previous + previous_size == address {
+                self.remove_free(previous, previous_size);
+                start = previous;
+                size += previous_size;
+            }
+        }
+
+        let end = address + allocation.size;
+        if let Some(&next_size) = self.free_by_address.get(&end) {
+            self.remove_free(end, next_size);
+            size += next_size;
+        }
+
+        self.insert_free(start, size);
+
+        Some(())
+    }
+
+    // returns false if the allocation can't grow in place
+    fn resize(&mut self, address: u32, size: u32) -> Option<bool> {
+        let size = Self::aligned_size(size);
+        let allocation = self.allocations.get(&address)?;
+        let (old_size, call_site) = (allocation.size, allocation.call_site);
+
+        let end = address + old_size;
+        let next_size = self.free_by_address.get(&end).copied().unwrap_or(0);
+        if size > old_size + next_size {
+            return Some(false);
+        }
+
+        if next_size != 0 {
+            self.remove_free(end, next_size);
+        }
+        if old_size + next_size > size {
+            self.insert_free(address + size, old_size + next_size - size);
+        }
+
+        self.allocations.get_mut(&address).unwrap().size = size;
+        self.update_usage(call_site, old_size, size);
+
+        Some(true)
+    }
+
+    fn size_of(&self, address: u32) -> Option<u32> {
+        self.allocations.get(&address).map(|x| x.size)
+    }
+
+    fn stats(&self) -> AllocatorStats {
+        AllocatorStats {
+            total: self.size,
+            used: self.used,
+            peak: self.peak,
+            allocations: self.allocations.len() as _,
+        }
+    }
+
+    fn update_usage(&mut self, call_site: u32, old_size: u32, new_size: u32) {
+        self.used = self.used - old_size + new_size;
+        self.peak = self.peak.max(self.used);
+
+        let call_site = self.call_sites.entry(call_site).or_default();
+        call_site.live_bytes = call_site.live_bytes - old_size + new_size;
+    }
+
+    fn insert_free(&mut self, address: u32, size: u32) {
+        self.free_by_address.insert(address, size);
+        self.free_by_size.insert((size, address));
     }
 
-    pub fn size(&self) -> u32 {
-        self.data & 0x7FFFFFFF
+    fn remove_free(&mut self, address: u32, size: u32) {
+        self.free_by_address.remove(&address);
+        self.free_by_size.remove(&(size, address));
     }
 
-    pub fn in_use(&self) -> bool {
-        self.data & 0x80000000 != 0
+    fn aligned_size(size: u32) -> u32 {
+        round_up(size.max(1) as usize, ALIGNMENT) as u32
     }
 }
 
-// crude, slow allocator.. we need to refactor it to faster one
 pub struct Allocator {}
 
 impl Allocator {
-    pub fn init(core: &mut ArmCore) -> anyhow::Result<(u32, u32)> {
-        core.map(HEAP_BASE, HEAP_SIZE)?;
+    pub fn init(core: &mut ArmCore, size: u32) -> anyhow::Result<(u32, u32)> {
+        let size = round_up(size as usize, 0x1000) as u32;
+        core.map(HEAP_BASE, size)?;
 
-        let header = AllocationHeader::new(HEAP_SIZE, false);
+        *core.heap() = Some(Heap::new(HEAP_BASE, size));
 
-        write_generic(core, HEAP_BASE, header)?;
-
-        Ok((HEAP_BASE, HEAP_SIZE))
+        Ok((HEAP_BASE, size))
     }
 
     pub fn alloc(core: &mut ArmCore, size: u32) -> anyhow::Result<u32> {
-        let alloc_size = round_up(size as usize + size_of::<AllocationHeader>(), 4) as u32;
+        let (_, lr) = core.read_pc_lr()?;
 
-        let address = Self::find_address(core, alloc_size).with_context(|| format!("Failed to allocate {} bytes", size))?;
+        let address = Self::heap(core)?
+            .alloc(size, lr)
+            .with_context(|| format!("Failed to allocate {} bytes", size))?;
 
-        let previous_header: AllocationHeader = read_generic(core, address)?;
+        tracing::trace!("Allocated {:#x} bytes at {:#x}", size, address);
 
-        let header = AllocationHeader::new(alloc_size, true);
-        write_generic(core, address, header)?;
+        Ok(address)
+    }
 
-        // write next
-        if previous_header.size() > alloc_size {
-            let next_header = AllocationHeader::new(previous_header.size() - alloc_size, false);
-            write_generic(core, address + alloc_size, next_header)?;
+    pub fn free(core: &mut ArmCore, address: u32) -> anyhow::Result<()> {
+        if address == 0 {
+            return Ok(());
         }
 
-        tracing::trace!("Allocated {:#x} bytes at {:#x}", size, address + size_of::<AllocationHeader>() as u32);
+        tracing::trace!("Freeing {:#x}", address);
 
-        Ok(address + size_of::<AllocationHeader>() as u32)
+        Self::heap(core)?
+            .free(address)
+            .with_context(|| format!("Freeing unallocated address {:#x}", address))
     }
 
-    pub fn free(core: &mut ArmCore, address: u32) -> anyhow::Result<()> {
-        let base_address = address - size_of::<AllocationHeader>() as u32;
+    /// Resizes an allocation, moving it if it can't grow in place. Contents are kept up to the smaller size.
+    pub fn realloc(core: &mut ArmCore, address: u32, size: u32) -> anyhow::Result<u32> {
+        if address == 0 {
+            return Self::alloc(core, size);
+        }
 
-        tracing::trace!("Freeing {:#x}", address);
+        let old_size = {
+            let mut heap = Self::heap(core)?;
+            let old_size = heap
+                .size_of(address)
+                .with_context(|| format!("Reallocating unallocated address {:#x}", address))?;
 
-        let header: AllocationHeader = read_generic(core, base_address)?;
-        assert!(header.in_use());
+            if heap.resize(address, size).unwrap() {
+                tracing::trace!("Resized {:#x} to {:#x} bytes", address, size);
 
-        let header = AllocationHeader::new(header.size(), false);
-        write_generic(core, base_address, header)?;
+                return Ok(address);
+            }
 
-        Ok(())
+            old_size
+        };
+
+        let new_address = Self::alloc(core, size)?;
+
+        let data = core.read_bytes(address, old_size.min(size))?;
+        core.write_bytes(new_address, &data)?;
+
+        Self::free(core, address)?;
+
+        Ok(new_address)
     }
 
-    fn find_address(core: &ArmCore, request_size: u32) -> Option<u32> {
-        let mut cursor = HEAP_BASE;
-        loop {
-            let header: AllocationHeader = read_generic(core, cursor).ok()?;
-            if !header.in_use() && header.size() >= request_size {
-                return Some(cursor);
-            } else {
-                cursor += header.size();
-            }
+    pub fn stats(core: &ArmCore) -> anyhow::Result<AllocatorStats> {
+        Ok(Self::heap(core)?.stats())
+    }
 
-            if cursor >= HEAP_BASE + HEAP_SIZE {
-                break;
-            }
+    /// Heap usage and live allocations grouped by call site, largest first
+    pub fn leak_report(core: &ArmCore) -> anyhow::Result<String> {
+        let heap = Self::heap(core)?;
+        let stats = heap.stats();
+
+        let mut report = format!(
+            "Heap: {} bytes in {} allocations live, peak {} of {} bytes\n",
+            stats.used, stats.allocations, stats.peak, stats.total
+        );
+
+        let mut call_sites = heap.call_sites.iter().filter(|(_, x)| x.live_count != 0).collect::<Vec<_>>();
+        call_sites.sort_by_key(|(_, x)| core::cmp::Reverse(x.live_bytes));
+
+        for (address, call_site) in call_sites {
+            writeln!(
+                report,
+                "{:#010x}: {} bytes in {} of {} allocations",
+                address, call_site.live_bytes, call_site.live_count, call_site.count
+            )?;
         }
 
-        None
+        Ok(report)
+    }
+
+    fn heap(core: &ArmCore) -> anyhow::Result<RefMut<'_, Heap>> {
+        RefMut::filter_map(core.heap(), |x| x.as_mut()).ok().context("Heap is not initialized")
     }
 }
 
@@ -104,22 +281,80 @@ impl Allocator {
 mod tests {
     use alloc::boxed::Box;
 
+    use wie_common::util::{ByteRead, ByteWrite};
+
     use crate::{Allocator, ArmCore};
 
     use test_utils::TestPlatform;
 
     pub fn test_arm_core() -> ArmCore {
-        ArmCore::new(wie_backend::System::new(Box::new(TestPlatform), Box::new(())).handle()).unwrap()
+        ArmCore::new(wie_backend::System::new(Box::new(TestPlatform::new()), Box::new(()), wie_backend::encoding::EUC_KR).handle()).unwrap()
     }
 
     #[test]
     fn test_allocator() -> anyhow::Result<()> {
         let mut core = test_arm_core();
 
-        Allocator::init(&mut core)?;
+        Allocator::init(&mut core, 0x100000)?;
         let address = Allocator::alloc(&mut core, 10)?;
 
-        assert_eq!(address, 0x40000004);
+        assert_eq!(address, 0x40000000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_free() -> anyhow::Result<()> {
+        let mut core = test_arm_core();
+
+        Allocator::init(&mut core, 0x100000)?;
+        let first = Allocator::alloc(&mut core, 0x10)?;
+        let second = Allocator::alloc(&mut core, 0x10)?;
+        Allocator::alloc(&mut core, 0x10)?;
+
+        // freed blocks are merged and reused
+        Allocator::free(&mut core, first)?;
+        Allocator::free(&mut core, second)?;
+        assert_eq!(Allocator::alloc(&mut core, 0x20)?, first);
+        assert!(Allocator::free(&mut core, second).is_err());
+
+        let stats = Allocator::stats(&core)?;
+        assert_eq!(stats.total, 0x100000);
+        assert_eq!(stats.used, 0x20 + 0x10);
+        assert_eq!(stats.peak, 0x30);
+        assert_eq!(stats.allocations, 2);
+
+        // heap is full
+        assert!(Allocator::alloc(&mut core, 0x100000).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_realloc() -> anyhow::Result<()> {
+        let mut core = test_arm_core();
+
+        Allocator::init(&mut core, 0x100000)?;
+        let first = Allocator::alloc(&mut core, 0x20)?;
+        let second = Allocator::alloc(&mut core, 0x10)?;
+
+        // shrinks in place, the rest is freed
+        assert_eq!(Allocator::realloc(&mut core, first, 0x10)?, first);
+        assert_eq!(Allocator::alloc(&mut core, 0x10)?, first + 0x10);
+
+        // grows in place into the free space after it
+        assert_eq!(Allocator::realloc(&mut core, second, 0x100)?, second);
+
+        // moves with its contents if the next chunk is allocated
+        core.write_bytes(first, &[1, 2, 3, 4])?;
+        let moved = Allocator::realloc(&mut core, first, 0x20)?;
+        assert_eq!(moved, second + 0x100);
+        assert_eq!(core.read_bytes(moved, 4)?, [1, 2, 3, 4]);
+        assert!(Allocator::free(&mut core, first).is_err());
+
+        let stats = Allocator::stats(&core)?;
+        assert_eq!(stats.used, 0x10 + 0x100 + 0x20);
+        assert_eq!(stats.allocations, 3);
 
         Ok(())
     }
@@ -22,7 +22,7 @@ pub struct ArmCoreContext {
 }
 
 impl ArmCoreContext {
-    pub fn new(stack_base: u32) -> Self {
+    pub fn new(stack_base: u32, stack_size: u32) -> Self {
         Self {
             r0: 0,
             r1: 0,
@@ -37,7 +37,7 @@ impl ArmCoreContext {
             sl: 0,
             fp: 0,
             ip: 0,
-            sp: stack_base + 0x1000,
+            sp: stack_base + stack_size,
             lr: 0,
             pc: 0,
             cpsr: 0x10, // USR32
@@ -1,14 +1,24 @@
-use alloc::{borrow::ToOwned, boxed::Box, collections::BTreeMap, format, rc::Rc, string::String, vec::Vec};
-use core::{cell::RefCell, fmt::Debug, mem::size_of};
+use alloc::{borrow::ToOwned, boxed::Box, collections::BTreeMap, format, rc::Rc, string::String, vec, vec::Vec};
+use core::{
+    cell::{RefCell, RefMut},
+    fmt::{Debug, Write},
+    mem::size_of,
+    ops::Range,
+};
 
-use wie_backend::{AsyncCallable, SystemHandle};
+use wie_backend::{AsyncCallable, JoinHandle, NativeEngine, SystemHandle};
 use wie_common::util::{read_generic, round_up, ByteRead, ByteWrite};
 
 use crate::{
+    allocator::Heap,
     context::ArmCoreContext,
-    engine::{ArmEngine, ArmEngineResult, ArmRegister, MemoryPermission},
+    disasm::{disassemble, disassemble_arm, disassemble_thumb},
+    engine::{ArmEngine, ArmEngineResult, ArmRegister, MemoryFault, MemoryPermission, StopReason, TraceHook, TracedInstruction},
     function::{EmulatedFunction, RegisteredFunction, RegisteredFunctionHolder, ResultWriter},
     future::SpawnFuture,
+    gdb::{GdbState, GdbStub},
+    stack::Stacks,
+    symbols::{function_name, Symbols},
 };
 
 const FUNCTIONS_BASE: u32 = 0x71000000;
@@ -16,11 +26,29 @@ pub const RUN_FUNCTION_LR: u32 = 0x7f000000;
 pub const HEAP_BASE: u32 = 0x40000000;
 pub const PEB_BASE: u32 = 0x7ff00000;
 
+#[cfg(feature = "unicorn")]
+const DEFAULT_ENGINE: NativeEngine = NativeEngine::Unicorn;
+#[cfg(not(feature = "unicorn"))]
+const DEFAULT_ENGINE: NativeEngine = NativeEngine::Armv4tEmu;
+
 struct ArmCoreInner {
     engine: Box<dyn ArmEngine>,
     system: SystemHandle,
     functions: BTreeMap<u32, Rc<Box<dyn RegisteredFunction>>>,
     functions_count: usize,
+    gdb: Option<GdbStub>,
+    // stack base of the task the debugger stopped in, only that task serves it so registers are the stopped task's
+    gdb_task: Option<u32>,
+    // stack base of the task being polled
+    current_task: u32,
+    last_fault: Option<MemoryFault>,
+    heap: Option<Heap>,
+    stacks: Stacks,
+    // of spawned tasks, from the profile
+    stack_size: u32,
+    symbols: Symbols,
+    // loaded with `load`
+    images: Vec<Range<u32>>,
 }
 
 #[derive(Clone)]
@@ -30,19 +58,45 @@ pub struct ArmCore {
 
 impl ArmCore {
     pub fn new(system: SystemHandle) -> ArmEngineResult<Self> {
-        #[cfg(any(target_arch = "wasm32", target_os = "linux"))]
-        let mut engine = Box::new(crate::engine::Armv4tEmuEngine::new());
-        #[cfg(all(not(target_arch = "wasm32"), not(target_os = "linux")))]
-        let mut engine = Box::new(crate::engine::UnicornEngine::new());
+        let engine_kind = system.platform().native_engine().unwrap_or(DEFAULT_ENGINE);
+        let mut engine = Self::create_engine(engine_kind)?;
 
         engine.mem_map(FUNCTIONS_BASE, 0x1000, MemoryPermission::ReadExecute);
         engine.reg_write(ArmRegister::Cpsr, 0x10); // USR32
 
+        let debug_connection = system.platform().debug_connection();
+        if debug_connection.is_some() {
+            tracing::info!("Debugger attached, waiting for it to resume execution");
+        }
+
+        if let Some(mut trace) = system.platform().instruction_trace() {
+            engine.set_trace_hook(Some(Box::new(move |instruction: &TracedInstruction| {
+                let text = if instruction.thumb {
+                    disassemble_thumb(instruction.address, instruction.raw as u16, None).0
+                } else {
+                    disassemble_arm(instruction.address, instruction.raw)
+                };
+
+                let _ = writeln!(trace, "{:#010x}: {}", instruction.address, text);
+            })));
+        }
+
+        let stack_size = system.platform().profile().stack_size;
+
         let inner = ArmCoreInner {
             engine,
             system,
             functions: BTreeMap::new(),
             functions_count: 0,
+            gdb: debug_connection.map(GdbStub::new),
+            gdb_task: None,
+            current_task: 0,
+            last_fault: None,
+            heap: None,
+            stacks: Stacks::new(),
+            stack_size,
+            symbols: Symbols::new(),
+            images: Vec::new(),
         };
 
         Ok(Self {
@@ -50,6 +104,20 @@ impl ArmCore {
         })
     }
 
+    fn create_engine(kind: NativeEngine) -> ArmEngineResult<Box<dyn ArmEngine>> {
+        tracing::debug!("Using {:?} engine", kind);
+
+        Ok(match kind {
+            NativeEngine::Armv4tEmu => Box::new(crate::engine::Armv4tEmuEngine::new()),
+            #[cfg(feature = "unicorn")]
+            NativeEngine::Unicorn => Box::new(crate::engine::UnicornEngine::new()),
+            #[cfg(feature = "unicorn")]
+            NativeEngine::Lockstep => Box::new(crate::engine::LockstepEngine::new()),
+            #[cfg(not(feature = "unicorn"))]
+            _ => anyhow::bail!("{:?} engine needs the `unicorn` feature", kind),
+        })
+    }
+
     pub fn load(&mut self, data: &[u8], address: u32, map_size: usize) -> ArmEngineResult<()> {
         let mut inner = self.inner.borrow_mut();
 
@@ -57,15 +125,47 @@ impl ArmCore {
             .engine
             .mem_map(address, round_up(map_size, 0x1000), MemoryPermission::ReadWriteExecute);
         inner.engine.mem_write(address, data)?;
+        inner.images.push(address..address + map_size as u32);
 
         Ok(())
     }
 
+    /// Names code at `address` in backtraces, a `size` of 0 keeps the size of an existing symbol or extends it up to the next symbol
+    pub fn add_symbol(&mut self, address: u32, size: u32, name: &str) {
+        self.inner.borrow_mut().symbols.insert(address, size, name);
+    }
+
+    /// Address range of the loaded image containing `address`
+    pub fn image_range(&self, address: u32) -> Option<Range<u32>> {
+        self.inner.borrow().images.iter().find(|x| x.contains(&address)).cloned()
+    }
+
     #[allow(clippy::await_holding_refcell_ref)] // We manually drop RefMut https://github.com/rust-lang/rust-clippy/issues/6353
     async fn run_some(&mut self) -> ArmEngineResult<()> {
+        if self.inner.borrow().gdb.is_some() {
+            self.wait_for_debugger().await;
+        }
+
         let mut inner = self.inner.borrow_mut();
 
-        inner.engine.run(RUN_FUNCTION_LR, FUNCTIONS_BASE..FUNCTIONS_BASE + 0x1000, 1000)?;
+        let result = if inner.gdb.is_some() {
+            Self::run_debugged(&mut inner)
+        } else {
+            inner
+                .engine
+                .run(RUN_FUNCTION_LR, FUNCTIONS_BASE..FUNCTIONS_BASE + 0x1000, 1000)
+                .map(|_| ())
+        };
+
+        if let Err(err) = result {
+            inner.last_fault = err.downcast_ref::<MemoryFault>().copied();
+
+            if let Some(task) = inner.last_fault.and_then(|x| inner.stacks.overflowed_task(x.address)) {
+                return Err(err.context(format!("Stack overflow in task {}", task)));
+            }
+
+            return Err(err);
+        }
 
         let cur_pc = inner.engine.reg_read(ArmRegister::PC);
 
@@ -78,11 +178,81 @@ impl ArmCore {
             drop(inner);
 
             function.call(&mut self1, &mut system_clone).await?;
+        } else {
+            drop(inner);
+        }
+
+        // stepping over an instruction which jumped into a native function stops after the function returns
+        let mut inner = self.inner.borrow_mut();
+        if inner.gdb.as_ref().is_some_and(|x| x.state() == GdbState::Stepping) {
+            Self::stop_debugger(&mut inner, StopReason::Done)?;
         }
 
         Ok(())
     }
 
+    // serves the debugger until it resumes execution, other tasks keep running meanwhile.
+    // while stopped, other tasks wait here without serving it, as the engine holds their registers when they are polled
+    async fn wait_for_debugger(&mut self) {
+        loop {
+            let (mut system, now) = {
+                let mut inner = self.inner.borrow_mut();
+                let inner = &mut *inner;
+                let gdb = inner.gdb.as_mut().unwrap();
+
+                if inner.gdb_task.map_or(true, |x| x == inner.current_task) {
+                    if !gdb.poll(&mut *inner.engine) {
+                        inner.gdb = None;
+                        inner.gdb_task = None;
+
+                        return;
+                    }
+
+                    // stopped on attach or by an interrupt
+                    if gdb.state() == GdbState::Stopped {
+                        inner.gdb_task = Some(inner.current_task);
+                    }
+                }
+
+                if gdb.state() != GdbState::Stopped {
+                    inner.gdb_task = None;
+
+                    return;
+                }
+
+                let now = inner.system.platform().now();
+
+                (inner.system.clone(), now)
+            };
+
+            system.sleep(now + 10).await;
+        }
+    }
+
+    fn run_debugged(inner: &mut ArmCoreInner) -> ArmEngineResult<()> {
+        let stepping = inner.gdb.as_ref().unwrap().state() == GdbState::Stepping;
+
+        let count = if stepping { 1 } else { 1000 };
+        let reason = inner.engine.run(RUN_FUNCTION_LR, FUNCTIONS_BASE..FUNCTIONS_BASE + 0x1000, count)?;
+
+        if reason != StopReason::Done {
+            Self::stop_debugger(inner, reason)?;
+        }
+
+        Ok(())
+    }
+
+    fn stop_debugger(inner: &mut ArmCoreInner, reason: StopReason) -> ArmEngineResult<()> {
+        inner.gdb_task = Some(inner.current_task);
+
+        inner.gdb.as_mut().unwrap().stop(reason)
+    }
+
+    // called before polling a task, so the debugger can tell tasks apart
+    pub(crate) fn enter_task(&mut self, stack_base: u32) {
+        self.inner.borrow_mut().current_task = stack_base;
+    }
+
     pub async fn run_function<R>(&mut self, address: u32, params: &[u32]) -> ArmEngineResult<R>
     where
         R: RunFunctionResult<R>,
@@ -132,14 +302,51 @@ impl ArmCore {
         Ok(result)
     }
 
-    pub fn spawn<C, R, E>(&mut self, callable: C)
+    pub fn spawn<C, R, E>(&mut self, callable: C) -> JoinHandle<R>
+    where
+        C: AsyncCallable<R, E> + 'static,
+        R: 'static,
+        E: Debug + 'static,
+    {
+        let self_cloned = self.clone();
+        let (mut system, stack_size) = {
+            let inner = self.inner.borrow();
+            (inner.system.clone(), inner.stack_size)
+        };
+
+        system.spawn(move || SpawnFuture::<C, R, E>::run(self_cloned, None, stack_size, callable))
+    }
+
+    pub fn spawn_named<C, R, E>(&mut self, name: &str, callable: C) -> JoinHandle<R>
+    where
+        C: AsyncCallable<R, E> + 'static,
+        R: 'static,
+        E: Debug + 'static,
+    {
+        let stack_size = self.inner.borrow().stack_size;
+
+        self.spawn_with_stack_size(name, stack_size, callable)
+    }
+
+    /// Spawns a task with its own emulated stack of at least `stack_size` bytes
+    pub fn spawn_with_stack_size<C, R, E>(&mut self, name: &str, stack_size: u32, callable: C) -> JoinHandle<R>
     where
         C: AsyncCallable<R, E> + 'static,
         R: 'static,
         E: Debug + 'static,
     {
         let self_cloned = self.clone();
-        self.inner.borrow_mut().system.spawn(move || SpawnFuture::new(self_cloned, callable));
+        let mut system = self.inner.borrow().system.clone();
+        let name_cloned = name.to_owned();
+
+        system.spawn_named(name, move || {
+            SpawnFuture::<C, R, E>::run(self_cloned, Some(name_cloned), stack_size, callable)
+        })
+    }
+
+    /// Calls `hook` before each instruction, for tracers and coverage collectors
+    pub fn set_trace_hook(&mut self, hook: Option<TraceHook>) {
+        self.inner.borrow_mut().engine.set_trace_hook(hook);
     }
 
     pub fn register_function<F, P, E, R>(&mut self, function: F) -> ArmEngineResult<u32>
@@ -156,6 +363,8 @@ impl ArmCore {
 
         inner.engine.mem_write(address as u32, &bytes)?;
 
+        inner.symbols.insert(address as u32, 2, &function_name(function.name()));
+
         let callback = RegisteredFunctionHolder::new(function);
 
         inner.functions.insert(address as u32, Rc::new(Box::new(callback)));
@@ -177,9 +386,13 @@ impl ArmCore {
     }
 
     pub fn dump_reg_stack(&self, image_base: u32) -> String {
+        let fault = self.inner.borrow().last_fault.map(|x| format!("{}\n", x)).unwrap_or_default();
+
         format!(
-            "\n{}\nPossible call stack:\n{}\nStack:\n{}",
+            "\n{}{}\nCode:\n{}\nPossible call stack:\n{}\nStack:\n{}",
+            fault,
             self.dump_regs(),
+            self.dump_code(),
             self.dump_call_stack(image_base).unwrap(),
             self.dump_stack().unwrap()
         )
@@ -231,6 +444,26 @@ impl ArmCore {
         }
     }
 
+    // returns base and size of the stack
+    pub(crate) fn alloc_stack(&mut self, size: u32, task: &str) -> ArmEngineResult<(u32, u32)> {
+        let mut inner = self.inner.borrow_mut();
+
+        let (base, size, map) = inner.stacks.alloc(size, task)?;
+        if map {
+            inner.engine.mem_map(base, size as usize, MemoryPermission::ReadWrite);
+        }
+
+        Ok((base, size))
+    }
+
+    pub(crate) fn free_stack(&mut self, base: u32) {
+        self.inner.borrow_mut().stacks.free(base);
+    }
+
+    pub(crate) fn heap(&self) -> RefMut<'_, Option<Heap>> {
+        RefMut::map(self.inner.borrow_mut(), |x| &mut x.heap)
+    }
+
     pub(crate) fn read_pc_lr(&self) -> ArmEngineResult<(u32, u32)> {
         let inner = self.inner.borrow();
 
@@ -299,10 +532,8 @@ impl ArmCore {
         .join("\n")
     }
 
-    fn is_code_address(address: u32, image_base: u32) -> bool {
-        // TODO image size temp
-
-        address % 2 == 1 && ((image_base..image_base + 0x100000).contains(&address) || (FUNCTIONS_BASE..FUNCTIONS_BASE + 0x10000).contains(&address))
+    fn is_code_address(images: &[Range<u32>], address: u32) -> bool {
+        address % 2 == 1 && (images.iter().any(|x| x.contains(&address)) || (FUNCTIONS_BASE..FUNCTIONS_BASE + 0x10000).contains(&address))
     }
 
     fn dump_regs(&self) -> String {
@@ -311,43 +542,93 @@ impl ArmCore {
         Self::dump_regs_inner(&*inner.engine)
     }
 
-    fn format_callstack_address(address: u32, image_base: u32) -> String {
-        let description = if (image_base..image_base + 0x100000).contains(&address) {
-            format!("<Base>+{:#x}", address - image_base)
+    fn describe_address(symbols: &Symbols, images: &[Range<u32>], address: u32, image_base: u32) -> String {
+        if let Some(x) = symbols.symbolize(address) {
+            x
+        } else if images.iter().any(|x| x.contains(&address)) {
+            format!("<Base>+{:#x}", address.wrapping_sub(image_base))
         } else if (FUNCTIONS_BASE..FUNCTIONS_BASE + 0x10000).contains(&address) {
             "<Native function>".to_owned()
         } else {
             "<Unknown>".to_owned()
-        };
+        }
+    }
 
-        format!("{:#x}: {}\n", address, description)
+    fn format_callstack_address(engine: &mut dyn ArmEngine, address: u32, thumb: bool, description: &str) -> String {
+        let instruction = engine
+            .mem_read(address, 4)
+            .ok()
+            .and_then(|x| disassemble(address, &x, thumb).into_iter().next());
+
+        if let Some(instruction) = instruction {
+            format!("{:#x}: {} ({})\n", address, description, instruction.text)
+        } else {
+            format!("{:#x}: {}\n", address, description)
+        }
     }
 
+    // caller to callee summary, followed by each frame
     fn dump_call_stack(&self, image_base: u32) -> ArmEngineResult<String> {
         let mut inner = self.inner.borrow_mut();
+        let ArmCoreInner { engine, symbols, images, .. } = &mut *inner;
+        let engine = &mut **engine;
 
-        let sp = inner.engine.reg_read(ArmRegister::SP);
-        let pc = inner.engine.reg_read(ArmRegister::PC);
-        let lr = inner.engine.reg_read(ArmRegister::LR);
+        let sp = engine.reg_read(ArmRegister::SP);
+        let pc = engine.reg_read(ArmRegister::PC);
+        let lr = engine.reg_read(ArmRegister::LR);
+        let thumb = engine.reg_read(ArmRegister::Cpsr) & (1 << 5) != 0;
 
-        let mut call_stack = Self::format_callstack_address(pc, image_base);
+        let mut frames = vec![(pc, thumb)];
         if lr != RUN_FUNCTION_LR && lr != 0 {
-            call_stack += &Self::format_callstack_address(lr - 5, image_base);
+            frames.push((lr - 5, lr % 2 == 1));
         }
 
         for i in 0..128 {
             let address = sp + (i * 4);
-            let value = inner.engine.mem_read(address, size_of::<u32>())?;
+            // the stack may end, or sp may be in a guard page
+            let Ok(value) = engine.mem_read(address, size_of::<u32>()) else {
+                continue;
+            };
             let value_u32 = u32::from_le_bytes(value.try_into().unwrap());
 
-            if value_u32 > 5 && Self::is_code_address(value_u32 - 4, image_base) {
-                call_stack += &Self::format_callstack_address(value_u32 - 5, image_base);
+            if value_u32 > 5 && Self::is_code_address(images, value_u32 - 4) {
+                frames.push((value_u32 - 5, true));
             }
         }
 
+        let descriptions = frames
+            .iter()
+            .map(|&(address, _)| Self::describe_address(symbols, images, address, image_base))
+            .collect::<Vec<_>>();
+
+        let mut call_stack = descriptions.iter().rev().map(String::as_str).collect::<Vec<_>>().join(" -> ") + "\n";
+        for ((address, thumb), description) in frames.into_iter().zip(descriptions) {
+            call_stack += &Self::format_callstack_address(engine, address, thumb, &description);
+        }
+
         Ok(call_stack)
     }
 
+    // instructions around pc, decoding from a few instructions before it
+    fn dump_code(&self) -> String {
+        let mut inner = self.inner.borrow_mut();
+
+        let pc = inner.engine.reg_read(ArmRegister::PC);
+        let thumb = inner.engine.reg_read(ArmRegister::Cpsr) & (1 << 5) != 0;
+        let instruction_size = if thumb { 2 } else { 4 };
+
+        let start = pc.saturating_sub(instruction_size * 4);
+        let data = match inner.engine.mem_read(start, (pc - start + instruction_size * 5) as usize) {
+            Ok(data) => data,
+            Err(_) => return format!("{:#x}: <Unreadable>\n", pc),
+        };
+
+        disassemble(start, &data, thumb)
+            .into_iter()
+            .map(|x| format!("{} {}\n", if x.address == pc { "=>" } else { "  " }, x))
+            .collect()
+    }
+
     fn dump_stack(&self) -> ArmEngineResult<String> {
         let mut inner = self.inner.borrow_mut();
 
@@ -356,10 +637,11 @@ impl ArmCore {
         let mut result = String::new();
         for i in 0..16 {
             let address = sp + (i * 4);
-            let value = inner.engine.mem_read(address, size_of::<u32>())?;
-            let value_u32 = u32::from_le_bytes(value.try_into().unwrap());
-
-            result += &format!("SP+{:#x}: {:#x}\n", i * 4, value_u32);
+            if let Ok(value) = inner.engine.mem_read(address, size_of::<u32>()) {
+                result += &format!("SP+{:#x}: {:#x}\n", i * 4, u32::from_le_bytes(value.try_into().unwrap()));
+            } else {
+                result += &format!("SP+{:#x}: <Unreadable>\n", i * 4);
+            }
         }
 
         Ok(result)
@@ -0,0 +1,112 @@
+mod arm;
+mod thumb;
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Display, Formatter};
+
+pub use self::{arm::disassemble_arm, thumb::disassemble_thumb};
+
+const CONDITIONS: [&str; 16] = [
+    "eq", "ne", "cs", "cc", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt", "le", "", "nv",
+];
+const REGISTERS: [&str; 16] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "sb", "sl", "fp", "ip", "sp", "lr", "pc",
+];
+const SHIFTS: [&str; 4] = ["lsl", "lsr", "asr", "ror"];
+
+pub struct Instruction {
+    pub address: u32,
+    pub size: u32,
+    pub text: String,
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#010x}: {}", self.address, self.text)
+    }
+}
+
+/// Disassembles instructions in `data` located at `address`, thumb bl pairs are decoded as one instruction
+pub fn disassemble(address: u32, data: &[u8], thumb: bool) -> Vec<Instruction> {
+    let mut result = Vec::new();
+    let mut offset = 0;
+
+    if thumb {
+        while offset + 2 <= data.len() {
+            let raw = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            let next = data.get(offset + 2..offset + 4).map(|x| u16::from_le_bytes([x[0], x[1]]));
+
+            let instruction_address = address.wrapping_add(offset as u32);
+            let (text, size) = disassemble_thumb(instruction_address, raw, next);
+
+            result.push(Instruction {
+                address: instruction_address,
+                size,
+                text,
+            });
+            offset += size as usize;
+        }
+    } else {
+        for (i, x) in data.chunks_exact(4).enumerate() {
+            let instruction_address = address.wrapping_add(i as u32 * 4);
+            let raw = u32::from_le_bytes([x[0], x[1], x[2], x[3]]);
+
+            result.push(Instruction {
+                address: instruction_address,
+                size: 4,
+                text: disassemble_arm(instruction_address, raw),
+            });
+        }
+    }
+
+    result
+}
+
+fn register(number: u32) -> &'static str {
+    REGISTERS[(number & 0xf) as usize]
+}
+
+fn register_list(list: u32) -> String {
+    let registers = (0..16).filter(|x| list & (1 << x) != 0).map(register).collect::<Vec<_>>();
+
+    ["{", &registers.join(", "), "}"].concat()
+}
+
+fn sign_extend(value: u32, bits: u32) -> i32 {
+    ((value << (32 - bits)) as i32) >> (32 - bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+
+    use super::{disassemble, disassemble_arm, disassemble_thumb};
+
+    #[test]
+    fn test_arm() {
+        assert_eq!(disassemble_arm(0x1000, 0xe92d4010), "push {r4, lr}");
+        assert_eq!(disassemble_arm(0x1000, 0xeb000010), "bl #0x1048");
+        assert_eq!(disassemble_arm(0x1000, 0xe59f0010), "ldr r0, [pc, #0x10] ; 0x1018");
+        assert_eq!(disassemble_arm(0x1000, 0xe7910102), "ldr r0, [r1, r2, lsl #2]");
+        assert_eq!(disassemble_arm(0x1000, 0xe1d000b2), "ldrh r0, [r0, #0x2]");
+        assert_eq!(disassemble_arm(0x1000, 0xe16f0f11), "clz r0, r1");
+        assert_eq!(disassemble_arm(0x1000, 0xfa000001), "blx #0x100c");
+    }
+
+    #[test]
+    fn test_thumb() {
+        assert_eq!(disassemble_thumb(0x2000, 0xb510, None), ("push {r4, lr}".into(), 2));
+        assert_eq!(disassemble_thumb(0x2000, 0x4801, None), ("ldr r0, [pc, #0x4] ; 0x2008".into(), 2));
+        assert_eq!(disassemble_thumb(0x2000, 0xd0fe, None), ("beq #0x2000".into(), 2));
+        assert_eq!(disassemble_thumb(0x2000, 0xf000, Some(0xf802)), ("bl #0x2008".into(), 4));
+        assert_eq!(disassemble_thumb(0x2000, 0xf000, Some(0xe802)), ("blx #0x2008".into(), 4));
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let code = [0x01, 0x20, 0x00, 0xf0, 0x02, 0xf8, 0x70, 0x47];
+        let result = disassemble(0x2000, &code, true).into_iter().map(|x| x.text).collect::<Vec<_>>();
+
+        assert_eq!(result, ["movs r0, #0x1", "bl #0x200a", "bx lr"].map(String::from));
+    }
+}
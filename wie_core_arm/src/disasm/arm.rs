@@ -0,0 +1,388 @@
+use alloc::{format, string::String};
+
+use super::{register, register_list, sign_extend, CONDITIONS, SHIFTS};
+
+const DATA_PROCESSING: [&str; 16] = [
+    "and", "eor", "sub", "rsb", "add", "adc", "sbc", "rsc", "tst", "teq", "cmp", "cmn", "orr", "mov", "bic", "mvn",
+];
+
+/// Disassembles an ARMv5TE arm instruction, using UAL mnemonics
+pub fn disassemble_arm(address: u32, raw: u32) -> String {
+    let cond = raw >> 28;
+    if cond == 0xf {
+        return unconditional(address, raw);
+    }
+
+    let c = CONDITIONS[cond as usize];
+
+    match (raw >> 25) & 7 {
+        0b000 => decode_000(raw, c),
+        0b001 => decode_001(raw, c),
+        0b010 | 0b011 => load_store(address, raw, c),
+        0b100 => block_transfer(raw, c),
+        0b101 => branch(address, raw, c),
+        0b110 => coprocessor_transfer(raw, c),
+        _ => {
+            if raw & (1 << 24) != 0 {
+                format!("swi{} #{:#x}", c, raw & 0xffffff)
+            } else {
+                coprocessor(raw, c)
+            }
+        }
+    }
+}
+
+fn bit(raw: u32, n: u32) -> bool {
+    raw & (1 << n) != 0
+}
+
+fn rn(raw: u32) -> &'static str {
+    register(raw >> 16)
+}
+
+fn rd(raw: u32) -> &'static str {
+    register(raw >> 12)
+}
+
+fn rs(raw: u32) -> &'static str {
+    register(raw >> 8)
+}
+
+fn rm(raw: u32) -> &'static str {
+    register(raw)
+}
+
+fn sign(raw: u32) -> &'static str {
+    if bit(raw, 23) {
+        ""
+    } else {
+        "-"
+    }
+}
+
+fn decode_000(raw: u32, c: &str) -> String {
+    if bit(raw, 7) && bit(raw, 4) {
+        if (raw >> 5) & 3 != 0 {
+            return extra_load_store(raw, c);
+        }
+
+        return match (raw >> 23) & 0x1f {
+            0b00000 => {
+                let s = if bit(raw, 20) { "s" } else { "" };
+                let (rd, rn) = (register(raw >> 16), register(raw >> 12));
+
+                if bit(raw, 21) {
+                    format!("mla{}{} {}, {}, {}, {}", s, c, rd, rm(raw), rs(raw), rn)
+                } else {
+                    format!("mul{}{} {}, {}, {}", s, c, rd, rm(raw), rs(raw))
+                }
+            }
+            0b00001 => {
+                let s = if bit(raw, 20) { "s" } else { "" };
+                let mnemonic = ["umull", "umlal", "smull", "smlal"][((raw >> 21) & 3) as usize];
+
+                format!("{}{}{} {}, {}, {}, {}", mnemonic, s, c, rd(raw), rn(raw), rm(raw), rs(raw))
+            }
+            0b00010 if (raw >> 20) & 3 == 0 => {
+                let b = if bit(raw, 22) { "b" } else { "" };
+
+                format!("swp{}{} {}, {}, [{}]", b, c, rd(raw), rm(raw), rn(raw))
+            }
+            _ => undefined(raw),
+        };
+    }
+
+    // tst, teq, cmp and cmn without s are miscellaneous instructions
+    if (raw >> 23) & 3 == 0b10 && !bit(raw, 20) {
+        return miscellaneous(raw, c);
+    }
+
+    data_processing(raw, c, shifted_register(raw))
+}
+
+fn decode_001(raw: u32, c: &str) -> String {
+    if (raw >> 23) & 3 == 0b10 && !bit(raw, 20) {
+        if !bit(raw, 21) {
+            return undefined(raw);
+        }
+
+        return format!("msr{} {}, #{:#x}", c, psr_fields(raw), immediate(raw));
+    }
+
+    data_processing(raw, c, format!("#{:#x}", immediate(raw)))
+}
+
+fn immediate(raw: u32) -> u32 {
+    (raw & 0xff).rotate_right(((raw >> 8) & 0xf) * 2)
+}
+
+fn shifted_register(raw: u32) -> String {
+    let shift = (raw >> 5) & 3;
+
+    if bit(raw, 4) {
+        return format!("{}, {} {}", rm(raw), SHIFTS[shift as usize], rs(raw));
+    }
+
+    match (shift, (raw >> 7) & 0x1f) {
+        (0, 0) => rm(raw).into(),
+        (3, 0) => format!("{}, rrx", rm(raw)),
+        (_, 0) => format!("{}, {} #32", rm(raw), SHIFTS[shift as usize]),
+        (_, amount) => format!("{}, {} #{}", rm(raw), SHIFTS[shift as usize], amount),
+    }
+}
+
+fn data_processing(raw: u32, c: &str, operand: String) -> String {
+    let opcode = (raw >> 21) & 0xf;
+    let mnemonic = DATA_PROCESSING[opcode as usize];
+    let s = if bit(raw, 20) { "s" } else { "" };
+
+    match opcode {
+        8..=11 => format!("{}{} {}, {}", mnemonic, c, rn(raw), operand),
+        13 | 15 => format!("{}{}{} {}, {}", mnemonic, s, c, rd(raw), operand),
+        _ => format!("{}{}{} {}, {}, {}", mnemonic, s, c, rd(raw), rn(raw), operand),
+    }
+}
+
+fn psr_fields(raw: u32) -> String {
+    let psr = if bit(raw, 22) { "spsr" } else { "cpsr" };
+    let fields = ["c", "x", "s", "f"]
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| bit(raw, 16 + *i as u32))
+        .map(|(_, x)| *x)
+        .collect::<String>();
+
+    format!("{}_{}", psr, fields)
+}
+
+fn miscellaneous(raw: u32, c: &str) -> String {
+    let op1 = (raw >> 21) & 3;
+    let op2 = (raw >> 4) & 0xf;
+
+    match op2 {
+        0 if op1 & 1 == 0 => {
+            let psr = if bit(raw, 22) { "spsr" } else { "cpsr" };
+
+            format!("mrs{} {}, {}", c, rd(raw), psr)
+        }
+        0 => format!("msr{} {}, {}", c, psr_fields(raw), rm(raw)),
+        1 if op1 == 1 => format!("bx{} {}", c, rm(raw)),
+        1 if op1 == 3 => format!("clz{} {}, {}", c, rd(raw), rm(raw)),
+        3 if op1 == 1 => format!("blx{} {}", c, rm(raw)),
+        5 => {
+            let mnemonic = ["qadd", "qsub", "qdadd", "qdsub"][op1 as usize];
+
+            format!("{}{} {}, {}, {}", mnemonic, c, rd(raw), rm(raw), rn(raw))
+        }
+        7 if op1 == 1 => format!("bkpt #{:#x}", ((raw >> 4) & 0xfff0) | (raw & 0xf)),
+        _ if op2 & 0b1001 == 0b1000 => {
+            let x = if bit(raw, 5) { "t" } else { "b" };
+            let y = if bit(raw, 6) { "t" } else { "b" };
+            let (rd, rn) = (register(raw >> 16), register(raw >> 12));
+
+            match op1 {
+                0 => format!("smla{}{}{} {}, {}, {}, {}", x, y, c, rd, rm(raw), rs(raw), rn),
+                1 if x == "b" => format!("smlaw{}{} {}, {}, {}, {}", y, c, rd, rm(raw), rs(raw), rn),
+                1 => format!("smulw{}{} {}, {}, {}", y, c, rd, rm(raw), rs(raw)),
+                2 => format!("smlal{}{}{} {}, {}, {}, {}", x, y, c, rn, rd, rm(raw), rs(raw)),
+                _ => format!("smul{}{}{} {}, {}, {}", x, y, c, rd, rm(raw), rs(raw)),
+            }
+        }
+        _ => undefined(raw),
+    }
+}
+
+// `[rn, offset]`, `[rn, offset]!` or `[rn], offset`
+fn address_mode(raw: u32, offset: Option<String>) -> String {
+    match (bit(raw, 24), offset) {
+        (true, None) => format!("[{}]", rn(raw)),
+        (true, Some(offset)) => format!("[{}, {}]{}", rn(raw), offset, if bit(raw, 21) { "!" } else { "" }),
+        (false, None) => format!("[{}], #0", rn(raw)),
+        (false, Some(offset)) => format!("[{}], {}", rn(raw), offset),
+    }
+}
+
+fn extra_load_store(raw: u32, c: &str) -> String {
+    let mnemonic = match (bit(raw, 20), (raw >> 5) & 3) {
+        (true, 1) => "ldrh",
+        (true, 2) => "ldrsb",
+        (true, _) => "ldrsh",
+        (false, 1) => "strh",
+        (false, 2) => "ldrd",
+        (false, _) => "strd",
+    };
+
+    let offset = if bit(raw, 22) {
+        let imm = ((raw >> 4) & 0xf0) | (raw & 0xf);
+
+        (imm != 0).then(|| format!("#{}{:#x}", sign(raw), imm))
+    } else {
+        Some(format!("{}{}", sign(raw), rm(raw)))
+    };
+
+    format!("{}{} {}, {}", mnemonic, c, rd(raw), address_mode(raw, offset))
+}
+
+fn load_store(address: u32, raw: u32, c: &str) -> String {
+    let register_offset = bit(raw, 25);
+    if register_offset && bit(raw, 4) {
+        return undefined(raw);
+    }
+
+    let load = if bit(raw, 20) { "ldr" } else { "str" };
+    let byte = if bit(raw, 22) { "b" } else { "" };
+    let translate = if !bit(raw, 24) && bit(raw, 21) { "t" } else { "" };
+
+    let offset = if register_offset {
+        Some(format!("{}{}", sign(raw), shifted_register(raw)))
+    } else {
+        let imm = raw & 0xfff;
+
+        (imm != 0).then(|| format!("#{}{:#x}", sign(raw), imm))
+    };
+
+    let mut result = format!("{}{}{}{} {}, {}", load, byte, translate, c, rd(raw), address_mode(raw, offset));
+
+    // pc relative literal
+    if !register_offset && bit(raw, 24) && (raw >> 16) & 0xf == 15 {
+        let imm = raw & 0xfff;
+        let base = address.wrapping_add(8);
+        let target = if bit(raw, 23) { base.wrapping_add(imm) } else { base.wrapping_sub(imm) };
+
+        result += &format!(" ; {:#x}", target);
+    }
+
+    result
+}
+
+fn block_transfer(raw: u32, c: &str) -> String {
+    let list = raw & 0xffff;
+    let load = bit(raw, 20);
+    let writeback = bit(raw, 21);
+    let mode = (raw >> 23) & 3;
+
+    if (raw >> 16) & 0xf == 13 && writeback {
+        if load && mode == 0b01 {
+            return format!("pop{} {}", c, register_list(list));
+        }
+        if !load && mode == 0b10 {
+            return format!("push{} {}", c, register_list(list));
+        }
+    }
+
+    let mnemonic = if load { "ldm" } else { "stm" };
+    let mode = ["da", "ia", "db", "ib"][mode as usize];
+
+    format!(
+        "{}{}{} {}{}, {}{}",
+        mnemonic,
+        mode,
+        c,
+        rn(raw),
+        if writeback { "!" } else { "" },
+        register_list(list),
+        if bit(raw, 22) { "^" } else { "" }
+    )
+}
+
+fn branch(address: u32, raw: u32, c: &str) -> String {
+    let link = if bit(raw, 24) { "l" } else { "" };
+    let target = address.wrapping_add(8).wrapping_add_signed(sign_extend(raw & 0xffffff, 24) << 2);
+
+    format!("b{}{} #{:#x}", link, c, target)
+}
+
+fn coprocessor_transfer(raw: u32, c: &str) -> String {
+    let cp = (raw >> 8) & 0xf;
+
+    // mcrr and mrrc
+    if (raw >> 21) & 0xf == 0b0010 {
+        let mnemonic = if bit(raw, 20) { "mrrc" } else { "mcrr" };
+
+        return format!(
+            "{}{} p{}, #{}, {}, {}, c{}",
+            mnemonic,
+            c,
+            cp,
+            (raw >> 4) & 0xf,
+            rd(raw),
+            rn(raw),
+            raw & 0xf
+        );
+    }
+
+    let mnemonic = if bit(raw, 20) { "ldc" } else { "stc" };
+    let long = if bit(raw, 22) { "l" } else { "" };
+    let imm = (raw & 0xff) * 4;
+    let offset = if !bit(raw, 24) && !bit(raw, 21) {
+        // unindexed, offset is an option for the coprocessor
+        Some(format!("{{{:#x}}}", raw & 0xff))
+    } else {
+        (imm != 0).then(|| format!("#{}{:#x}", sign(raw), imm))
+    };
+
+    format!("{}{}{} p{}, c{}, {}", mnemonic, long, c, cp, (raw >> 12) & 0xf, address_mode(raw, offset))
+}
+
+fn coprocessor(raw: u32, c: &str) -> String {
+    let cp = (raw >> 8) & 0xf;
+    let op2 = (raw >> 5) & 7;
+    let (crn, crm) = ((raw >> 16) & 0xf, raw & 0xf);
+
+    if bit(raw, 4) {
+        let mnemonic = if bit(raw, 20) { "mrc" } else { "mcr" };
+
+        format!(
+            "{}{} p{}, #{}, {}, c{}, c{}, #{}",
+            mnemonic,
+            c,
+            cp,
+            (raw >> 21) & 7,
+            rd(raw),
+            crn,
+            crm,
+            op2
+        )
+    } else {
+        format!(
+            "cdp{} p{}, #{}, c{}, c{}, c{}, #{}",
+            c,
+            cp,
+            (raw >> 20) & 0xf,
+            (raw >> 12) & 0xf,
+            crn,
+            crm,
+            op2
+        )
+    }
+}
+
+fn unconditional(address: u32, raw: u32) -> String {
+    if (raw >> 25) & 7 == 0b101 {
+        let h = if bit(raw, 24) { 2 } else { 0 };
+        let target = address
+            .wrapping_add(8)
+            .wrapping_add_signed(sign_extend(raw & 0xffffff, 24) << 2)
+            .wrapping_add(h);
+
+        return format!("blx #{:#x}", target);
+    }
+
+    if raw & 0x0d70_f000 == 0x0550_f000 {
+        let offset = if bit(raw, 25) {
+            Some(format!("{}{}", sign(raw), shifted_register(raw)))
+        } else {
+            let imm = raw & 0xfff;
+
+            (imm != 0).then(|| format!("#{}{:#x}", sign(raw), imm))
+        };
+
+        return format!("pld {}", address_mode(raw | (1 << 24), offset));
+    }
+
+    undefined(raw)
+}
+
+fn undefined(raw: u32) -> String {
+    format!("<undefined {:#010x}>", raw)
+}
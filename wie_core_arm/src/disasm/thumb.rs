@@ -0,0 +1,155 @@
+use alloc::{format, string::String};
+
+use super::{register, register_list, sign_extend, CONDITIONS, SHIFTS};
+
+const ALU: [&str; 16] = [
+    "ands", "eors", "lsls", "lsrs", "asrs", "adcs", "sbcs", "rors", "tst", "negs", "cmp", "cmn", "orrs", "muls", "bics", "mvns",
+];
+const LOAD_STORE_REGISTER: [&str; 8] = ["str", "strh", "strb", "ldrsb", "ldr", "ldrh", "ldrb", "ldrsh"];
+
+/// Disassembles a thumb instruction, returning the text and the size in bytes.
+/// `next` is the following halfword, used to decode bl and blx pairs as one instruction.
+pub fn disassemble_thumb(address: u32, raw: u16, next: Option<u16>) -> (String, u32) {
+    let raw = raw as u32;
+
+    let rd = register(raw & 7);
+    let rs = register((raw >> 3) & 7);
+
+    let text = match raw >> 11 {
+        0b00000..=0b00010 => {
+            let shift = (raw >> 11) & 3;
+            let amount = (raw >> 6) & 0x1f;
+
+            match (shift, amount) {
+                (0, 0) => format!("movs {}, {}", rd, rs),
+                (_, 0) => format!("{}s {}, {}, #32", SHIFTS[shift as usize], rd, rs),
+                (_, amount) => format!("{}s {}, {}, #{}", SHIFTS[shift as usize], rd, rs, amount),
+            }
+        }
+        0b00011 => {
+            let mnemonic = if raw & (1 << 9) != 0 { "subs" } else { "adds" };
+            let operand = (raw >> 6) & 7;
+
+            if raw & (1 << 10) != 0 {
+                format!("{} {}, {}, #{}", mnemonic, rd, rs, operand)
+            } else {
+                format!("{} {}, {}, {}", mnemonic, rd, rs, register(operand))
+            }
+        }
+        0b00100..=0b00111 => {
+            let mnemonic = ["movs", "cmp", "adds", "subs"][((raw >> 11) & 3) as usize];
+
+            format!("{} {}, #{:#x}", mnemonic, register((raw >> 8) & 7), raw & 0xff)
+        }
+        0b01000 if raw & (1 << 10) == 0 => format!("{} {}, {}", ALU[((raw >> 6) & 0xf) as usize], rd, rs),
+        0b01000 => {
+            let rm = register((raw >> 3) & 0xf);
+            let rd = register((raw & 7) | ((raw >> 4) & 8));
+
+            match (raw >> 8) & 3 {
+                0 => format!("add {}, {}", rd, rm),
+                1 => format!("cmp {}, {}", rd, rm),
+                2 => format!("mov {}, {}", rd, rm),
+                _ if raw & (1 << 7) != 0 => format!("blx {}", rm),
+                _ => format!("bx {}", rm),
+            }
+        }
+        0b01001 => {
+            let imm = (raw & 0xff) * 4;
+            let target = (address.wrapping_add(4) & !3).wrapping_add(imm);
+
+            format!("ldr {}, [pc, #{:#x}] ; {:#x}", register((raw >> 8) & 7), imm, target)
+        }
+        0b01010 | 0b01011 => {
+            let mnemonic = LOAD_STORE_REGISTER[((raw >> 9) & 7) as usize];
+
+            format!("{} {}, [{}, {}]", mnemonic, rd, rs, register((raw >> 6) & 7))
+        }
+        0b01100..=0b01111 => {
+            let byte = raw & (1 << 12) != 0;
+            let mnemonic = ["str", "ldr", "strb", "ldrb"][((raw >> 11) & 3) as usize];
+            let imm = if byte { (raw >> 6) & 0x1f } else { ((raw >> 6) & 0x1f) * 4 };
+
+            format!("{} {}, [{}, #{:#x}]", mnemonic, rd, rs, imm)
+        }
+        0b10000 | 0b10001 => {
+            let mnemonic = if raw & (1 << 11) != 0 { "ldrh" } else { "strh" };
+
+            format!("{} {}, [{}, #{:#x}]", mnemonic, rd, rs, ((raw >> 6) & 0x1f) * 2)
+        }
+        0b10010 | 0b10011 => {
+            let mnemonic = if raw & (1 << 11) != 0 { "ldr" } else { "str" };
+
+            format!("{} {}, [sp, #{:#x}]", mnemonic, register((raw >> 8) & 7), (raw & 0xff) * 4)
+        }
+        0b10100 | 0b10101 => {
+            let base = if raw & (1 << 11) != 0 { "sp" } else { "pc" };
+
+            format!("add {}, {}, #{:#x}", register((raw >> 8) & 7), base, (raw & 0xff) * 4)
+        }
+        0b10110 | 0b10111 => miscellaneous(raw),
+        0b11000 | 0b11001 => {
+            let mnemonic = if raw & (1 << 11) != 0 { "ldmia" } else { "stmia" };
+
+            format!("{} {}!, {}", mnemonic, register((raw >> 8) & 7), register_list(raw & 0xff))
+        }
+        0b11010 | 0b11011 => match (raw >> 8) & 0xf {
+            0xe => undefined(raw),
+            0xf => format!("swi #{:#x}", raw & 0xff),
+            cond => {
+                let target = address.wrapping_add(4).wrapping_add_signed(sign_extend(raw & 0xff, 8) << 1);
+
+                format!("b{} #{:#x}", CONDITIONS[cond as usize], target)
+            }
+        },
+        0b11100 => {
+            let target = address.wrapping_add(4).wrapping_add_signed(sign_extend(raw & 0x7ff, 11) << 1);
+
+            format!("b #{:#x}", target)
+        }
+        0b11110 => {
+            if let Some(next) = next.map(|x| x as u32).filter(|x| x >> 11 == 0b11111 || x >> 11 == 0b11101) {
+                let offset = sign_extend(((raw & 0x7ff) << 12) | ((next & 0x7ff) << 1), 23);
+                let target = address.wrapping_add(4).wrapping_add_signed(offset);
+
+                return if next >> 11 == 0b11111 {
+                    (format!("bl #{:#x}", target), 4)
+                } else {
+                    (format!("blx #{:#x}", target & !3), 4)
+                };
+            }
+
+            format!("(bl prefix) #{:#x}", sign_extend(raw & 0x7ff, 11) << 12)
+        }
+        0b11111 => format!("(bl suffix) #{:#x}", (raw & 0x7ff) << 1),
+        _ => format!("(blx suffix) #{:#x}", (raw & 0x7ff) << 1),
+    };
+
+    (text, 2)
+}
+
+fn miscellaneous(raw: u32) -> String {
+    if (raw >> 8) & 0xf == 0 {
+        let mnemonic = if raw & (1 << 7) != 0 { "sub" } else { "add" };
+
+        return format!("{} sp, #{:#x}", mnemonic, (raw & 0x7f) * 4);
+    }
+
+    if (raw >> 9) & 3 == 0b10 {
+        let load = raw & (1 << 11) != 0;
+        let extra = if load { 1 << 15 } else { 1 << 14 };
+        let list = (raw & 0xff) | if raw & (1 << 8) != 0 { extra } else { 0 };
+
+        return format!("{} {}", if load { "pop" } else { "push" }, register_list(list));
+    }
+
+    if (raw >> 8) & 0xff == 0xbe {
+        return format!("bkpt #{:#x}", raw & 0xff);
+    }
+
+    undefined(raw)
+}
+
+fn undefined(raw: u32) -> String {
+    format!("<undefined {:#06x}>", raw)
+}
@@ -1,35 +1,122 @@
 mod armv4t_emu;
-#[cfg(all(not(target_arch = "wasm32"), not(target_os = "linux")))]
+#[cfg(feature = "unicorn")]
+mod lockstep;
+#[cfg(feature = "unicorn")]
 mod unicorn;
 
-use alloc::vec::Vec;
-use core::ops::Range;
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
 
-#[cfg(any(target_arch = "wasm32", target_os = "linux"))]
+#[cfg(feature = "unicorn")]
+pub use self::{lockstep::LockstepEngine, unicorn::UnicornEngine};
 pub use armv4t_emu::Armv4tEmuEngine;
-#[cfg(all(not(target_arch = "wasm32"), not(target_os = "linux")))]
-pub use unicorn::UnicornEngine;
 
 pub type ArmEngineResult<T> = anyhow::Result<T>;
 pub type ArmEngineError = anyhow::Error;
 
+pub type TraceHook = Box<dyn FnMut(&TracedInstruction)>;
+
 pub trait ArmEngine {
-    fn run(&mut self, end: u32, hook: Range<u32>, count: u32) -> ArmEngineResult<()>;
+    fn run(&mut self, end: u32, hook: Range<u32>, count: u32) -> ArmEngineResult<StopReason>;
     fn reg_write(&mut self, reg: ArmRegister, value: u32);
     fn reg_read(&self, reg: ArmRegister) -> u32;
     fn mem_map(&mut self, address: u32, size: usize, permission: MemoryPermission);
     fn mem_write(&mut self, address: u32, data: &[u8]) -> ArmEngineResult<()>;
     fn mem_read(&mut self, address: u32, size: usize) -> ArmEngineResult<Vec<u8>>;
+
+    /// Stops `run` before the instruction at `address`.
+    /// Breakpoint we stopped at last time is ignored once, so running again makes progress.
+    fn add_breakpoint(&mut self, address: u32);
+    fn remove_breakpoint(&mut self, address: u32);
+    /// Stops `run` after an instruction accessing memory in `range`
+    fn add_watchpoint(&mut self, range: Range<u32>, kind: WatchpointKind);
+    fn remove_watchpoint(&mut self, range: Range<u32>, kind: WatchpointKind);
+    /// `hook` is called before each instruction is executed, `None` removes it
+    fn set_trace_hook(&mut self, hook: Option<TraceHook>);
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StopReason {
+    /// `end`, `hook` range or instruction count is reached
+    Done,
+    Breakpoint(u32),
+    Watchpoint {
+        address: u32,
+        size: u32,
+        write: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WatchpointKind {
+    Read,
+    Write,
+    Access,
+}
+
+impl WatchpointKind {
+    pub fn matches(self, write: bool) -> bool {
+        match self {
+            Self::Read => !write,
+            Self::Write => write,
+            Self::Access => true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MemoryAccess {
+    Read,
+    Write,
+    Execute,
+}
+
+/// Access to unmapped memory or against page permissions by emulated code.
+/// `run` fails with it, get it back with `ArmEngineError::downcast_ref`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MemoryFault {
+    pub address: u32,
+    pub size: u32,
+    pub access: MemoryAccess,
+    /// address of the faulting instruction
+    pub pc: u32,
+}
+
+impl Display for MemoryFault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let access = match self.access {
+            MemoryAccess::Read => "read",
+            MemoryAccess::Write => "write",
+            MemoryAccess::Execute => "execute",
+        };
+
+        write!(
+            f,
+            "Invalid memory {} of {} bytes at {:#x}, PC: {:#x}",
+            access, self.size, self.address, self.pc
+        )
+    }
+}
+
+pub struct TracedInstruction {
+    pub address: u32,
+    pub thumb: bool,
+    /// 32bit arm instruction, or 16bit thumb instruction
+    pub raw: u32,
 }
 
 #[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy)]
 pub enum MemoryPermission {
     ReadExecute = 5,
     ReadWrite = 6,
     ReadWriteExecute = 7,
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ArmRegister {
     R0,
     R1,
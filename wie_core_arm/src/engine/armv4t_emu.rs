@@ -1,38 +1,189 @@
-use alloc::{boxed::Box, vec::Vec};
+mod armv5te;
+mod block_map;
+
+use alloc::{boxed::Box, collections::BTreeSet, rc::Rc, vec, vec::Vec};
 use core::{array, ops::Range};
 
 use armv4t_emu::{reg, Cpu, Memory, Mode};
 
-use crate::engine::{ArmEngine, ArmEngineResult, ArmRegister, MemoryPermission};
+use crate::engine::{
+    ArmEngine, ArmEngineResult, ArmRegister, MemoryAccess, MemoryFault, MemoryPermission, StopReason, TraceHook, TracedInstruction, WatchpointKind,
+};
+
+use self::{
+    armv5te::Armv5teInstruction,
+    block_map::{Block, BlockInstruction, BlockMap, CODE_PAGE_SIZE},
+};
+
+// registers saved before each instruction, to be restored if it faults
+const REGISTERS: [u8; 17] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, reg::SP, reg::LR, reg::PC, reg::CPSR];
 
 pub struct Armv4tEmuEngine {
     cpu: Cpu,
     mem: Armv4tEmuMemory,
+    blocks: BlockMap,
+    breakpoints: BTreeSet<u32>,
+    last_breakpoint: Option<u32>,
+    trace_hook: Option<TraceHook>,
 }
 
 impl Armv4tEmuEngine {
-    #[allow(dead_code)]
     pub fn new() -> Self {
         Self {
             cpu: Cpu::new(),
             mem: Armv4tEmuMemory::new(),
+            blocks: BlockMap::new(),
+            breakpoints: BTreeSet::new(),
+            last_breakpoint: None,
+            trace_hook: None,
         }
     }
+
+    fn block(&mut self, pc: u32, thumb: bool) -> ArmEngineResult<Rc<Block>> {
+        if let Some(x) = self.blocks.get(pc, thumb) {
+            return Ok(x);
+        }
+
+        // blocks don't cross pages, and permission changes invalidate them
+        self.mem.check_access(pc, if thumb { 2 } else { 4 }, MemoryAccess::Execute);
+        self.check_fault(pc, None)?;
+
+        let code = self.mem.read_range(pc, Block::max_size(pc, thumb))?;
+        self.mem.mark_code_page(pc / CODE_PAGE_SIZE);
+
+        Ok(self.blocks.insert(pc, Block::scan(pc, thumb, &code)))
+    }
+
+    fn execute(&mut self, instruction: &BlockInstruction) {
+        self.mem.fetch_address = instruction.address;
+
+        match instruction.instruction {
+            Armv5teInstruction::Armv4t => {
+                self.cpu.step(&mut self.mem);
+            }
+            Armv5teInstruction::InterworkingLoad => {
+                self.mem.last_read = None;
+                self.cpu.step(&mut self.mem);
+
+                // pc is loaded last, nothing is loaded if the condition failed
+                if let Some(x) = self.mem.last_read.take() {
+                    armv5te::interworking_branch(&mut self.cpu, x);
+                }
+            }
+            x => armv5te::execute(&mut self.cpu, &mut self.mem, x, instruction.address, instruction.raw),
+        }
+    }
+
+    fn registers(&self) -> [u32; 17] {
+        REGISTERS.map(|x| self.cpu.reg_get(Mode::User, x))
+    }
+
+    // the faulting instruction is not completed: registers are restored to `registers`, taken before it, and pc is left at it.
+    // memory written by the accesses before the faulting one (e.g. in stm) is kept
+    fn check_fault(&mut self, pc: u32, registers: Option<&[u32; 17]>) -> ArmEngineResult<()> {
+        if let Some((address, size, access)) = self.mem.fault.take() {
+            if let Some(registers) = registers {
+                for (&reg, &value) in REGISTERS.iter().zip(registers) {
+                    self.cpu.reg_set(Mode::User, reg, value);
+                }
+            }
+            self.cpu.reg_set(Mode::User, reg::PC, pc);
+
+            return Err(anyhow::Error::msg(MemoryFault { address, size, access, pc }));
+        }
+
+        Ok(())
+    }
+
+    fn trace(&mut self, instruction: &BlockInstruction, thumb: bool) {
+        if let Some(hook) = &mut self.trace_hook {
+            hook(&TracedInstruction {
+                address: instruction.address,
+                thumb,
+                raw: instruction.raw,
+            });
+        }
+    }
+
+    /// Records address and size of each guest write until taken, to compare with another engine
+    #[cfg(feature = "unicorn")]
+    pub(crate) fn record_writes(&mut self) {
+        self.mem.writes = Some(Vec::new());
+    }
+
+    #[cfg(feature = "unicorn")]
+    pub(crate) fn take_writes(&mut self) -> Vec<(u32, u32)> {
+        self.mem.writes.as_mut().map(core::mem::take).unwrap_or_default()
+    }
 }
 
 impl ArmEngine for Armv4tEmuEngine {
-    fn run(&mut self, end: u32, hook: Range<u32>, mut count: u32) -> ArmEngineResult<()> {
-        loop {
+    fn run(&mut self, end: u32, hook: Range<u32>, mut count: u32) -> ArmEngineResult<StopReason> {
+        let mut skip_breakpoint = self.last_breakpoint.take();
+
+        while count > 0 {
+            for page in self.mem.written_code_pages.drain(..) {
+                self.blocks.invalidate_page(page);
+            }
+
             let pc = self.cpu.reg_get(Mode::User, reg::PC);
-            if pc == end || hook.contains(&pc) || count == 0 {
+            if pc == end || hook.contains(&pc) {
                 break;
             }
 
-            self.cpu.step(&mut self.mem);
-            count -= 1;
+            if self.breakpoints.contains(&pc) && skip_breakpoint.take() != Some(pc) {
+                self.last_breakpoint = Some(pc);
+
+                return Ok(StopReason::Breakpoint(pc));
+            }
+            skip_breakpoint = None;
+
+            let thumb = self.cpu.reg_get(Mode::User, reg::CPSR) & (1 << 5) != 0;
+            let block = self.block(pc, thumb)?;
+
+            // stop addresses are checked per block, the block runs up to the first one in it
+            let mut stop = block.end;
+            if end > pc && end < stop {
+                stop = end;
+            }
+            if hook.start < stop && hook.end > pc + 1 {
+                stop = stop.min(hook.start.max(pc + 1));
+            }
+            if let Some(&x) = self.breakpoints.range(pc + 1..stop).next() {
+                stop = x;
+            }
+
+            let instruction_size = if thumb { 2 } else { 4 };
+            for instruction in block.instructions.iter().take(count as usize).take_while(|x| x.address < stop) {
+                self.trace(instruction, thumb);
+                count -= 1;
+
+                if instruction.memory {
+                    // only memory accesses fault, so registers are saved just for them
+                    let registers = self.registers();
+                    self.execute(instruction);
+                    self.check_fault(instruction.address, Some(&registers))?;
+
+                    if let Some(x) = self.mem.watch_hit.take() {
+                        return Ok(x);
+                    }
+
+                    // the code has been overwritten
+                    if !self.mem.written_code_pages.is_empty() {
+                        break;
+                    }
+                } else {
+                    self.execute(instruction);
+                }
+
+                // taken conditional branch
+                if self.cpu.reg_get(Mode::User, reg::PC) != instruction.address + instruction_size {
+                    break;
+                }
+            }
         }
 
-        Ok(())
+        Ok(StopReason::Done)
     }
 
     fn reg_write(&mut self, reg: ArmRegister, value: u32) {
@@ -51,20 +202,36 @@ impl ArmEngine for Armv4tEmuEngine {
         self.cpu.reg_get(Mode::User, reg.into_armv4t())
     }
 
-    fn mem_map(&mut self, address: u32, size: usize, _permission: MemoryPermission) {
-        self.mem.map(address, size);
+    fn mem_map(&mut self, address: u32, size: usize, permission: MemoryPermission) {
+        self.mem.map(address, size, permission);
     }
 
     fn mem_write(&mut self, address: u32, data: &[u8]) -> ArmEngineResult<()> {
-        self.mem.write_range(address, data);
-
-        Ok(())
+        self.mem.write_range(address, data)
     }
 
     fn mem_read(&mut self, address: u32, size: usize) -> ArmEngineResult<Vec<u8>> {
-        let result = self.mem.read_range(address, size);
+        self.mem.read_range(address, size)
+    }
 
-        Ok(result)
+    fn add_breakpoint(&mut self, address: u32) {
+        self.breakpoints.insert(address);
+    }
+
+    fn remove_breakpoint(&mut self, address: u32) {
+        self.breakpoints.remove(&address);
+    }
+
+    fn add_watchpoint(&mut self, range: Range<u32>, kind: WatchpointKind) {
+        self.mem.watchpoints.push((range, kind));
+    }
+
+    fn remove_watchpoint(&mut self, range: Range<u32>, kind: WatchpointKind) {
+        self.mem.watchpoints.retain(|x| x.0 != range || x.1 != kind);
+    }
+
+    fn set_trace_hook(&mut self, hook: Option<TraceHook>) {
+        self.trace_hook = hook;
     }
 }
 
@@ -92,79 +259,207 @@ impl ArmRegister {
     }
 }
 
+const PERMISSION_PAGE_SIZE: u32 = 0x1000;
+const PERMISSION_READ: u8 = 4;
+const PERMISSION_WRITE: u8 = 2;
+const PERMISSION_EXECUTE: u8 = 1;
+
+struct Page {
+    data: [u8; 0x10000],
+    // MemoryPermission bits per 4KB page, 0 if unmapped
+    permissions: [u8; 0x10],
+}
+
 struct Armv4tEmuMemory {
-    pages: [Option<Box<[u8; 0x10000]>>; 0x10000],
+    pages: [Option<Box<Page>>; 0x10000],
+    // first invalid access by the current instruction
+    fault: Option<(u32, u32, MemoryAccess)>,
+    watchpoints: Vec<(Range<u32>, WatchpointKind)>,
+    watch_hit: Option<StopReason>,
+    // instruction fetches are not memory reads for watchpoints
+    fetch_address: u32,
+    // last word read other than the instruction fetch
+    last_read: Option<u32>,
+    // bitmap of pages with scanned blocks, writes to them invalidate the blocks
+    code_pages: Vec<u64>,
+    written_code_pages: Vec<u32>,
+    // address and size of guest writes, while recording
+    writes: Option<Vec<(u32, u32)>>,
 }
 
 impl Armv4tEmuMemory {
-    #[allow(dead_code)]
     fn new() -> Self {
         Self {
             pages: array::from_fn(|_| None),
+            fault: None,
+            watchpoints: Vec::new(),
+            watch_hit: None,
+            fetch_address: 0,
+            last_read: None,
+            code_pages: vec![0; (0x1_0000_0000 / CODE_PAGE_SIZE as u64 / 64) as usize],
+            written_code_pages: Vec::new(),
+            writes: None,
+        }
+    }
+
+    fn mark_code_page(&mut self, page: u32) {
+        self.code_pages[page as usize / 64] |= 1 << (page % 64);
+    }
+
+    fn record_write(&mut self, address: u32, size: u32) {
+        if let Some(x) = &mut self.writes {
+            x.push((address, size));
+        }
+    }
+
+    fn check_code_write(&mut self, address: u32, size: usize) {
+        if size == 0 {
+            return;
+        }
+
+        let last = address.wrapping_add(size as u32 - 1);
+        for page in address / CODE_PAGE_SIZE..=last / CODE_PAGE_SIZE {
+            let (index, bit) = (page as usize / 64, 1 << (page % 64));
+
+            if self.code_pages[index] & bit != 0 {
+                self.code_pages[index] &= !bit;
+                self.written_code_pages.push(page);
+            }
+        }
+    }
+
+    fn check_watchpoints(&mut self, address: u32, size: u32, write: bool) {
+        if self.watchpoints.is_empty() || (!write && address == self.fetch_address) {
+            return;
         }
+
+        let hit = self
+            .watchpoints
+            .iter()
+            .any(|(range, kind)| kind.matches(write) && address < range.end && range.start < address.saturating_add(size));
+        if hit && self.watch_hit.is_none() {
+            self.watch_hit = Some(StopReason::Watchpoint { address, size, write });
+        }
+    }
+
+    fn permission(&self, permission_page: usize) -> u8 {
+        self.pages[permission_page / 0x10]
+            .as_ref()
+            .map_or(0, |x| x.permissions[permission_page % 0x10])
+    }
+
+    fn permission_pages(address: u32, size: usize) -> Range<usize> {
+        let start = address as u64 / PERMISSION_PAGE_SIZE as u64;
+        let end = (address as u64 + size as u64).div_ceil(PERMISSION_PAGE_SIZE as u64);
+
+        start as usize..end as usize
     }
 
-    fn map(&mut self, address: u32, size: usize) {
+    fn map(&mut self, address: u32, size: usize, permission: MemoryPermission) {
         let page_start = address & !0xffff;
         let page_end = (address + size as u32 + 0xffff) & !0xffff;
 
         for page in (page_start..page_end).step_by(0x10000) {
             let page_data = &mut self.pages[page as usize / 0x10000];
             if page_data.is_none() {
-                *page_data = Some(Box::new([0; 0x10000]));
+                *page_data = Some(Box::new(Page {
+                    data: [0; 0x10000],
+                    permissions: [0; 0x10],
+                }));
             }
         }
+
+        for x in Self::permission_pages(address, size) {
+            self.pages[x / 0x10].as_mut().unwrap().permissions[x % 0x10] = permission as u8;
+        }
+        self.check_code_write(address, size);
     }
 
-    fn read_range(&self, address: u32, size: usize) -> Vec<u8> {
+    // records a fault and returns false if the access is not allowed
+    fn check_access(&mut self, address: u32, size: u32, access: MemoryAccess) -> bool {
+        let required = match access {
+            MemoryAccess::Read => PERMISSION_READ,
+            MemoryAccess::Write => PERMISSION_WRITE,
+            MemoryAccess::Execute => PERMISSION_EXECUTE,
+        };
+
+        let allowed = Self::permission_pages(address, size as usize).all(|x| self.permission(x) & required != 0);
+        if !allowed && self.fault.is_none() {
+            self.fault = Some((address, size, access));
+        }
+
+        allowed
+    }
+
+    fn check_mapped(&self, address: u32, size: usize) -> ArmEngineResult<()> {
+        let pages = Self::permission_pages(address, size);
+
+        if let Some(x) = pages.clone().find(|&x| self.permission(x) == 0) {
+            anyhow::bail!("Access to unmapped address {:#x}", (x as u32 * PERMISSION_PAGE_SIZE).max(address))
+        }
+
+        Ok(())
+    }
+
+    fn read_range(&self, address: u32, size: usize) -> ArmEngineResult<Vec<u8>> {
+        self.check_mapped(address, size)?;
+
         let mut result = Vec::with_capacity(size);
         let mut remaining_size = size;
         let mut current_address = address;
 
         while remaining_size > 0 {
             let page_address = current_address & !0xffff;
-            let page_data = self.pages[page_address as usize / 0x10000].as_ref().unwrap();
+            let page_data = self.pages[page_address as usize / 0x10000]
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Access to unmapped address {:#x}", current_address))?;
             let offset = (current_address - page_address) as usize;
             let available_bytes = (0x10000 - offset).min(remaining_size);
 
-            result.extend_from_slice(&page_data[offset..offset + available_bytes]);
+            result.extend_from_slice(&page_data.data[offset..offset + available_bytes]);
             remaining_size -= available_bytes;
             current_address += available_bytes as u32;
         }
 
-        result
+        Ok(result)
     }
 
-    fn write_range(&mut self, address: u32, data: &[u8]) {
+    fn write_range(&mut self, address: u32, data: &[u8]) -> ArmEngineResult<()> {
+        self.check_mapped(address, data.len())?;
+        self.check_code_write(address, data.len());
+
         let mut current_address = address;
         let mut data_index = 0;
 
         while data_index < data.len() {
             let page_address = current_address & !0xffff;
-            let page_data = self.pages[page_address as usize / 0x10000].as_mut().unwrap();
+            let page_data = self.pages[page_address as usize / 0x10000]
+                .as_mut()
+                .ok_or_else(|| anyhow::anyhow!("Access to unmapped address {:#x}", current_address))?;
             let offset = (current_address - page_address) as usize;
             let available_bytes = (0x10000 - offset).min(data.len() - data_index);
 
-            page_data[offset..offset + available_bytes].copy_from_slice(&data[data_index..data_index + available_bytes]);
+            page_data.data[offset..offset + available_bytes].copy_from_slice(&data[data_index..data_index + available_bytes]);
             data_index += available_bytes;
             current_address += available_bytes as u32;
         }
+
+        Ok(())
     }
 
+    // accesses are checked against permissions first, which are only set on mapped pages
     fn get_page(&mut self, addr: u32) -> &mut [u8; 0x10000] {
-        let page_address = addr & !0xffff;
-        let page_data = self.pages[page_address as usize / 0x10000].as_mut();
-
-        if let Some(x) = page_data {
-            x
-        } else {
-            panic!("Access to unmapped address {:#x}", addr); // TODO can we propagate error?
-        }
+        &mut self.pages[addr as usize / 0x10000].as_mut().unwrap().data
     }
 }
 
 impl Memory for Armv4tEmuMemory {
     fn r8(&mut self, addr: u32) -> u8 {
+        if !self.check_access(addr, 1, MemoryAccess::Read) {
+            return 0;
+        }
+        self.check_watchpoints(addr, 1, false);
+
         let offset = addr & 0xffff;
 
         let data = self.get_page(addr);
@@ -173,6 +468,11 @@ impl Memory for Armv4tEmuMemory {
     }
 
     fn r16(&mut self, addr: u32) -> u16 {
+        if !self.check_access(addr, 2, MemoryAccess::Read) {
+            return 0;
+        }
+        self.check_watchpoints(addr, 2, false);
+
         let offset = addr & 0xffff;
 
         let data = self.get_page(addr);
@@ -181,16 +481,34 @@ impl Memory for Armv4tEmuMemory {
     }
 
     fn r32(&mut self, addr: u32) -> u32 {
+        if !self.check_access(addr, 4, MemoryAccess::Read) {
+            return 0;
+        }
+        self.check_watchpoints(addr, 4, false);
+
         let offset = addr & 0xffff;
 
         let data = self.get_page(addr);
-        (data[offset as usize] as u32)
+        let value = (data[offset as usize] as u32)
             | ((data[offset as usize + 1] as u32) << 8)
             | ((data[offset as usize + 2] as u32) << 16)
-            | ((data[offset as usize + 3] as u32) << 24)
+            | ((data[offset as usize + 3] as u32) << 24);
+
+        if addr != self.fetch_address {
+            self.last_read = Some(value);
+        }
+
+        value
     }
 
     fn w8(&mut self, addr: u32, val: u8) {
+        if !self.check_access(addr, 1, MemoryAccess::Write) {
+            return;
+        }
+        self.check_watchpoints(addr, 1, true);
+        self.check_code_write(addr, 1);
+        self.record_write(addr, 1);
+
         let offset = addr & 0xffff;
 
         let data = self.get_page(addr);
@@ -199,6 +517,13 @@ impl Memory for Armv4tEmuMemory {
     }
 
     fn w16(&mut self, addr: u32, val: u16) {
+        if !self.check_access(addr, 2, MemoryAccess::Write) {
+            return;
+        }
+        self.check_watchpoints(addr, 2, true);
+        self.check_code_write(addr, 2);
+        self.record_write(addr, 2);
+
         let offset = addr & 0xffff;
 
         let data = self.get_page(addr);
@@ -208,6 +533,13 @@ impl Memory for Armv4tEmuMemory {
     }
 
     fn w32(&mut self, addr: u32, val: u32) {
+        if !self.check_access(addr, 4, MemoryAccess::Write) {
+            return;
+        }
+        self.check_watchpoints(addr, 4, true);
+        self.check_code_write(addr, 4);
+        self.record_write(addr, 4);
+
         let offset = addr & 0xffff;
 
         let data = self.get_page(addr);
@@ -218,3 +550,162 @@ impl Memory for Armv4tEmuMemory {
         data[offset as usize + 3] = (val >> 24) as u8;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, rc::Rc, vec::Vec};
+    use core::cell::RefCell;
+
+    use crate::engine::{ArmEngine, ArmRegister, MemoryAccess, MemoryFault, MemoryPermission, StopReason, WatchpointKind};
+
+    use super::Armv4tEmuEngine;
+
+    // mov r0, #1; mov r1, #0x1100; str r0, [r1]; mov r0, #2
+    const CODE: [u32; 4] = [0xe3a00001, 0xe3a01c11, 0xe5810000, 0xe3a00002];
+
+    fn test_engine() -> anyhow::Result<Armv4tEmuEngine> {
+        let mut engine = Armv4tEmuEngine::new();
+
+        engine.mem_map(0x1000, 0x1000, MemoryPermission::ReadWriteExecute);
+        engine.mem_write(0x1000, &CODE.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>())?;
+        engine.reg_write(ArmRegister::Cpsr, 0x10);
+        engine.reg_write(ArmRegister::PC, 0x1000);
+
+        Ok(engine)
+    }
+
+    #[test]
+    fn test_breakpoint() -> anyhow::Result<()> {
+        let mut engine = test_engine()?;
+        engine.add_breakpoint(0x100c);
+
+        assert_eq!(engine.run(0x1010, 0..0, 100)?, StopReason::Breakpoint(0x100c));
+        assert_eq!(engine.reg_read(ArmRegister::R0), 1);
+
+        // resuming executes the instruction we stopped at
+        assert_eq!(engine.run(0x1010, 0..0, 100)?, StopReason::Done);
+        assert_eq!(engine.reg_read(ArmRegister::R0), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_watchpoint() -> anyhow::Result<()> {
+        let mut engine = test_engine()?;
+        engine.add_watchpoint(0x1100..0x1104, WatchpointKind::Write);
+
+        let reason = engine.run(0x1010, 0..0, 100)?;
+
+        assert_eq!(
+            reason,
+            StopReason::Watchpoint {
+                address: 0x1100,
+                size: 4,
+                write: true
+            }
+        );
+        assert_eq!(engine.reg_read(ArmRegister::PC), 0x100c);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trace_hook() -> anyhow::Result<()> {
+        let mut engine = test_engine()?;
+
+        let traced = Rc::new(RefCell::new(Vec::new()));
+        let traced_cloned = traced.clone();
+        engine.set_trace_hook(Some(Box::new(move |x| traced_cloned.borrow_mut().push((x.address, x.raw)))));
+
+        engine.run(0x1010, 0..0, 100)?;
+
+        assert_eq!(
+            *traced.borrow(),
+            [(0x1000, CODE[0]), (0x1004, CODE[1]), (0x1008, CODE[2]), (0x100c, CODE[3])]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_self_modifying_code() -> anyhow::Result<()> {
+        let mut engine = test_engine()?;
+
+        // ldr r2, [pc, #4]; str r2, [pc, #-4]; mov r0, #1; clz r0, r1
+        let code = [0xe59f2004u32, 0xe50f2004, 0xe3a00001, 0xe16f0f11];
+        engine.mem_write(0x1000, &code.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>())?;
+        engine.reg_write(ArmRegister::R1, 0);
+
+        // scan the block at 0x1008 first
+        engine.reg_write(ArmRegister::PC, 0x1008);
+        engine.run(0x100c, 0..0, 100)?;
+        engine.reg_write(ArmRegister::PC, 0x1000);
+
+        let traced = Rc::new(RefCell::new(Vec::new()));
+        let traced_cloned = traced.clone();
+        engine.set_trace_hook(Some(Box::new(move |x| traced_cloned.borrow_mut().push((x.address, x.raw)))));
+
+        engine.run(0x100c, 0..0, 100)?;
+
+        // mov r0, #1 is replaced with clz r0, r1
+        assert_eq!(traced.borrow()[2], (0x1008, 0xe16f0f11));
+        assert_eq!(engine.reg_read(ArmRegister::R0), 32);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_fault() -> anyhow::Result<()> {
+        let mut engine = Armv4tEmuEngine::new();
+        engine.mem_map(0x1000, 0x1000, MemoryPermission::ReadExecute);
+        engine.mem_map(0x2000, 0x1000, MemoryPermission::ReadWrite);
+
+        // ldr r0, [r1], #4; str r0, [r2, #4]!; bx r3
+        let code = [0xe4910004u32, 0xe5a20004, 0xe12fff13];
+        engine.mem_write(0x1000, &code.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>())?;
+        engine.reg_write(ArmRegister::Cpsr, 0x10);
+        engine.reg_write(ArmRegister::PC, 0x1000);
+
+        let run = |engine: &mut Armv4tEmuEngine| engine.run(0, 0..0, 100).unwrap_err().downcast_ref::<MemoryFault>().copied();
+
+        // unmapped, in the same 64KB page as mapped ones
+        engine.reg_write(ArmRegister::R0, 0x1234);
+        engine.reg_write(ArmRegister::R1, 0x8000);
+        let fault = MemoryFault {
+            address: 0x8000,
+            size: 4,
+            access: MemoryAccess::Read,
+            pc: 0x1000,
+        };
+        assert_eq!(run(&mut engine), Some(fault));
+        assert_eq!(engine.reg_read(ArmRegister::PC), 0x1000);
+        // neither the load nor the writeback happened
+        assert_eq!(engine.reg_read(ArmRegister::R0), 0x1234);
+        assert_eq!(engine.reg_read(ArmRegister::R1), 0x8000);
+
+        engine.reg_write(ArmRegister::R1, 0x2000);
+        engine.reg_write(ArmRegister::R2, 0x10fc);
+        let fault = MemoryFault {
+            address: 0x1100,
+            size: 4,
+            access: MemoryAccess::Write,
+            pc: 0x1004,
+        };
+        assert_eq!(run(&mut engine), Some(fault));
+        assert_eq!(engine.reg_read(ArmRegister::R1), 0x2004);
+        assert_eq!(engine.reg_read(ArmRegister::R2), 0x10fc);
+        assert_eq!(engine.mem_read(0x1100, 4)?, [0; 4]);
+
+        engine.reg_write(ArmRegister::R2, 0x2000);
+        engine.reg_write(ArmRegister::R3, 0x2000);
+        let fault = MemoryFault {
+            address: 0x2000,
+            size: 4,
+            access: MemoryAccess::Execute,
+            pc: 0x2000,
+        };
+        assert_eq!(run(&mut engine), Some(fault));
+
+        Ok(())
+    }
+}
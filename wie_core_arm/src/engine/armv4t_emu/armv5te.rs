@@ -0,0 +1,714 @@
+// ARMv5TE instructions on top of armv4t_emu, which only implements ARMv4T.
+// Instructions added in v5TE are decoded and executed here before armv4t_emu sees them.
+
+use armv4t_emu::{reg, Cpu, Memory, Mode};
+
+const CPSR_Q: u32 = 1 << 27;
+const CPSR_T: u32 = 1 << 5;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Armv5teInstruction {
+    /// Plain ARMv4T instruction, executed by armv4t_emu
+    Armv4t,
+    /// Executed by armv4t_emu, loads pc. bit 0 of the loaded value selects the instruction set
+    InterworkingLoad,
+    BlxImmediate,
+    Pld,
+    BlxRegister,
+    Clz,
+    SaturatingAddSubtract,
+    SignedMultiply,
+    LoadStoreDoubleword,
+    ThumbBlxSuffix,
+    ThumbBlxRegister,
+}
+
+pub fn decode(raw: u32, thumb: bool) -> Armv5teInstruction {
+    if thumb {
+        return if raw >> 11 == 0b11101 {
+            Armv5teInstruction::ThumbBlxSuffix
+        } else if raw & 0xff87 == 0x4780 {
+            Armv5teInstruction::ThumbBlxRegister
+        } else if raw & 0xff00 == 0xbd00 {
+            // pop with pc
+            Armv5teInstruction::InterworkingLoad
+        } else {
+            Armv5teInstruction::Armv4t
+        };
+    }
+
+    if raw >> 28 == 0xf {
+        return if (raw >> 25) & 7 == 0b101 {
+            Armv5teInstruction::BlxImmediate
+        } else if raw & 0x0d70_f000 == 0x0550_f000 {
+            Armv5teInstruction::Pld
+        } else {
+            Armv5teInstruction::Armv4t
+        };
+    }
+
+    if raw & 0x0fff_fff0 == 0x012f_ff30 {
+        Armv5teInstruction::BlxRegister
+    } else if raw & 0x0fff_0ff0 == 0x016f_0f10 {
+        Armv5teInstruction::Clz
+    } else if raw & 0x0f90_0ff0 == 0x0100_0050 {
+        Armv5teInstruction::SaturatingAddSubtract
+    } else if raw & 0x0f90_0090 == 0x0100_0080 {
+        Armv5teInstruction::SignedMultiply
+    } else if raw & 0x0e10_00d0 == 0x0000_00d0 {
+        Armv5teInstruction::LoadStoreDoubleword
+    } else if raw & 0x0c50_f000 == 0x0410_f000 || raw & 0x0e10_8000 == 0x0810_8000 {
+        // ldr pc, or ldm with pc in the list
+        Armv5teInstruction::InterworkingLoad
+    } else {
+        Armv5teInstruction::Armv4t
+    }
+}
+
+/// Executes an instruction added in ARMv5TE, which isn't `Armv4t` or `InterworkingLoad`
+pub fn execute<M: Memory>(cpu: &mut Cpu, mem: &mut M, instruction: Armv5teInstruction, pc: u32, raw: u32) {
+    match instruction {
+        Armv5teInstruction::ThumbBlxSuffix => {
+            // the bl prefix before it has put the upper half of the offset in lr
+            let target = get(cpu, reg::LR as u32).wrapping_add((raw & 0x7ff) << 1) & !3;
+
+            set(cpu, reg::LR as u32, (pc + 2) | 1);
+            interworking_branch(cpu, target);
+        }
+        Armv5teInstruction::ThumbBlxRegister => {
+            let target = get(cpu, (raw >> 3) & 0xf);
+
+            set(cpu, reg::LR as u32, (pc + 2) | 1);
+            interworking_branch(cpu, target);
+        }
+        Armv5teInstruction::BlxImmediate => {
+            // h bit selects the halfword
+            let offset = (sign_extend(raw & 0xffffff, 24) << 2) | ((raw >> 23) & 2) as i32;
+
+            set(cpu, reg::LR as u32, pc + 4);
+            interworking_branch(cpu, pc.wrapping_add(8).wrapping_add_signed(offset) | 1);
+        }
+        Armv5teInstruction::Pld => {
+            // no cache to preload
+            set(cpu, reg::PC as u32, pc + 4);
+        }
+        _ => {
+            set(cpu, reg::PC as u32, pc + 4);
+            if !condition_passed(get(cpu, reg::CPSR as u32), raw >> 28) {
+                return;
+            }
+
+            match instruction {
+                Armv5teInstruction::BlxRegister => {
+                    let target = get(cpu, raw & 0xf);
+
+                    set(cpu, reg::LR as u32, pc + 4);
+                    interworking_branch(cpu, target);
+                }
+                Armv5teInstruction::Clz => {
+                    let value = get(cpu, raw & 0xf).leading_zeros();
+
+                    set(cpu, (raw >> 12) & 0xf, value);
+                }
+                Armv5teInstruction::SaturatingAddSubtract => saturating_add_subtract(cpu, raw),
+                Armv5teInstruction::SignedMultiply => signed_multiply(cpu, raw),
+                Armv5teInstruction::LoadStoreDoubleword => load_store_doubleword(cpu, mem, pc, raw),
+                _ => unreachable!("{:?} is executed by armv4t_emu", instruction),
+            }
+        }
+    }
+}
+
+/// Branches to `target`, switching to thumb if bit 0 is set
+pub fn interworking_branch(cpu: &mut Cpu, target: u32) {
+    let cpsr = get(cpu, reg::CPSR as u32);
+
+    if target & 1 != 0 {
+        set(cpu, reg::CPSR as u32, cpsr | CPSR_T);
+        set(cpu, reg::PC as u32, target & !1);
+    } else {
+        set(cpu, reg::CPSR as u32, cpsr & !CPSR_T);
+        set(cpu, reg::PC as u32, target & !3);
+    }
+}
+
+/// Whether the instruction may write pc, which ends a basic block
+pub fn is_branch(raw: u32, thumb: bool) -> bool {
+    if thumb {
+        return match raw >> 11 {
+            // conditional branch and swi, b, blx suffix, bl suffix
+            0b11010 | 0b11011 | 0b11100 | 0b11101 | 0b11111 => true,
+            // add, mov to pc and bx, blx
+            0b01000 if raw & (1 << 10) != 0 => (raw >> 8) & 3 == 3 || ((raw & 7) | ((raw >> 4) & 8)) == 15,
+            _ => raw & 0xff00 == 0xbd00,
+        };
+    }
+
+    match (raw >> 25) & 7 {
+        _ if raw >> 28 == 0xf => true,
+        // data processing, bx and loads with rd == pc
+        0b000 | 0b001 | 0b010 | 0b011 => (raw >> 12) & 0xf == 15 || raw & 0x0ff0_00d0 == 0x0120_0010,
+        // ldm with pc
+        0b100 => raw & (1 << 20) != 0 && raw & (1 << 15) != 0,
+        // b, bl
+        0b101 => true,
+        // swi
+        0b111 => raw & (1 << 24) != 0,
+        _ => false,
+    }
+}
+
+/// Whether the instruction may read or write memory other than its own fetch, which is all that can fault or hit a watchpoint
+pub fn accesses_memory(raw: u32, thumb: bool) -> bool {
+    if thumb {
+        return match raw >> 12 {
+            // ldr pc-relative
+            0b0100 => raw >> 11 == 0b01001,
+            // load/store with register offset, immediate offset, halfword, sp-relative, ldmia/stmia
+            0b0101 | 0b0110 | 0b0111 | 0b1000 | 0b1001 | 0b1100 => true,
+            // push, pop
+            0b1011 => raw & 0x0600 == 0x0400,
+            _ => false,
+        };
+    }
+
+    match (raw >> 25) & 7 {
+        // halfword and doubleword transfers and swp, along with multiplies which share the encoding space
+        0b000 => raw & 0x90 == 0x90,
+        // ldr, str, ldm, stm, ldc, stc
+        0b010 | 0b011 | 0b100 | 0b110 => true,
+        _ => false,
+    }
+}
+
+fn get(cpu: &Cpu, register: u32) -> u32 {
+    cpu.reg_get(Mode::User, register as u8)
+}
+
+fn set(cpu: &mut Cpu, register: u32, value: u32) {
+    cpu.reg_set(Mode::User, register as u8, value)
+}
+
+fn set_q(cpu: &mut Cpu) {
+    let cpsr = get(cpu, reg::CPSR as u32);
+
+    set(cpu, reg::CPSR as u32, cpsr | CPSR_Q);
+}
+
+// qadd, qsub, qdadd, qdsub
+fn saturating_add_subtract(cpu: &mut Cpu, raw: u32) {
+    let op = (raw >> 21) & 3;
+    let rm = get(cpu, raw & 0xf) as i32 as i64;
+    let rn = get(cpu, (raw >> 16) & 0xf) as i32 as i64;
+
+    let (operand, doubled) = if op & 2 != 0 { saturate(rn * 2) } else { (rn as i32, false) };
+    let (result, saturated) = if op & 1 != 0 {
+        saturate(rm - operand as i64)
+    } else {
+        saturate(rm + operand as i64)
+    };
+
+    set(cpu, (raw >> 12) & 0xf, result as u32);
+    if doubled || saturated {
+        set_q(cpu);
+    }
+}
+
+// smla<x><y>, smlaw<y>, smulw<y>, smlal<x><y>, smul<x><y>
+fn signed_multiply(cpu: &mut Cpu, raw: u32) {
+    let op = (raw >> 21) & 3;
+    let rd = (raw >> 16) & 0xf;
+    let rn = (raw >> 12) & 0xf;
+    let rm = get(cpu, raw & 0xf);
+    let rs = get(cpu, (raw >> 8) & 0xf);
+
+    let x = halfword(rm, raw & (1 << 5) != 0);
+    let y = halfword(rs, raw & (1 << 6) != 0);
+
+    match op {
+        0 => {
+            let (result, overflow) = (x * y).overflowing_add(get(cpu, rn) as i32);
+
+            set(cpu, rd, result as u32);
+            if overflow {
+                set_q(cpu);
+            }
+        }
+        1 => {
+            let product = ((rm as i32 as i64 * y as i64) >> 16) as i32;
+
+            if raw & (1 << 5) == 0 {
+                let (result, overflow) = product.overflowing_add(get(cpu, rn) as i32);
+
+                set(cpu, rd, result as u32);
+                if overflow {
+                    set_q(cpu);
+                }
+            } else {
+                set(cpu, rd, product as u32);
+            }
+        }
+        2 => {
+            let accumulator = ((get(cpu, rd) as u64) << 32) | get(cpu, rn) as u64;
+            let result = accumulator.wrapping_add((x * y) as i64 as u64);
+
+            set(cpu, rn, result as u32);
+            set(cpu, rd, (result >> 32) as u32);
+        }
+        _ => set(cpu, rd, (x * y) as u32),
+    }
+}
+
+// ldrd, strd
+fn load_store_doubleword<M: Memory>(cpu: &mut Cpu, mem: &mut M, pc: u32, raw: u32) {
+    let rn = (raw >> 16) & 0xf;
+    let rd = (raw >> 12) & 0xe;
+
+    let base = if rn == reg::PC as u32 { pc + 8 } else { get(cpu, rn) };
+    let offset = if raw & (1 << 22) != 0 {
+        ((raw >> 4) & 0xf0) | (raw & 0xf)
+    } else {
+        get(cpu, raw & 0xf)
+    };
+    let offset_base = if raw & (1 << 23) != 0 {
+        base.wrapping_add(offset)
+    } else {
+        base.wrapping_sub(offset)
+    };
+
+    let pre_indexed = raw & (1 << 24) != 0;
+    let address = if pre_indexed { offset_base } else { base };
+    let load = raw & (1 << 5) == 0;
+
+    if load {
+        let low = mem.r32(address);
+        let high = mem.r32(address.wrapping_add(4));
+
+        set(cpu, rd, low);
+        set(cpu, rd + 1, high);
+    } else {
+        mem.w32(address, get(cpu, rd));
+        mem.w32(address.wrapping_add(4), get(cpu, rd + 1));
+    }
+
+    let writeback = !pre_indexed || raw & (1 << 21) != 0;
+    if writeback && !(load && (rn == rd || rn == rd + 1)) {
+        set(cpu, rn, offset_base);
+    }
+}
+
+fn condition_passed(cpsr: u32, cond: u32) -> bool {
+    let n = cpsr & (1 << 31) != 0;
+    let z = cpsr & (1 << 30) != 0;
+    let c = cpsr & (1 << 29) != 0;
+    let v = cpsr & (1 << 28) != 0;
+
+    match cond {
+        0x0 => z,
+        0x1 => !z,
+        0x2 => c,
+        0x3 => !c,
+        0x4 => n,
+        0x5 => !n,
+        0x6 => v,
+        0x7 => !v,
+        0x8 => c && !z,
+        0x9 => !c || z,
+        0xa => n == v,
+        0xb => n != v,
+        0xc => !z && n == v,
+        0xd => z || n != v,
+        _ => true,
+    }
+}
+
+fn saturate(value: i64) -> (i32, bool) {
+    if value > i32::MAX as i64 {
+        (i32::MAX, true)
+    } else if value < i32::MIN as i64 {
+        (i32::MIN, true)
+    } else {
+        (value as i32, false)
+    }
+}
+
+fn halfword(value: u32, top: bool) -> i32 {
+    if top {
+        (value >> 16) as i16 as i32
+    } else {
+        value as i16 as i32
+    }
+}
+
+fn sign_extend(value: u32, bits: u32) -> i32 {
+    ((value << (32 - bits)) as i32) >> (32 - bits)
+}
+
+// instruction conformance tests, expected values follow the ARM Architecture Reference Manual
+#[cfg(test)]
+mod tests {
+    use alloc::{format, vec::Vec};
+
+    use crate::engine::{ArmEngine, ArmRegister, MemoryPermission};
+
+    use super::super::Armv4tEmuEngine;
+
+    use ArmRegister::{Cpsr, LR, PC, R0, R1, R2, R3, R4, R5, SP};
+
+    enum Code {
+        Arm(&'static [u32]),
+        Thumb(&'static [u16]),
+    }
+
+    struct Case {
+        name: &'static str,
+        code: Code,
+        steps: u32,
+        registers: &'static [(ArmRegister, u32)],
+        memory: &'static [(u32, u32)],
+        expected_registers: &'static [(ArmRegister, u32)],
+        expected_memory: &'static [(u32, u32)],
+    }
+
+    const USR: u32 = 0x10;
+    const THUMB: u32 = 0x30;
+    const Q: u32 = 1 << 27;
+    const Z: u32 = 1 << 30;
+
+    const CASES: &[Case] = &[
+        Case {
+            name: "clz r0, r1",
+            code: Code::Arm(&[0xe16f0f11]),
+            steps: 1,
+            registers: &[(R1, 0x00f0_0000)],
+            memory: &[],
+            expected_registers: &[(R0, 8), (PC, 0x1004)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "clz r0, r1 with zero",
+            code: Code::Arm(&[0xe16f0f11]),
+            steps: 1,
+            registers: &[(R1, 0)],
+            memory: &[],
+            expected_registers: &[(R0, 32)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "qadd r0, r1, r2 saturating",
+            code: Code::Arm(&[0xe1020051]),
+            steps: 1,
+            registers: &[(R1, 0x7fff_fff0), (R2, 0x20)],
+            memory: &[],
+            expected_registers: &[(R0, 0x7fff_ffff), (Cpsr, USR | Q)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "qadd r0, r1, r2",
+            code: Code::Arm(&[0xe1020051]),
+            steps: 1,
+            registers: &[(R1, 0xffff_fff0), (R2, 0x20)],
+            memory: &[],
+            expected_registers: &[(R0, 0x10), (Cpsr, USR)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "qsub r0, r1, r2 saturating",
+            code: Code::Arm(&[0xe1220051]),
+            steps: 1,
+            registers: &[(R1, 0x8000_0010), (R2, 0x20)],
+            memory: &[],
+            expected_registers: &[(R0, 0x8000_0000), (Cpsr, USR | Q)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "qdadd r0, r1, r2",
+            code: Code::Arm(&[0xe1420051]),
+            steps: 1,
+            registers: &[(R1, 1), (R2, 0x10)],
+            memory: &[],
+            expected_registers: &[(R0, 0x21), (Cpsr, USR)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "qdsub r0, r1, r2 saturating doubling",
+            code: Code::Arm(&[0xe1620051]),
+            steps: 1,
+            registers: &[(R1, 0), (R2, 0x4000_0000)],
+            memory: &[],
+            expected_registers: &[(R0, 0x8000_0001), (Cpsr, USR | Q)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "smulbb r0, r1, r2",
+            code: Code::Arm(&[0xe1600281]),
+            steps: 1,
+            registers: &[(R1, 0x0003_fffe), (R2, 0x0005_0007)],
+            memory: &[],
+            expected_registers: &[(R0, 0xffff_fff2)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "smultt r0, r1, r2",
+            code: Code::Arm(&[0xe16002e1]),
+            steps: 1,
+            registers: &[(R1, 0x0003_fffe), (R2, 0x0005_0007)],
+            memory: &[],
+            expected_registers: &[(R0, 15)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "smlabb r0, r1, r2, r3 overflowing",
+            code: Code::Arm(&[0xe1003281]),
+            steps: 1,
+            registers: &[(R1, 0x7fff), (R2, 0x7fff), (R3, 0x7fff_ffff)],
+            memory: &[],
+            expected_registers: &[(R0, 0xbfff_0000), (Cpsr, USR | Q)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "smlawb r0, r1, r2, r3",
+            code: Code::Arm(&[0xe1203281]),
+            steps: 1,
+            registers: &[(R1, 0x0001_0000), (R2, 3), (R3, 4)],
+            memory: &[],
+            expected_registers: &[(R0, 7), (Cpsr, USR)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "smulwt r0, r1, r2",
+            code: Code::Arm(&[0xe12002e1]),
+            steps: 1,
+            registers: &[(R1, 0xffff_0000), (R2, 0x0002_0000)],
+            memory: &[],
+            expected_registers: &[(R0, 0xffff_fffe)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "smlalbb r0, r1, r2, r3",
+            code: Code::Arm(&[0xe1410382]),
+            steps: 1,
+            registers: &[(R0, 0xffff_ffff), (R1, 0), (R2, 2), (R3, 3)],
+            memory: &[],
+            expected_registers: &[(R0, 5), (R1, 1)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "ldrd r2, [r0, #8]",
+            code: Code::Arm(&[0xe1c020d8]),
+            steps: 1,
+            registers: &[(R0, 0x2000)],
+            memory: &[(0x2008, 0x1122_3344), (0x200c, 0x5566_7788)],
+            expected_registers: &[(R0, 0x2000), (R2, 0x1122_3344), (R3, 0x5566_7788)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "ldrd r4, [r0, -r1]!",
+            code: Code::Arm(&[0xe12040d1]),
+            steps: 1,
+            registers: &[(R0, 0x2010), (R1, 8)],
+            memory: &[(0x2008, 5), (0x200c, 6)],
+            expected_registers: &[(R0, 0x2008), (R4, 5), (R5, 6)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "strd r2, [r0], #8",
+            code: Code::Arm(&[0xe0c020f8]),
+            steps: 1,
+            registers: &[(R0, 0x2000), (R2, 1), (R3, 2)],
+            memory: &[],
+            expected_registers: &[(R0, 0x2008)],
+            expected_memory: &[(0x2000, 1), (0x2004, 2)],
+        },
+        Case {
+            name: "pld [r0]",
+            code: Code::Arm(&[0xf5d0f000]),
+            steps: 1,
+            registers: &[(R0, 0x2000)],
+            memory: &[],
+            expected_registers: &[(PC, 0x1004)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "blx #0x1008",
+            code: Code::Arm(&[0xfa000000]),
+            steps: 1,
+            registers: &[],
+            memory: &[],
+            expected_registers: &[(PC, 0x1008), (LR, 0x1004), (Cpsr, THUMB)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "blx #0x100a",
+            code: Code::Arm(&[0xfb000000]),
+            steps: 1,
+            registers: &[],
+            memory: &[],
+            expected_registers: &[(PC, 0x100a), (LR, 0x1004), (Cpsr, THUMB)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "blx r1",
+            code: Code::Arm(&[0xe12fff31]),
+            steps: 1,
+            registers: &[(R1, 0x2001)],
+            memory: &[],
+            expected_registers: &[(PC, 0x2000), (LR, 0x1004), (Cpsr, THUMB)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "blxne r1 not taken",
+            code: Code::Arm(&[0x112fff31]),
+            steps: 1,
+            registers: &[(Cpsr, USR | Z), (R1, 0x2001), (LR, 0)],
+            memory: &[],
+            expected_registers: &[(PC, 0x1004), (LR, 0), (Cpsr, USR | Z)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "ldr pc, [sp], #4 to thumb",
+            code: Code::Arm(&[0xe49df004]),
+            steps: 1,
+            registers: &[(SP, 0x2800)],
+            memory: &[(0x2800, 0x3001)],
+            expected_registers: &[(PC, 0x3000), (SP, 0x2804), (Cpsr, THUMB)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "pop {r4, pc} to thumb",
+            code: Code::Arm(&[0xe8bd8010]),
+            steps: 1,
+            registers: &[(SP, 0x2800)],
+            memory: &[(0x2800, 7), (0x2804, 0x3001)],
+            expected_registers: &[(R4, 7), (PC, 0x3000), (SP, 0x2808), (Cpsr, THUMB)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "pop {r4, pc} to arm",
+            code: Code::Arm(&[0xe8bd8010]),
+            steps: 1,
+            registers: &[(SP, 0x2800)],
+            memory: &[(0x2800, 7), (0x2804, 0x3000)],
+            expected_registers: &[(R4, 7), (PC, 0x3000), (SP, 0x2808), (Cpsr, USR)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "thumb bl #0x1008",
+            code: Code::Thumb(&[0xf000, 0xf802]),
+            steps: 2,
+            registers: &[],
+            memory: &[],
+            expected_registers: &[(PC, 0x1008), (LR, 0x1005), (Cpsr, THUMB)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "thumb blx #0x1008",
+            code: Code::Thumb(&[0xf000, 0xe802]),
+            steps: 2,
+            registers: &[],
+            memory: &[],
+            expected_registers: &[(PC, 0x1008), (LR, 0x1005), (Cpsr, USR)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "thumb blx r1",
+            code: Code::Thumb(&[0x4788]),
+            steps: 1,
+            registers: &[(R1, 0x2000)],
+            memory: &[],
+            expected_registers: &[(PC, 0x2000), (LR, 0x1003), (Cpsr, USR)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "thumb pop {pc} to arm",
+            code: Code::Thumb(&[0xbd00]),
+            steps: 1,
+            registers: &[(SP, 0x2800)],
+            memory: &[(0x2800, 0x3000)],
+            expected_registers: &[(PC, 0x3000), (SP, 0x2804), (Cpsr, USR)],
+            expected_memory: &[],
+        },
+        Case {
+            name: "thumb pop {pc} to thumb",
+            code: Code::Thumb(&[0xbd00]),
+            steps: 1,
+            registers: &[(SP, 0x2800)],
+            memory: &[(0x2800, 0x3001)],
+            expected_registers: &[(PC, 0x3000), (SP, 0x2804), (Cpsr, THUMB)],
+            expected_memory: &[],
+        },
+    ];
+
+    fn run_case(case: &Case) -> anyhow::Result<()> {
+        let mut engine = Armv4tEmuEngine::new();
+        engine.mem_map(0x1000, 0x3000, MemoryPermission::ReadWriteExecute);
+        engine.reg_write(Cpsr, USR);
+
+        match case.code {
+            Code::Arm(code) => {
+                engine.mem_write(0x1000, &code.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>())?;
+                engine.reg_write(PC, 0x1000);
+            }
+            Code::Thumb(code) => {
+                engine.mem_write(0x1000, &code.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>())?;
+                engine.reg_write(PC, 0x1001);
+            }
+        }
+
+        for &(register, value) in case.registers {
+            engine.reg_write(register, value);
+        }
+        for &(address, value) in case.memory {
+            engine.mem_write(address, &value.to_le_bytes())?;
+        }
+
+        engine.run(0, 0..0, case.steps)?;
+
+        for &(register, value) in case.expected_registers {
+            let actual = engine.reg_read(register);
+            anyhow::ensure!(actual == value, "{:?}: expected {:#x}, got {:#x}", register, value, actual);
+        }
+        for &(address, value) in case.expected_memory {
+            let actual = u32::from_le_bytes(engine.mem_read(address, 4)?.try_into().unwrap());
+            anyhow::ensure!(actual == value, "[{:#x}]: expected {:#x}, got {:#x}", address, value, actual);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_armv5te_conformance() {
+        let failures = CASES
+            .iter()
+            .filter_map(|x| run_case(x).err().map(|e| format!("{}: {}", x.name, e)))
+            .collect::<Vec<_>>();
+
+        assert!(failures.is_empty(), "{:#?}", failures);
+    }
+
+    #[test]
+    fn test_accesses_memory() {
+        // ldr, strh, ldrd, swp, ldm, push, pop, ldr pc-relative, str sp-relative
+        let memory = [
+            (0xe5910000, false),
+            (0xe1c100b0, false),
+            (0xe1c020d0, false),
+            (0xe1010092, false),
+            (0xe8bd8000, false),
+        ]
+        .into_iter()
+        .chain([(0xb510, true), (0xbd10, true), (0x4801, true), (0x9001, true)]);
+        // mov, clz, b, adds, bx, bl prefix
+        let other =
+            [(0xe3a00001, false), (0xe16f0f11, false), (0xeafffffa, false)]
+                .into_iter()
+                .chain([(0x3001, true), (0x4770, true), (0xf000, true)]);
+
+        for (raw, thumb) in memory {
+            assert!(super::accesses_memory(raw, thumb), "{:#x}", raw);
+        }
+        for (raw, thumb) in other {
+            assert!(!super::accesses_memory(raw, thumb), "{:#x}", raw);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// armv4t_emu has no api to execute a predecoded instruction, its `step` fetches and decodes again.
+// so this only records where straight-line code ends, which instructions need ARMv5TE handling and which access memory.
+// `run` skips fault, watchpoint and code write checks for instructions which don't access memory, but not the decoding.
+
+use alloc::{collections::BTreeMap, rc::Rc, vec::Vec};
+
+use super::armv5te::Armv5teInstruction;
+
+pub const CODE_PAGE_SIZE: u32 = 0x1000;
+const MAX_BLOCK_INSTRUCTIONS: usize = 64;
+
+/// Instruction as classified at scan time, executed by `Armv4tEmuEngine::execute`
+pub struct BlockInstruction {
+    pub address: u32,
+    /// 32bit arm instruction, or 16bit thumb instruction
+    pub raw: u32,
+    pub instruction: Armv5teInstruction,
+    /// Whether it may access memory, so it can fault, hit a watchpoint or overwrite code
+    pub memory: bool,
+}
+
+// instructions from an entry address up to a branch, which never crosses a code page
+pub struct Block {
+    pub thumb: bool,
+    /// address after the last instruction
+    pub end: u32,
+    pub instructions: Vec<BlockInstruction>,
+}
+
+impl Block {
+    pub fn scan(address: u32, thumb: bool, code: &[u8]) -> Self {
+        let size = if thumb { 2 } else { 4 };
+        let mut instructions = Vec::new();
+
+        for (i, x) in code.chunks_exact(size).enumerate() {
+            let raw = x.iter().rev().fold(0, |acc, &x| (acc << 8) | x as u32);
+
+            instructions.push(BlockInstruction {
+                address: address + (i * size) as u32,
+                raw,
+                instruction: super::armv5te::decode(raw, thumb),
+                memory: super::armv5te::accesses_memory(raw, thumb),
+            });
+
+            if super::armv5te::is_branch(raw, thumb) {
+                break;
+            }
+        }
+
+        Self {
+            thumb,
+            end: address + (instructions.len() * size) as u32,
+            instructions,
+        }
+    }
+
+    /// The most bytes a block starting at `address` can span
+    pub fn max_size(address: u32, thumb: bool) -> usize {
+        let size = if thumb { 2 } else { 4 };
+
+        ((CODE_PAGE_SIZE - (address % CODE_PAGE_SIZE)) as usize).min(MAX_BLOCK_INSTRUCTIONS * size)
+    }
+}
+
+// scanned blocks, invalidated when their code page is written
+pub struct BlockMap {
+    // keyed by entry address, with bit 0 set for thumb
+    blocks: BTreeMap<u32, Rc<Block>>,
+}
+
+impl BlockMap {
+    pub fn new() -> Self {
+        Self { blocks: BTreeMap::new() }
+    }
+
+    pub fn get(&self, address: u32, thumb: bool) -> Option<Rc<Block>> {
+        self.blocks.get(&(address | thumb as u32)).cloned()
+    }
+
+    pub fn insert(&mut self, address: u32, block: Block) -> Rc<Block> {
+        let block = Rc::new(block);
+        self.blocks.insert(address | block.thumb as u32, block.clone());
+
+        block
+    }
+
+    pub fn invalidate_page(&mut self, page: u32) {
+        let start = page * CODE_PAGE_SIZE;
+        let keys = self
+            .blocks
+            .range(start..=start + (CODE_PAGE_SIZE - 1))
+            .map(|(&x, _)| x)
+            .collect::<Vec<_>>();
+
+        for key in keys {
+            self.blocks.remove(&key);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use alloc::{format, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    disasm::disassemble,
+    engine::{ArmEngine, ArmEngineResult, ArmRegister, Armv4tEmuEngine, MemoryPermission, StopReason, TraceHook, UnicornEngine, WatchpointKind},
+};
+
+const REGISTERS: [ArmRegister; 17] = [
+    ArmRegister::R0,
+    ArmRegister::R1,
+    ArmRegister::R2,
+    ArmRegister::R3,
+    ArmRegister::R4,
+    ArmRegister::R5,
+    ArmRegister::R6,
+    ArmRegister::R7,
+    ArmRegister::R8,
+    ArmRegister::SB,
+    ArmRegister::SL,
+    ArmRegister::FP,
+    ArmRegister::IP,
+    ArmRegister::SP,
+    ArmRegister::LR,
+    ArmRegister::PC,
+    ArmRegister::Cpsr,
+];
+// NZCVQ and thumb, other bits aren't emulated alike
+const CPSR_MASK: u32 = 0xf800_0020;
+
+/// Runs armv4t_emu and unicorn one instruction at a time, failing after the first instruction they disagree on registers or written memory.
+/// Reads, hooks and debugging go to armv4t_emu.
+pub struct LockstepEngine {
+    primary: Armv4tEmuEngine,
+    secondary: UnicornEngine,
+    // instructions run so far
+    count: u64,
+}
+
+impl LockstepEngine {
+    pub fn new() -> Self {
+        let mut primary = Armv4tEmuEngine::new();
+        primary.record_writes();
+
+        let mut secondary = UnicornEngine::new();
+        secondary.record_writes();
+
+        Self {
+            primary,
+            secondary,
+            count: 0,
+        }
+    }
+
+    fn compare(&mut self, pc: u32, thumb: bool) -> ArmEngineResult<()> {
+        let mut differences = Vec::new();
+
+        for register in REGISTERS {
+            let mask = if register == ArmRegister::Cpsr { CPSR_MASK } else { !0 };
+
+            let (primary, secondary) = (self.primary.reg_read(register) & mask, self.secondary.reg_read(register) & mask);
+            if primary != secondary {
+                differences.push(format!("{:?}: {:#x} != {:#x}", register, primary, secondary));
+            }
+        }
+
+        let mut writes = self.primary.take_writes();
+        writes.extend(self.secondary.take_writes());
+        writes.sort_unstable();
+        writes.dedup();
+
+        for (address, size) in writes {
+            let primary = self.primary.mem_read(address, size as usize)?;
+            let secondary = self.secondary.mem_read(address, size as usize)?;
+            if primary != secondary {
+                differences.push(format!("[{:#x}]: {:02x?} != {:02x?}", address, primary, secondary));
+            }
+        }
+
+        if differences.is_empty() {
+            return Ok(());
+        }
+
+        let instruction = self
+            .primary
+            .mem_read(pc, 4)
+            .ok()
+            .and_then(|x| disassemble(pc, &x, thumb).into_iter().next())
+            .map(|x| x.text)
+            .unwrap_or_default();
+
+        anyhow::bail!(
+            "Engines diverged after instruction #{} at {:#x} ({}), armv4t_emu != unicorn:\n{}",
+            self.count,
+            pc,
+            instruction,
+            differences.join("\n")
+        )
+    }
+}
+
+impl ArmEngine for LockstepEngine {
+    fn run(&mut self, end: u32, hook: Range<u32>, count: u32) -> ArmEngineResult<StopReason> {
+        for _ in 0..count {
+            let pc = self.primary.reg_read(ArmRegister::PC);
+            if pc == end || hook.contains(&pc) {
+                break;
+            }
+            let thumb = self.primary.reg_read(ArmRegister::Cpsr) & (1 << 5) != 0;
+
+            let reason = self.primary.run(end, hook.clone(), 1)?;
+            // stopped before the instruction
+            if let StopReason::Breakpoint(_) = reason {
+                return Ok(reason);
+            }
+
+            self.secondary.run(end, hook.clone(), 1)?;
+            self.count += 1;
+
+            self.compare(pc, thumb)?;
+
+            if reason != StopReason::Done {
+                return Ok(reason);
+            }
+        }
+
+        Ok(StopReason::Done)
+    }
+
+    fn reg_write(&mut self, reg: ArmRegister, value: u32) {
+        self.primary.reg_write(reg, value);
+        self.secondary.reg_write(reg, value);
+    }
+
+    fn reg_read(&self, reg: ArmRegister) -> u32 {
+        self.primary.reg_read(reg)
+    }
+
+    fn mem_map(&mut self, address: u32, size: usize, permission: MemoryPermission) {
+        self.primary.mem_map(address, size, permission);
+        self.secondary.mem_map(address, size, permission);
+    }
+
+    fn mem_write(&mut self, address: u32, data: &[u8]) -> ArmEngineResult<()> {
+        self.primary.mem_write(address, data)?;
+        self.secondary.mem_write(address, data)
+    }
+
+    fn mem_read(&mut self, address: u32, size: usize) -> ArmEngineResult<Vec<u8>> {
+        self.primary.mem_read(address, size)
+    }
+
+    fn add_breakpoint(&mut self, address: u32) {
+        self.primary.add_breakpoint(address);
+    }
+
+    fn remove_breakpoint(&mut self, address: u32) {
+        self.primary.remove_breakpoint(address);
+    }
+
+    fn add_watchpoint(&mut self, range: Range<u32>, kind: WatchpointKind) {
+        self.primary.add_watchpoint(range, kind);
+    }
+
+    fn remove_watchpoint(&mut self, range: Range<u32>, kind: WatchpointKind) {
+        self.primary.remove_watchpoint(range, kind);
+    }
+
+    fn set_trace_hook(&mut self, hook: Option<TraceHook>) {
+        self.primary.set_trace_hook(hook);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::engine::{ArmEngine, ArmRegister, MemoryPermission, StopReason};
+
+    use super::LockstepEngine;
+
+    // mov r0, #1; mov r1, #0x1100; str r0, [r1]; mov r0, #2
+    const CODE: [u32; 4] = [0xe3a00001, 0xe3a01c11, 0xe5810000, 0xe3a00002];
+
+    fn test_engine() -> anyhow::Result<LockstepEngine> {
+        let mut engine = LockstepEngine::new();
+
+        engine.mem_map(0x1000, 0x1000, MemoryPermission::ReadWriteExecute);
+        engine.mem_write(0x1000, &CODE.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>())?;
+        engine.reg_write(ArmRegister::Cpsr, 0x10);
+        engine.reg_write(ArmRegister::PC, 0x1000);
+
+        Ok(engine)
+    }
+
+    #[test]
+    fn test_lockstep() -> anyhow::Result<()> {
+        let mut engine = test_engine()?;
+
+        assert_eq!(engine.run(0x1010, 0..0, 100)?, StopReason::Done);
+        assert_eq!(engine.count, 4);
+        assert_eq!(engine.reg_read(ArmRegister::R0), 2);
+        assert_eq!(engine.mem_read(0x1100, 4)?, [1, 0, 0, 0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_divergence() -> anyhow::Result<()> {
+        let mut engine = test_engine()?;
+        engine.secondary.reg_write(ArmRegister::R5, 1);
+
+        let error = engine.run(0x1010, 0..0, 100).unwrap_err();
+        assert!(error.to_string().contains("R5: 0x0 != 0x1"));
+
+        Ok(())
+    }
+}
@@ -1,19 +1,40 @@
-use alloc::{format, vec::Vec};
-use core::ops::Range;
+use alloc::{collections::BTreeMap, format, rc::Rc, vec::Vec};
+use core::{cell::RefCell, ops::Range};
 
 use capstone::{arch::BuildsCapstone, Capstone};
 use unicorn_engine::{
+    ffi::uc_hook,
     unicorn_const::{uc_error, Arch, HookType, MemType, Mode, Permission},
     RegisterARM, Unicorn,
 };
 
 use crate::{
-    engine::{ArmEngine, ArmEngineResult, ArmRegister, MemoryPermission},
+    engine::{
+        ArmEngine, ArmEngineResult, ArmRegister, MemoryAccess, MemoryFault, MemoryPermission, StopReason, TraceHook, TracedInstruction,
+        WatchpointKind,
+    },
     ArmCore,
 };
 
+// shared with hook closures
+#[derive(Default)]
+struct HookState {
+    stop_reason: Option<StopReason>,
+    last_breakpoint: Option<u32>,
+    skip_breakpoint: Option<u32>,
+    trace_hook: Option<TraceHook>,
+    // address, size and access of the last invalid memory access
+    fault: Option<(u32, u32, MemoryAccess)>,
+    // address and size of guest writes, while recording
+    writes: Option<Vec<(u32, u32)>>,
+}
+
 pub struct UnicornEngine {
     uc: Unicorn<'static, ()>,
+    state: Rc<RefCell<HookState>>,
+    breakpoints: BTreeMap<u32, uc_hook>,
+    watchpoints: Vec<(Range<u32>, WatchpointKind, uc_hook)>,
+    trace_hook: Option<uc_hook>,
 }
 
 impl UnicornEngine {
@@ -22,9 +43,60 @@ impl UnicornEngine {
 
         // uc.add_block_hook(Self::code_hook).map_err(UnicornError)?;
         // uc.add_code_hook(0, 0xffff_ffff_ffff_ffff, Self::code_hook).unwrap();
-        uc.add_mem_hook(HookType::MEM_INVALID, 0, 0xffff_ffff_ffff_ffff, Self::mem_hook).unwrap();
 
-        Self { uc }
+        let mut engine = Self::from_unicorn(uc);
+
+        let state = engine.state.clone();
+        engine
+            .uc
+            .add_mem_hook(
+                HookType::MEM_INVALID,
+                0,
+                0xffff_ffff_ffff_ffff,
+                move |uc, mem_type, address, size, value| {
+                    let access = match mem_type {
+                        MemType::WRITE_UNMAPPED | MemType::WRITE_PROT => MemoryAccess::Write,
+                        MemType::FETCH_UNMAPPED | MemType::FETCH_PROT => MemoryAccess::Execute,
+                        _ => MemoryAccess::Read,
+                    };
+                    state.borrow_mut().fault = Some((address as u32, size as u32, access));
+
+                    Self::mem_hook(uc, mem_type, address, size, value)
+                },
+            )
+            .unwrap();
+
+        engine
+    }
+
+    /// Records address and size of each guest write until taken, to compare with another engine
+    pub(crate) fn record_writes(&mut self) {
+        self.state.borrow_mut().writes = Some(Vec::new());
+
+        let state = self.state.clone();
+        self.uc
+            .add_mem_hook(HookType::MEM_WRITE, 0, 0xffff_ffff_ffff_ffff, move |_, _, address, size, _| {
+                if let Some(x) = &mut state.borrow_mut().writes {
+                    x.push((address as u32, size as u32));
+                }
+
+                true
+            })
+            .unwrap();
+    }
+
+    pub(crate) fn take_writes(&mut self) -> Vec<(u32, u32)> {
+        self.state.borrow_mut().writes.as_mut().map(core::mem::take).unwrap_or_default()
+    }
+
+    fn from_unicorn(uc: Unicorn<'static, ()>) -> Self {
+        Self {
+            uc,
+            state: Rc::new(RefCell::new(HookState::default())),
+            breakpoints: BTreeMap::new(),
+            watchpoints: Vec::new(),
+            trace_hook: None,
+        }
     }
 
     #[allow(dead_code)]
@@ -49,9 +121,7 @@ impl UnicornEngine {
             .collect::<Vec<_>>()
             .join("\n");
 
-        let engine = UnicornEngine {
-            uc: Unicorn::try_from(uc.get_handle()).unwrap(),
-        };
+        let engine = UnicornEngine::from_unicorn(Unicorn::try_from(uc.get_handle()).unwrap());
         tracing::trace!("{}\n{}", insn_str, ArmCore::dump_regs_inner(&engine));
     }
 
@@ -84,9 +154,7 @@ impl UnicornEngine {
 
             true
         } else {
-            let engine = UnicornEngine {
-                uc: Unicorn::try_from(uc.get_handle()).unwrap(),
-            };
+            let engine = UnicornEngine::from_unicorn(Unicorn::try_from(uc.get_handle()).unwrap());
 
             tracing::error!(
                 "Invalid Memory Access\n\
@@ -104,7 +172,7 @@ impl UnicornEngine {
 }
 
 impl ArmEngine for UnicornEngine {
-    fn run(&mut self, end: u32, hook: Range<u32>, count: u32) -> ArmEngineResult<()> {
+    fn run(&mut self, end: u32, hook: Range<u32>, count: u32) -> ArmEngineResult<StopReason> {
         let hook = self
             .uc
             .add_code_hook(hook.start as u64, hook.end as u64, |uc, _, _| uc.emu_stop().unwrap())
@@ -119,10 +187,36 @@ impl ArmEngine for UnicornEngine {
             self.uc.reg_read(RegisterARM::PC).unwrap()
         };
 
-        self.uc.emu_start(pc, end as u64, 0, count as _).map_err(UnicornError)?;
+        {
+            let mut state = self.state.borrow_mut();
+            state.skip_breakpoint = state.last_breakpoint.take();
+            state.fault = None;
+        }
+
+        let result = self.uc.emu_start(pc, end as u64, 0, count as _).map_err(UnicornError);
         self.uc.remove_hook(hook).unwrap();
 
-        Ok(())
+        if result.is_err() {
+            if let Some((address, size, access)) = self.state.borrow_mut().fault.take() {
+                return Err(anyhow::Error::msg(MemoryFault {
+                    address,
+                    size,
+                    access,
+                    pc: self.uc.reg_read(RegisterARM::PC).unwrap() as u32,
+                }));
+            }
+        }
+        result?;
+
+        let mut state = self.state.borrow_mut();
+        state.skip_breakpoint = None;
+
+        let reason = state.stop_reason.take().unwrap_or(StopReason::Done);
+        if let StopReason::Breakpoint(x) = reason {
+            state.last_breakpoint = Some(x);
+        }
+
+        Ok(reason)
     }
 
     fn reg_write(&mut self, reg: ArmRegister, value: u32) {
@@ -146,6 +240,116 @@ impl ArmEngine for UnicornEngine {
     fn mem_read(&mut self, address: u32, size: usize) -> ArmEngineResult<Vec<u8>> {
         Ok(self.uc.mem_read_as_vec(address as u64, size).map_err(UnicornError)?)
     }
+
+    fn add_breakpoint(&mut self, address: u32) {
+        if self.breakpoints.contains_key(&address) {
+            return;
+        }
+
+        let state = self.state.clone();
+        let hook = self
+            .uc
+            .add_code_hook(address as u64, address as u64, move |uc, address, _| {
+                let mut state = state.borrow_mut();
+
+                // the first instruction after stopping at this breakpoint
+                if state.skip_breakpoint.take() == Some(address as u32) {
+                    return;
+                }
+
+                state.stop_reason = Some(StopReason::Breakpoint(address as u32));
+                uc.emu_stop().unwrap();
+            })
+            .unwrap();
+
+        self.breakpoints.insert(address, hook);
+    }
+
+    fn remove_breakpoint(&mut self, address: u32) {
+        if let Some(hook) = self.breakpoints.remove(&address) {
+            self.uc.remove_hook(hook).unwrap();
+        }
+    }
+
+    fn add_watchpoint(&mut self, range: Range<u32>, kind: WatchpointKind) {
+        let hook_type = match kind {
+            WatchpointKind::Read => HookType::MEM_READ,
+            WatchpointKind::Write => HookType::MEM_WRITE,
+            WatchpointKind::Access => HookType::MEM_READ | HookType::MEM_WRITE,
+        };
+
+        let state = self.state.clone();
+        let hook = self
+            .uc
+            .add_mem_hook(
+                hook_type,
+                range.start as u64,
+                range.end as u64 - 1,
+                move |uc, mem_type, address, size, _| {
+                    let mut state = state.borrow_mut();
+                    if state.stop_reason.is_none() {
+                        state.stop_reason = Some(StopReason::Watchpoint {
+                            address: address as u32,
+                            size: size as u32,
+                            write: mem_type == MemType::WRITE,
+                        });
+                    }
+
+                    // unicorn stops after the current instruction
+                    uc.emu_stop().unwrap();
+
+                    true
+                },
+            )
+            .unwrap();
+
+        self.watchpoints.push((range, kind, hook));
+    }
+
+    fn remove_watchpoint(&mut self, range: Range<u32>, kind: WatchpointKind) {
+        let (removed, kept) = self.watchpoints.drain(..).partition::<Vec<_>, _>(|x| x.0 == range && x.1 == kind);
+        self.watchpoints = kept;
+
+        for (_, _, hook) in removed {
+            self.uc.remove_hook(hook).unwrap();
+        }
+    }
+
+    fn set_trace_hook(&mut self, hook: Option<TraceHook>) {
+        if let Some(x) = self.trace_hook.take() {
+            self.uc.remove_hook(x).unwrap();
+        }
+
+        let install = hook.is_some();
+        self.state.borrow_mut().trace_hook = hook;
+
+        if install {
+            let state = self.state.clone();
+            let hook = self
+                .uc
+                .add_code_hook(1, 0, move |uc, address, size| {
+                    let cpsr = uc.reg_read(RegisterARM::CPSR).unwrap();
+                    let thumb = cpsr & (1 << 5) != 0;
+
+                    let size = if thumb { 2 } else { 4 }.min(size as usize);
+                    let raw = uc
+                        .mem_read_as_vec(address, size)
+                        .map(|x| x.iter().rev().fold(0, |acc, &x| (acc << 8) | x as u32))
+                        .unwrap_or(0);
+
+                    if let Some(hook) = &mut state.borrow_mut().trace_hook {
+                        hook(&TracedInstruction {
+                            address: address as u32,
+                            thumb,
+                            raw,
+                        });
+                    }
+                })
+                .unwrap();
+
+            self.trace_hook = Some(hook);
+        }
+    }
 }
 
 impl ArmRegister {
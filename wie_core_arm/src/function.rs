@@ -91,6 +91,11 @@ generate_fn_helper!(P0, P1, P2, P3);
 #[async_trait::async_trait(?Send)]
 pub trait EmulatedFunction<P, E, R> {
     async fn call(&self, core: &mut ArmCore, system: &mut SystemHandle) -> Result<R, E>;
+
+    /// Name shown in backtraces, the type name by default
+    fn name(&self) -> &'static str {
+        core::any::type_name::<Self>()
+    }
 }
 
 macro_rules! generate_emulated_function {
@@ -1,4 +1,4 @@
-use alloc::boxed::Box;
+use alloc::{boxed::Box, string::String};
 use core::{
     future::Future,
     marker::PhantomData,
@@ -8,7 +8,7 @@ use core::{
 
 use wie_backend::AsyncCallable;
 
-use crate::{context::ArmCoreContext, Allocator, ArmCore};
+use crate::{context::ArmCoreContext, ArmCore};
 
 pub struct SpawnFuture<C, R, E> {
     core: ArmCore,
@@ -24,18 +24,25 @@ where
     R: 'static,
     E: core::fmt::Debug + 'static,
 {
-    pub fn new(mut core: ArmCore, callable: C) -> Self {
-        let stack_base = Allocator::alloc(&mut core, 0x1000).unwrap();
-        let context = ArmCoreContext::new(stack_base);
+    pub fn new(mut core: ArmCore, name: Option<&str>, stack_size: u32, callable: C) -> anyhow::Result<Self> {
+        let (stack_base, stack_size) = core.alloc_stack(stack_size, name.unwrap_or("<unnamed>"))?;
+        let context = ArmCoreContext::new(stack_base, stack_size);
         let callable_fut = Box::pin(callable.call());
 
-        Self {
+        Ok(Self {
             core,
             context,
             stack_base,
             callable_fut,
             _phantom: PhantomData,
-        }
+        })
+    }
+
+    /// Allocates the stack when first polled, so running out of stack space fails the task instead of panicking
+    pub async fn run(core: ArmCore, name: Option<String>, stack_size: u32, callable: C) -> anyhow::Result<R> {
+        Self::new(core, name.as_deref(), stack_size, callable)?
+            .await
+            .map_err(|x| anyhow::anyhow!("{:?}", x))
     }
 }
 
@@ -43,19 +50,47 @@ impl<C, R, E> Future for SpawnFuture<C, R, E> {
     type Output = Result<R, E>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let stack_base = self.stack_base;
+        self.core.enter_task(stack_base);
         self.core.clone().restore_context(&self.context); // XXX clone is added to satisfy borrow checker
         let result = self.callable_fut.as_mut().poll(cx);
         self.context = self.core.save_context();
 
-        if let Poll::Ready(x) = result {
-            let stack_base = self.stack_base;
-            Allocator::free(&mut self.core, stack_base).unwrap();
+        result
+    }
+}
 
-            Poll::Ready(x)
-        } else {
-            Poll::Pending
-        }
+// stack is freed on drop, so aborted tasks don't leak it
+impl<C, R, E> Drop for SpawnFuture<C, R, E> {
+    fn drop(&mut self) {
+        let stack_base = self.stack_base;
+        self.core.free_stack(stack_base);
     }
 }
 
 impl<C, R, E> Unpin for SpawnFuture<C, R, E> {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, string::ToString};
+
+    use test_utils::TestPlatform;
+    use wie_backend::{encoding::EUC_KR, System};
+
+    use crate::ArmCore;
+
+    #[test]
+    fn test_stack_alloc_failure() -> anyhow::Result<()> {
+        let mut system = System::new(Box::new(TestPlatform::new()), Box::new(()), EUC_KR);
+        let mut core = ArmCore::new(system.handle())?;
+
+        // fails the task, instead of panicking on spawn
+        let handle = core.spawn_with_stack_size("huge", 0x40000000, || async { anyhow::Ok(()) });
+
+        let error = system.tick().unwrap_err();
+        assert!(error.to_string().contains("Out of stack space"));
+        assert!(handle.is_finished());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,524 @@
+use alloc::{boxed::Box, collections::BTreeSet, format, string::String, vec::Vec};
+use core::ops::Range;
+
+use wie_backend::DebugConnection;
+
+use crate::engine::{ArmEngine, ArmEngineResult, ArmRegister, StopReason, WatchpointKind};
+
+// register numbers follow gdb's org.gnu.gdb.arm.core feature, cpsr is 25 for compatibility with the legacy fpa layout
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <architecture>arm</architecture>
+  <feature name="org.gnu.gdb.arm.core">
+    <reg name="r0" bitsize="32" type="uint32"/>
+    <reg name="r1" bitsize="32" type="uint32"/>
+    <reg name="r2" bitsize="32" type="uint32"/>
+    <reg name="r3" bitsize="32" type="uint32"/>
+    <reg name="r4" bitsize="32" type="uint32"/>
+    <reg name="r5" bitsize="32" type="uint32"/>
+    <reg name="r6" bitsize="32" type="uint32"/>
+    <reg name="r7" bitsize="32" type="uint32"/>
+    <reg name="r8" bitsize="32" type="uint32"/>
+    <reg name="r9" bitsize="32" type="uint32"/>
+    <reg name="r10" bitsize="32" type="uint32"/>
+    <reg name="r11" bitsize="32" type="uint32"/>
+    <reg name="r12" bitsize="32" type="uint32"/>
+    <reg name="sp" bitsize="32" type="data_ptr"/>
+    <reg name="lr" bitsize="32"/>
+    <reg name="pc" bitsize="32" type="code_ptr"/>
+    <reg name="cpsr" bitsize="32" regnum="25"/>
+  </feature>
+</target>
+"#;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GdbState {
+    Stopped,
+    Running,
+    Stepping,
+}
+
+// gdb remote serial protocol server, in all-stop mode with a single thread, which is the task that stopped.
+// execution control is done by `ArmCore::run_some`, breakpoints and watchpoints are installed on the engine.
+pub struct GdbStub {
+    connection: Box<dyn DebugConnection>,
+    input: Vec<u8>,
+    state: GdbState,
+    // kept to remove them from the engine on detach
+    breakpoints: BTreeSet<u32>,
+    watchpoints: Vec<(Range<u32>, WatchpointKind)>,
+}
+
+impl GdbStub {
+    // debugger expects the target to be stopped when it attaches
+    pub fn new(connection: Box<dyn DebugConnection>) -> Self {
+        Self {
+            connection,
+            input: Vec::new(),
+            state: GdbState::Stopped,
+            breakpoints: BTreeSet::new(),
+            watchpoints: Vec::new(),
+        }
+    }
+
+    pub fn state(&self) -> GdbState {
+        self.state
+    }
+
+    /// Handles requests arrived so far, returns false if the debugger has gone
+    pub fn poll(&mut self, engine: &mut dyn ArmEngine) -> bool {
+        match self.poll_inner(engine) {
+            Ok(()) => true,
+            Err(x) => {
+                tracing::warn!("Debugger detached: {}", x);
+
+                false
+            }
+        }
+    }
+
+    pub fn stop(&mut self, reason: StopReason) -> ArmEngineResult<()> {
+        self.state = GdbState::Stopped;
+
+        let reply = if let StopReason::Watchpoint { address, write, .. } = reason {
+            let kind = self
+                .watchpoints
+                .iter()
+                .find(|(range, kind)| range.contains(&address) && kind.matches(write))
+                .map(|x| x.1);
+
+            let name = match kind {
+                Some(WatchpointKind::Read) => "rwatch",
+                Some(WatchpointKind::Access) => "awatch",
+                _ => "watch",
+            };
+
+            format!("T{:02x}{}:{:x};", SIGTRAP, name, address)
+        } else {
+            format!("S{:02x}", SIGTRAP)
+        };
+
+        self.send(&reply)
+    }
+
+    fn poll_inner(&mut self, engine: &mut dyn ArmEngine) -> ArmEngineResult<()> {
+        let mut buf = [0; 1024];
+        loop {
+            let read = self.connection.read(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+
+            self.input.extend_from_slice(&buf[..read]);
+        }
+
+        while let Some(packet) = self.next_packet()? {
+            let response = self.handle_packet(engine, &packet)?;
+            if let Some(response) = response {
+                self.send(&response)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn next_packet(&mut self) -> ArmEngineResult<Option<String>> {
+        loop {
+            match self.input.first() {
+                None => return Ok(None),
+                Some(b'$') => break,
+                Some(0x03) => {
+                    self.input.remove(0);
+
+                    if self.state != GdbState::Stopped {
+                        self.state = GdbState::Stopped;
+                        self.send(&format!("S{:02x}", SIGINT))?;
+                    }
+                }
+                // acks and garbage between packets
+                Some(_) => {
+                    self.input.remove(0);
+                }
+            }
+        }
+
+        let end = if let Some(x) = self.input.iter().position(|&x| x == b'#') {
+            x
+        } else {
+            return Ok(None);
+        };
+        if self.input.len() < end + 3 {
+            return Ok(None);
+        }
+
+        let packet = self.input.drain(..end + 3).collect::<Vec<_>>();
+        let data = &packet[1..end];
+
+        let checksum = core::str::from_utf8(&packet[end + 1..]).ok().and_then(|x| u8::from_str_radix(x, 16).ok());
+        if checksum != Some(data.iter().fold(0u8, |x, y| x.wrapping_add(*y))) {
+            self.connection.write(b"-")?;
+
+            return Ok(None);
+        }
+        self.connection.write(b"+")?;
+
+        Ok(Some(String::from_utf8_lossy(data).into_owned()))
+    }
+
+    fn send(&mut self, data: &str) -> ArmEngineResult<()> {
+        let checksum = data.bytes().fold(0u8, |x, y| x.wrapping_add(y));
+
+        self.connection.write(format!("${}#{:02x}", data, checksum).as_bytes())
+    }
+
+    // returns none if execution resumes, stop reply is sent when it stops again
+    fn handle_packet(&mut self, engine: &mut dyn ArmEngine, packet: &str) -> ArmEngineResult<Option<String>> {
+        tracing::trace!("gdb: {}", packet);
+
+        if packet.is_empty() || !packet.is_char_boundary(1) {
+            return Ok(Some(String::new()));
+        }
+        let (command, args) = packet.split_at(1);
+
+        let response = match command {
+            "?" => format!("S{:02x}", SIGTRAP),
+            "g" => (0..16).chain([25]).map(|x| encode_u32(engine.reg_read(register(x).unwrap()))).collect(),
+            "G" => {
+                let values = decode_hex(args)?;
+                for (i, value) in values.chunks_exact(4).take(17).enumerate() {
+                    let register_number = if i == 16 { 25 } else { i };
+
+                    engine.reg_write(register(register_number).unwrap(), u32::from_le_bytes(value.try_into().unwrap()));
+                }
+
+                "OK".into()
+            }
+            "p" => match register(usize::from_str_radix(args, 16)?) {
+                Some(x) => encode_u32(engine.reg_read(x)),
+                None => "E01".into(),
+            },
+            "P" => {
+                let (number, value) = args.split_once('=').ok_or_else(|| anyhow::anyhow!("Invalid packet {}", packet))?;
+                let value = u32::from_le_bytes(decode_hex(value)?.try_into().map_err(|_| anyhow::anyhow!("Invalid packet {}", packet))?);
+
+                match register(usize::from_str_radix(number, 16)?) {
+                    Some(x) => {
+                        engine.reg_write(x, value);
+
+                        "OK".into()
+                    }
+                    None => "E01".into(),
+                }
+            }
+            "m" => {
+                let (address, length) = parse_address_length(args)?;
+
+                match engine.mem_read(address, length as _) {
+                    Ok(x) => encode_hex(&x),
+                    Err(_) => "E01".into(),
+                }
+            }
+            "M" => {
+                let (range, data) = args.split_once(':').ok_or_else(|| anyhow::anyhow!("Invalid packet {}", packet))?;
+                let (address, _) = parse_address_length(range)?;
+
+                match engine.mem_write(address, &decode_hex(data)?) {
+                    Ok(()) => "OK".into(),
+                    Err(_) => "E01".into(),
+                }
+            }
+            "c" | "s" => {
+                if !args.is_empty() {
+                    engine.reg_write(ArmRegister::PC, u32::from_str_radix(args, 16)?);
+                }
+
+                self.state = if command == "c" { GdbState::Running } else { GdbState::Stepping };
+
+                return Ok(None);
+            }
+            "Z" | "z" => self.handle_breakpoint(engine, command == "Z", args)?,
+            "D" => {
+                self.detach(engine);
+
+                "OK".into()
+            }
+            "k" => {
+                self.detach(engine);
+
+                return Ok(None);
+            }
+            "H" | "T" => "OK".into(),
+            "q" => self.handle_query(args),
+            _ => String::new(),
+        };
+
+        Ok(Some(response))
+    }
+
+    fn handle_breakpoint(&mut self, engine: &mut dyn ArmEngine, insert: bool, args: &str) -> ArmEngineResult<String> {
+        let mut parts = args.split(',');
+        let kind = parts.next().unwrap_or_default();
+        let address = u32::from_str_radix(parts.next().unwrap_or_default(), 16)?;
+        let size = u32::from_str_radix(parts.next().unwrap_or_default(), 16)?;
+
+        let watchpoint_kind = match kind {
+            // software and hardware breakpoints are the same for us, as we don't patch memory
+            "0" | "1" => {
+                if insert {
+                    engine.add_breakpoint(address);
+                    self.breakpoints.insert(address);
+                } else {
+                    engine.remove_breakpoint(address);
+                    self.breakpoints.remove(&address);
+                }
+
+                return Ok("OK".into());
+            }
+            "2" => WatchpointKind::Write,
+            "3" => WatchpointKind::Read,
+            "4" => WatchpointKind::Access,
+            _ => return Ok(String::new()),
+        };
+
+        let range = address..address.saturating_add(size.max(1));
+        if insert {
+            engine.add_watchpoint(range.clone(), watchpoint_kind);
+            self.watchpoints.push((range, watchpoint_kind));
+        } else {
+            engine.remove_watchpoint(range.clone(), watchpoint_kind);
+            self.watchpoints.retain(|x| x.0 != range || x.1 != watchpoint_kind);
+        }
+
+        Ok("OK".into())
+    }
+
+    fn detach(&mut self, engine: &mut dyn ArmEngine) {
+        for address in core::mem::take(&mut self.breakpoints) {
+            engine.remove_breakpoint(address);
+        }
+        for (range, kind) in core::mem::take(&mut self.watchpoints) {
+            engine.remove_watchpoint(range, kind);
+        }
+
+        self.state = GdbState::Running;
+    }
+
+    fn handle_query(&self, args: &str) -> String {
+        if args.starts_with("Supported") {
+            return "PacketSize=4000;qXfer:features:read+".into();
+        }
+
+        if let Some(x) = args.strip_prefix("Xfer:features:read:target.xml:") {
+            let (offset, length) = match parse_address_length(x) {
+                Ok(x) => x,
+                Err(_) => return "E01".into(),
+            };
+            let offset = (offset as usize).min(TARGET_XML.len());
+            let end = (offset + length as usize).min(TARGET_XML.len());
+
+            let prefix = if end == TARGET_XML.len() { "l" } else { "m" };
+
+            return format!("{}{}", prefix, &TARGET_XML[offset..end]);
+        }
+
+        match args {
+            "Attached" => "1".into(),
+            "C" => "QC1".into(),
+            "fThreadInfo" => "m1".into(),
+            "sThreadInfo" => "l".into(),
+            _ => String::new(),
+        }
+    }
+}
+
+fn register(number: usize) -> Option<ArmRegister> {
+    Some(match number {
+        0 => ArmRegister::R0,
+        1 => ArmRegister::R1,
+        2 => ArmRegister::R2,
+        3 => ArmRegister::R3,
+        4 => ArmRegister::R4,
+        5 => ArmRegister::R5,
+        6 => ArmRegister::R6,
+        7 => ArmRegister::R7,
+        8 => ArmRegister::R8,
+        9 => ArmRegister::SB,
+        10 => ArmRegister::SL,
+        11 => ArmRegister::FP,
+        12 => ArmRegister::IP,
+        13 => ArmRegister::SP,
+        14 => ArmRegister::LR,
+        15 => ArmRegister::PC,
+        25 => ArmRegister::Cpsr,
+        _ => return None,
+    })
+}
+
+// `addr,length` in hex
+fn parse_address_length(args: &str) -> ArmEngineResult<(u32, u32)> {
+    let (address, length) = args.split_once(',').ok_or_else(|| anyhow::anyhow!("Invalid range {}", args))?;
+
+    Ok((u32::from_str_radix(address, 16)?, u32::from_str_radix(length, 16)?))
+}
+
+fn encode_u32(value: u32) -> String {
+    encode_hex(&value.to_le_bytes())
+}
+
+fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+fn decode_hex(data: &str) -> ArmEngineResult<Vec<u8>> {
+    data.as_bytes()
+        .chunks_exact(2)
+        .map(|x| Ok(u8::from_str_radix(core::str::from_utf8(x)?, 16)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, format, rc::Rc, string::String, vec::Vec};
+    use core::cell::RefCell;
+
+    use wie_backend::DebugConnection;
+
+    use crate::engine::{ArmEngine, ArmRegister, Armv4tEmuEngine, MemoryPermission, StopReason};
+
+    use super::{GdbState, GdbStub};
+
+    #[derive(Clone, Default)]
+    struct TestConnection {
+        input: Rc<RefCell<Vec<u8>>>,
+        output: Rc<RefCell<Vec<u8>>>,
+    }
+
+    impl DebugConnection for TestConnection {
+        fn read(&mut self, buf: &mut [u8]) -> anyhow::Result<usize> {
+            let mut input = self.input.borrow_mut();
+            let length = input.len().min(buf.len());
+            buf[..length].copy_from_slice(&input[..length]);
+            input.drain(..length);
+
+            Ok(length)
+        }
+
+        fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+            self.output.borrow_mut().extend_from_slice(data);
+
+            Ok(())
+        }
+    }
+
+    fn packet(data: &str) -> String {
+        format!("${}#{:02x}", data, data.bytes().fold(0u8, |x, y| x.wrapping_add(y)))
+    }
+
+    fn exchange(stub: &mut GdbStub, engine: &mut dyn ArmEngine, connection: &TestConnection, input: &[u8]) -> String {
+        connection.input.borrow_mut().extend_from_slice(input);
+        assert!(stub.poll(engine));
+
+        take_output(connection)
+    }
+
+    fn take_output(connection: &TestConnection) -> String {
+        String::from_utf8(core::mem::take(&mut *connection.output.borrow_mut())).unwrap()
+    }
+
+    fn test_engine() -> anyhow::Result<Armv4tEmuEngine> {
+        // mov r0, #1; mov r1, #0x1100; str r0, [r1]; mov r0, #2
+        let code = [0xe3a00001u32, 0xe3a01c11, 0xe5810000, 0xe3a00002];
+
+        let mut engine = Armv4tEmuEngine::new();
+        engine.mem_map(0x1000, 0x1000, MemoryPermission::ReadWriteExecute);
+        engine.mem_write(0x1000, &code.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>())?;
+        engine.reg_write(ArmRegister::Cpsr, 0x10);
+        engine.reg_write(ArmRegister::PC, 0x1000);
+
+        Ok(engine)
+    }
+
+    #[test]
+    fn test_packets() -> anyhow::Result<()> {
+        let connection = TestConnection::default();
+        let mut stub = GdbStub::new(Box::new(connection.clone()));
+        let mut engine = test_engine()?;
+        engine.reg_write(ArmRegister::R0, 0x12345678);
+
+        // acks and garbage before a packet are skipped
+        assert_eq!(
+            exchange(&mut stub, &mut engine, &connection, format!("+x{}", packet("?")).as_bytes()),
+            format!("+{}", packet("S05"))
+        );
+
+        // packets split across reads are handled once complete
+        let request = packet("p0");
+        let (first, second) = request.split_at(3);
+        assert_eq!(exchange(&mut stub, &mut engine, &connection, first.as_bytes()), "");
+        assert_eq!(
+            exchange(&mut stub, &mut engine, &connection, second.as_bytes()),
+            format!("+{}", packet("78563412"))
+        );
+
+        // bad checksum is nacked and not handled
+        assert_eq!(exchange(&mut stub, &mut engine, &connection, b"$P1=efbeadde#00"), "-");
+        assert_eq!(engine.reg_read(ArmRegister::R1), 0);
+
+        assert_eq!(
+            exchange(&mut stub, &mut engine, &connection, packet("P1=efbeadde").as_bytes()),
+            format!("+{}", packet("OK"))
+        );
+        assert_eq!(engine.reg_read(ArmRegister::R1), 0xdeadbeef);
+
+        // interrupt while running
+        assert_eq!(exchange(&mut stub, &mut engine, &connection, packet("c").as_bytes()), "+");
+        assert_eq!(stub.state(), GdbState::Running);
+        assert_eq!(exchange(&mut stub, &mut engine, &connection, &[0x03]), packet("S02"));
+        assert_eq!(stub.state(), GdbState::Stopped);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_breakpoint_packets() -> anyhow::Result<()> {
+        let connection = TestConnection::default();
+        let mut stub = GdbStub::new(Box::new(connection.clone()));
+        let mut engine = test_engine()?;
+        let ok = format!("+{}", packet("OK"));
+
+        assert_eq!(exchange(&mut stub, &mut engine, &connection, packet("Z0,100c,4").as_bytes()), ok);
+        assert_eq!(exchange(&mut stub, &mut engine, &connection, packet("Z2,1100,4").as_bytes()), ok);
+        assert_eq!(exchange(&mut stub, &mut engine, &connection, packet("c").as_bytes()), "+");
+
+        let reason = engine.run(0x1010, 0..0, 100)?;
+        assert_eq!(
+            reason,
+            StopReason::Watchpoint {
+                address: 0x1100,
+                size: 4,
+                write: true
+            }
+        );
+        stub.stop(reason)?;
+        assert_eq!(take_output(&connection), packet("T05watch:1100;"));
+
+        assert_eq!(exchange(&mut stub, &mut engine, &connection, packet("z2,1100,4").as_bytes()), ok);
+        assert_eq!(exchange(&mut stub, &mut engine, &connection, packet("c").as_bytes()), "+");
+
+        let reason = engine.run(0x1010, 0..0, 100)?;
+        assert_eq!(reason, StopReason::Breakpoint(0x100c));
+        stub.stop(reason)?;
+        assert_eq!(take_output(&connection), packet("S05"));
+
+        // detaching removes the breakpoint
+        assert_eq!(exchange(&mut stub, &mut engine, &connection, packet("D").as_bytes()), ok);
+        assert_eq!(engine.run(0x1010, 0..0, 100)?, StopReason::Done);
+        assert_eq!(engine.reg_read(ArmRegister::R0), 2);
+
+        Ok(())
+    }
+}
@@ -4,13 +4,21 @@ extern crate alloc;
 mod allocator;
 mod context;
 mod core;
+mod disasm;
 mod engine;
 mod function;
 mod future;
+mod gdb;
+mod stack;
+mod symbols;
 
 pub use self::{
-    allocator::Allocator,
+    allocator::{Allocator, AllocatorStats},
     core::{ArmCore, PEB_BASE},
-    engine::ArmEngineError,
+    disasm::{disassemble, disassemble_arm, disassemble_thumb, Instruction},
+    engine::{
+        ArmEngine, ArmEngineError, ArmEngineResult, ArmRegister, MemoryAccess, MemoryFault, MemoryPermission, StopReason, TraceHook,
+        TracedInstruction, WatchpointKind,
+    },
     function::{EmulatedFunction, EmulatedFunctionParam},
 };
@@ -0,0 +1,99 @@
+use alloc::{collections::BTreeMap, string::String};
+
+use anyhow::Context;
+
+use wie_common::util::round_up;
+
+const STACK_BASE: u32 = 0x50000000;
+const STACK_END: u32 = 0x70000000;
+// left unmapped below each stack, so overflowing it faults instead of corrupting memory
+const STACK_GUARD_SIZE: u32 = 0x1000;
+
+struct Stack {
+    size: u32,
+    // None if free for reuse
+    task: Option<String>,
+}
+
+pub struct Stacks {
+    next: u32,
+    // keyed by the lowest usable address
+    stacks: BTreeMap<u32, Stack>,
+}
+
+impl Stacks {
+    pub fn new() -> Self {
+        Self {
+            next: STACK_BASE,
+            stacks: BTreeMap::new(),
+        }
+    }
+
+    /// Returns base and size of a stack with at least `size` bytes, and whether it has to be mapped
+    pub fn alloc(&mut self, size: u32, task: &str) -> anyhow::Result<(u32, u32, bool)> {
+        let size = round_up(size as usize, STACK_GUARD_SIZE as usize) as u32;
+
+        // stacks stay mapped after their task ends, so reuse them
+        if let Some((&base, stack)) = self.stacks.iter_mut().find(|(_, x)| x.task.is_none() && x.size >= size) {
+            stack.task = Some(task.into());
+
+            return Ok((base, stack.size, false));
+        }
+
+        let base = self.next + STACK_GUARD_SIZE;
+        anyhow::ensure!(base.checked_add(size).context("Stack too large")? <= STACK_END, "Out of stack space");
+
+        self.next = base + size;
+        self.stacks.insert(
+            base,
+            Stack {
+                size,
+                task: Some(task.into()),
+            },
+        );
+
+        Ok((base, size, true))
+    }
+
+    pub fn free(&mut self, base: u32) {
+        if let Some(x) = self.stacks.get_mut(&base) {
+            x.task = None;
+        }
+    }
+
+    /// Task owning the stack whose guard page contains `address`
+    pub fn overflowed_task(&self, address: u32) -> Option<&str> {
+        let (base, stack) = self.stacks.range(address.checked_add(1)?..).next()?;
+
+        if base - STACK_GUARD_SIZE <= address {
+            stack.task.as_deref()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stacks;
+
+    #[test]
+    fn test_stacks() -> anyhow::Result<()> {
+        let mut stacks = Stacks::new();
+
+        let (first, size, map) = stacks.alloc(0x1800, "first")?;
+        assert_eq!((first, size, map), (0x50001000, 0x2000, true));
+        let (second, _, _) = stacks.alloc(0x1000, "second")?;
+        assert_eq!(second, 0x50004000);
+
+        assert_eq!(stacks.overflowed_task(first - 4), Some("first"));
+        assert_eq!(stacks.overflowed_task(second - 0x1000), Some("second"));
+        assert_eq!(stacks.overflowed_task(first + 0x1ffc), None);
+
+        stacks.free(first);
+        assert_eq!(stacks.overflowed_task(first - 4), None);
+        assert_eq!(stacks.alloc(0x1000, "third")?, (first, 0x2000, false));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use alloc::{borrow::ToOwned, collections::BTreeMap, format, string::String, vec::Vec};
+
+struct Symbol {
+    name: String,
+    // 0 if unknown, then the symbol extends to the next one
+    size: u32,
+}
+
+pub struct Symbols {
+    symbols: BTreeMap<u32, Symbol>,
+}
+
+impl Symbols {
+    pub fn new() -> Self {
+        Self { symbols: BTreeMap::new() }
+    }
+
+    // renaming a symbol with a `size` of 0 keeps its size
+    pub fn insert(&mut self, address: u32, size: u32, name: &str) {
+        // thumb bit
+        let address = address & !1;
+
+        let size = match self.symbols.get(&address) {
+            Some(x) if size == 0 => x.size,
+            _ => size,
+        };
+
+        self.symbols.insert(address, Symbol { name: name.to_owned(), size });
+    }
+
+    /// Formats `address` as `name+offset`
+    pub fn symbolize(&self, address: u32) -> Option<String> {
+        let (start, symbol) = self.symbols.range(..=address).next_back()?;
+        let offset = address - start;
+
+        if symbol.size != 0 && offset >= symbol.size {
+            return None;
+        }
+
+        if offset == 0 {
+            Some(symbol.name.clone())
+        } else {
+            Some(format!("{}+{:#x}", symbol.name, offset))
+        }
+    }
+}
+
+/// Shortens a rust type name to its last two path segments, e.g. `graphics::flush`
+pub fn function_name(type_name: &str) -> String {
+    let path = type_name.split('<').next().unwrap();
+    let segments = path.split("::").filter(|x| !x.starts_with('{')).collect::<Vec<_>>();
+
+    segments[segments.len().saturating_sub(2)..].join("::")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{function_name, Symbols};
+
+    #[test]
+    fn test_symbols() {
+        let mut symbols = Symbols::new();
+        symbols.insert(0x1001, 0, "org/foo/Game.run");
+        symbols.insert(0x2000, 4, "MC_grpFlush");
+
+        assert_eq!(symbols.symbolize(0x1000).as_deref(), Some("org/foo/Game.run"));
+        assert_eq!(symbols.symbolize(0x1024).as_deref(), Some("org/foo/Game.run+0x24"));
+        assert_eq!(symbols.symbolize(0x2002).as_deref(), Some("MC_grpFlush+0x2"));
+        assert_eq!(symbols.symbolize(0x2004), None);
+        assert_eq!(symbols.symbolize(0xfff), None);
+
+        symbols.insert(0x2001, 0, "org/foo/Game.paint");
+        assert_eq!(symbols.symbolize(0x2002).as_deref(), Some("org/foo/Game.paint+0x2"));
+        assert_eq!(symbols.symbolize(0x2004), None);
+    }
+
+    #[test]
+    fn test_function_name() {
+        assert_eq!(function_name("wie_wipi_c::api::graphics::flush"), "graphics::flush");
+        assert_eq!(function_name("wie_ktf::runtime::init::java_new"), "init::java_new");
+        assert_eq!(function_name("wie_ktf::app::KtfApp::start::{{closure}}"), "KtfApp::start");
+        assert_eq!(function_name("wie_ktf::method::Proxy<alloc::boxed::Box<u8>>"), "method::Proxy");
+    }
+}
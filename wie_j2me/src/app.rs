@@ -3,9 +3,12 @@ use alloc::{
     vec::Vec,
 };
 
-use wie_backend::{App, System, SystemHandle};
+use jvm::ClassInstanceRef;
+
+use wie_backend::{App, Instant, System, SystemHandle};
 use wie_common::Event;
 use wie_core_jvm::JvmCore;
+use wie_wipi_java::classes::javax::microedition::{lcdui::Display, midlet::MIDlet};
 
 pub struct J2MEApp {
     system: System,
@@ -28,9 +31,22 @@ impl J2MEApp {
         core.add_jar(&jar).await?;
 
         let normalized_class_name = main_class_name.replace('.', "/");
-        core.jvm().invoke_static(&normalized_class_name, "startApp", "()V", []).await?;
+        let midlet: ClassInstanceRef<MIDlet> = core.jvm().new_class(&normalized_class_name, "()V", []).await?.into();
+        core.jvm().invoke_virtual(&midlet, "startApp", "()V", []).await?;
 
-        Ok(())
+        loop {
+            match system.next_event().await {
+                Event::Pause => MIDlet::pause(core.jvm(), &midlet).await?,
+                Event::Resume => MIDlet::resume(core.jvm(), &midlet).await?,
+                Event::Exit => {
+                    core.jvm().invoke_virtual(&midlet, "destroyApp", "(Z)V", (true,)).await?;
+                    system.exit();
+
+                    return Ok(());
+                }
+                x => Display::handle_event(core.jvm(), x).await?,
+            }
+        }
     }
 }
 
@@ -49,14 +65,22 @@ impl App for J2MEApp {
     }
 
     fn crash_dump(&self) -> String {
-        "".into() // TODO
+        self.system.dump_tasks()
     }
 
     fn on_event(&mut self, event: Event) {
-        self.system.handle().event_queue().push(event)
+        self.system.handle().push_event(event)
     }
 
     fn tick(&mut self) -> anyhow::Result<()> {
         self.system.tick()
     }
+
+    fn next_wakeup(&self) -> Option<Instant> {
+        self.system.next_wakeup()
+    }
+
+    fn exit_requested(&self) -> bool {
+        self.system.exit_requested()
+    }
 }
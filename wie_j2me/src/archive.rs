@@ -1,70 +1,248 @@
 use alloc::{
     boxed::Box,
-    str,
+    collections::BTreeMap,
+    format,
     string::{String, ToString},
     vec::Vec,
 };
 
-use wie_backend::{App, Archive, Platform, System};
+use anyhow::Context;
+
+use wie_backend::{
+    encoding::{Encoding, EUC_KR, UTF_8},
+    read_zip_file, App, Archive, ArchiveFiles, ArchiveLoader, ArchiveMetadata, ArchivePlatform, Platform, System,
+};
 
 use crate::app::J2MEApp;
 
 pub struct J2MEArchive {
     jar: Vec<u8>,
-    descriptor: J2MEDescriptor,
+    metadata: ArchiveMetadata,
 }
 
 impl J2MEArchive {
-    pub fn from_jad_jar(jad: Vec<u8>, jar: Vec<u8>) -> Self {
-        let descriptor = J2MEDescriptor::parse(&jad);
+    pub fn from_jad_jar(jad: Vec<u8>, jar: Vec<u8>) -> anyhow::Result<Self> {
+        let mut properties = Self::read_manifest(&jar)?.unwrap_or_default();
+
+        // jad attributes override manifest ones, except for these which should be identical in both
+        for (key, value) in parse_properties(&jad) {
+            if matches!(key.as_str(), "MIDlet-Name" | "MIDlet-Vendor" | "MIDlet-Version") {
+                if let Some(x) = properties.get(&key).filter(|x| **x != value) {
+                    tracing::warn!("{} mismatch between jad ({}) and manifest ({})", key, value, x);
+                }
+            }
+
+            properties.insert(key, value);
+        }
+
+        Self::new(jar, properties)
+    }
+
+    pub fn from_jar(jar: Vec<u8>) -> anyhow::Result<Self> {
+        let properties = Self::read_manifest(&jar)?.context("No manifest in jar")?;
+
+        Self::new(jar, properties)
+    }
+
+    /// Path of the jar relative to the jad, from `MIDlet-Jar-URL`
+    pub fn jar_path(jad: &[u8]) -> Option<String> {
+        let properties = parse_properties(jad);
+        let url = properties.get("MIDlet-Jar-URL")?;
+
+        // we can't download, so absolute urls are expected to be next to the jad
+        let path = if url.contains("://") {
+            url.rsplit('/').next()?
+        } else {
+            url.trim_start_matches("./")
+        };
+
+        let path = path.split(['?', '#']).next()?;
+        if path.is_empty() {
+            return None;
+        }
 
-        Self { jar, descriptor }
+        Some(path.to_string())
+    }
+
+    fn read_manifest(jar: &[u8]) -> anyhow::Result<Option<BTreeMap<String, String>>> {
+        Ok(read_zip_file(jar, "META-INF/MANIFEST.MF")?.map(|x| parse_properties(&x)))
+    }
+
+    fn new(jar: Vec<u8>, properties: BTreeMap<String, String>) -> anyhow::Result<Self> {
+        let mut metadata = create_metadata(properties);
+
+        // MIDlet-1: <name>, <icon>, <class>
+        let icon = metadata
+            .property(&["MIDlet-Icon"])
+            .or_else(|| metadata.property(&["MIDlet-1"]).and_then(|x| x.split(',').nth(1)))
+            .map(|x| x.trim().trim_start_matches('/'))
+            .filter(|x| !x.is_empty());
+        if let Some(x) = icon {
+            metadata.icon = read_zip_file(&jar, x)?;
+        }
+
+        Ok(Self { jar, metadata })
+    }
+}
+
+pub struct J2MEArchiveLoader;
+
+impl ArchiveLoader for J2MEArchiveLoader {
+    fn name(&self) -> &'static str {
+        "J2ME"
+    }
+
+    fn description(&self) -> &'static str {
+        "<name>.jad with the jar it points to, or a single MIDlet jar"
+    }
+
+    fn probe(&self, files: &ArchiveFiles) -> bool {
+        let is_jad = find_jad_jar(files).is_some();
+        let is_jar = files.len() == 1 && files.keys().all(|x| x.to_ascii_lowercase().ends_with(".jar"));
+
+        is_jad || is_jar
+    }
+
+    fn load(&self, mut files: ArchiveFiles) -> anyhow::Result<Box<dyn Archive>> {
+        let archive = if let Some((jad_name, jar_name)) = find_jad_jar(&files) {
+            let jad = files.remove(&jad_name).unwrap();
+            let jar = files.remove(&jar_name).unwrap();
+
+            J2MEArchive::from_jad_jar(jad, jar)?
+        } else {
+            let jar = files.into_values().next().context("Invalid format")?;
+
+            J2MEArchive::from_jar(jar)?
+        };
+
+        Ok(Box::new(archive))
     }
 }
 
 impl Archive for J2MEArchive {
-    fn id(&self) -> String {
-        self.descriptor.name.clone()
+    fn metadata(&self) -> &ArchiveMetadata {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ArchiveMetadata {
+        &mut self.metadata
     }
 
     fn load_app(self: Box<Self>, platform: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>> {
-        let system = System::new(platform, Box::new(()));
+        let encoding = self.metadata.app_encoding(platform.profile(), UTF_8);
+        let system = System::new(platform, Box::new(()), encoding);
 
-        Ok(Box::new(J2MEApp::new(&self.descriptor.main_class_name, self.jar, system)?))
+        system.handle().app_properties_mut().extend(self.metadata.app_properties(encoding));
+
+        let jar = system.handle().resource().overlay_zip(&self.jar)?;
+
+        Ok(Box::new(J2MEApp::new(&self.metadata.main_class, jar, system)?))
     }
 }
 
-struct J2MEDescriptor {
-    name: String,
-    main_class_name: String,
+// the first jad whose jar is in `files`, at the path of its `MIDlet-Jar-URL` or else at `<jad name>.jar`
+fn find_jad_jar(files: &ArchiveFiles) -> Option<(String, String)> {
+    files
+        .iter()
+        .filter(|x| x.0.to_ascii_lowercase().ends_with(".jad"))
+        .find_map(|(jad_name, jad)| {
+            let base = jad_name.rfind('/').map(|x| &jad_name[..=x]).unwrap_or_default();
+            let stem = jad_name.rfind('.').map(|x| &jad_name[..x]).unwrap_or(jad_name);
+            let candidates = [
+                J2MEArchive::jar_path(jad).map(|x| format!("{}{}", base, x)),
+                Some(format!("{}.jar", stem)),
+            ];
+
+            let jar_name = candidates.into_iter().flatten().find(|x| files.contains_key(x))?;
+
+            Some((jad_name.clone(), jar_name))
+        })
 }
 
-impl J2MEDescriptor {
-    pub fn parse(data: &[u8]) -> Self {
-        let lines = data.split(|x| *x == b'\n');
+// jad and manifest should be utf-8, but some korean apps use euc-kr
+fn parse_properties(data: &[u8]) -> BTreeMap<String, String> {
+    // a wrapped line may split a multibyte character, so check the joined data
+    let joined = ArchiveMetadata::join_continuation_lines(data);
 
-        let mut name = String::new();
-        let mut main_class_name = String::new();
+    let encoding = if let Some((x, _)) = Encoding::for_bom(&joined) {
+        x
+    } else if core::str::from_utf8(&joined).is_ok() {
+        UTF_8
+    } else {
+        tracing::debug!("Descriptor is not utf-8, decoding as euc-kr");
 
-        for line in lines {
-            let line = str::from_utf8(line).unwrap().trim();
+        EUC_KR
+    };
 
-            if line.is_empty() {
-                continue;
-            }
+    ArchiveMetadata::parse_manifest(data, encoding)
+}
 
-            let mut parts = line.splitn(2, ':');
+fn create_metadata(properties: BTreeMap<String, String>) -> ArchiveMetadata {
+    let name = properties.get("MIDlet-Name").map(|x| x.as_str()).unwrap_or_default();
+    let main_class = properties
+        .get("MIDlet-1")
+        .and_then(|x| x.split(',').nth(2))
+        .map(|x| x.trim())
+        .unwrap_or_default();
+
+    let mut metadata = ArchiveMetadata::new(name, main_class, ArchivePlatform::J2me);
+    metadata.properties = properties;
+    metadata.fill_from_properties(
+        &["MIDlet-Name"],
+        &["MIDlet-Vendor"],
+        &["MIDlet-Version"],
+        &["MIDlet-Heap-Size"],
+        &["MIDlet-Screen-Size", "Nokia-MIDlet-Original-Display-Size"],
+    );
+
+    metadata
+}
 
-            let key = parts.next().unwrap().trim();
-            let value = parts.next().unwrap().trim();
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
 
-            match key {
-                "MIDlet-Name" => name = value.to_string(),
-                "MIDlet-1" => main_class_name = value.split(',').nth(2).unwrap().trim().to_string(),
-                _ => {}
-            }
-        }
+    use wie_backend::{ArchiveFiles, ArchiveLoader};
+
+    use super::{J2MEArchive, J2MEArchiveLoader};
+
+    #[test]
+    fn test_jar_path() {
+        assert_eq!(J2MEArchive::jar_path(b"MIDlet-Jar-URL: game.jar\n").as_deref(), Some("game.jar"));
+        assert_eq!(
+            J2MEArchive::jar_path(b"MIDlet-Jar-URL: ./lib/game.jar\n").as_deref(),
+            Some("lib/game.jar")
+        );
+        assert_eq!(
+            J2MEArchive::jar_path(b"MIDlet-Jar-URL: http://example.com/dl/game.jar?id=1\n").as_deref(),
+            Some("game.jar")
+        );
+
+        assert_eq!(J2MEArchive::jar_path(b"MIDlet-Name: Game\n"), None);
+        assert_eq!(J2MEArchive::jar_path(b"MIDlet-Jar-URL: http://example.com/\n"), None);
+    }
 
-        Self { name, main_class_name }
+    #[test]
+    fn test_probe() {
+        let jad = "MIDlet-Jar-URL: http://example.com/game.jar\n";
+        let probe = |files: &[(&str, &str)]| {
+            let files = files
+                .iter()
+                .map(|(name, data)| (name.to_string(), data.as_bytes().to_vec()))
+                .collect::<ArchiveFiles>();
+
+            J2MEArchiveLoader.probe(&files)
+        };
+
+        assert!(probe(&[("app/game.jad", jad), ("app/game.jar", "")]));
+        assert!(probe(&[("app/game_v2.jad", jad), ("app/game.jar", "")]));
+        assert!(probe(&[("game.jad", "MIDlet-Name: Game\n"), ("game.jar", "")]));
+        assert!(probe(&[("any.jar", "")]));
+
+        // the jar the jad points to is missing
+        assert!(!probe(&[("game.jad", jad)]));
+        assert!(!probe(&[("app.jad", jad), ("other.jar", "")]));
+        assert!(!probe(&[("app/game.jad", jad), ("game.jar", "")]));
+        assert!(!probe(&[("a.jar", ""), ("b.jar", "")]));
     }
 }
@@ -4,4 +4,4 @@ extern crate alloc;
 mod app;
 mod archive;
 
-pub use archive::J2MEArchive;
+pub use archive::{J2MEArchive, J2MEArchiveLoader};
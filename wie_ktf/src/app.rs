@@ -1,8 +1,11 @@
-use alloc::string::{String, ToString};
+use alloc::{
+    format,
+    string::{String, ToString},
+};
 
 use anyhow::Context;
 
-use wie_backend::{App, System, SystemHandle};
+use wie_backend::{App, Instant, System, SystemHandle};
 use wie_common::Event;
 use wie_core_arm::{Allocator, ArmCore};
 use wie_wipi_java::classes::org::kwis::msp::lcdui::Jlet;
@@ -19,12 +22,12 @@ pub struct KtfApp {
 }
 
 impl KtfApp {
-    pub fn new(main_class_name: &str, system: System) -> anyhow::Result<Self> {
+    pub fn new(main_class_name: &str, heap_size: u32, system: System) -> anyhow::Result<Self> {
         let system_handle = system.handle();
 
         let mut core = ArmCore::new(system_handle.clone())?;
 
-        Allocator::init(&mut core)?;
+        Allocator::init(&mut core, heap_size)?;
 
         let resource = system_handle.resource();
         let filename = resource.files().find(|x| x.starts_with("client.bin")).context("Invalid archive")?;
@@ -90,14 +93,26 @@ impl App for KtfApp {
     }
 
     fn crash_dump(&self) -> String {
-        self.core.dump_reg_stack(IMAGE_BASE)
+        format!("{}\n{}", self.core.dump_reg_stack(IMAGE_BASE), self.system.dump_tasks())
+    }
+
+    fn leak_report(&self) -> Option<String> {
+        Allocator::leak_report(&self.core).ok()
     }
 
     fn on_event(&mut self, event: Event) {
-        self.system.handle().event_queue().push(event)
+        self.system.handle().push_event(event)
     }
 
     fn tick(&mut self) -> anyhow::Result<()> {
         self.system.tick()
     }
+
+    fn next_wakeup(&self) -> Option<Instant> {
+        self.system.next_wakeup()
+    }
+
+    fn exit_requested(&self) -> bool {
+        self.system.exit_requested()
+    }
 }
@@ -1,15 +1,17 @@
-use alloc::{borrow::ToOwned, boxed::Box, collections::BTreeMap, format, string::String, vec::Vec};
+use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec::Vec};
 
 use anyhow::Context;
 
-use wie_backend::{App, Archive, Platform, System};
+use wie_backend::{
+    encoding::{Encoding, EUC_KR},
+    read_zip_file, App, Archive, ArchiveFiles, ArchiveLoader, ArchiveMetadata, ArchivePlatform, Platform, System,
+};
 
 use crate::{app::KtfApp, context::KtfContext};
 
 pub struct KtfArchive {
     jar: Vec<u8>,
-    id: String,
-    main_class_name: String,
+    metadata: ArchiveMetadata,
     additional_files: BTreeMap<String, Vec<u8>>,
 }
 
@@ -19,35 +21,81 @@ impl KtfArchive {
     }
 
     pub fn from_zip(mut files: BTreeMap<String, Vec<u8>>) -> anyhow::Result<Self> {
-        let adf = files.get("__adf__").context("Invalid format")?;
-        let adf = KtfAdf::parse(adf);
+        let adf = files.remove("__adf__").context("Invalid format")?;
+        let metadata = parse_adf(&adf, EUC_KR);
 
-        tracing::info!("Loading app {}, mclass {}", adf.aid, adf.mclass);
+        tracing::info!("Loading app {}, mclass {}", metadata.id, metadata.main_class);
 
-        let jar = files.remove(&format!("{}.jar", adf.aid)).context("Invalid format")?;
+        let jar = files.remove(&format!("{}.jar", metadata.id)).context("Invalid format")?;
 
         let additional_files = files.into_iter().filter(|x| x.0.starts_with("P/")).collect();
 
-        Ok(Self::from_jar(jar, adf.aid, adf.mclass, additional_files))
+        let mut archive = Self::from_jar(jar, metadata.id.clone(), metadata.main_class.clone(), additional_files);
+        archive.metadata = metadata;
+        archive.metadata.descriptor = adf;
+        archive.metadata.icon = archive.find_icon()?;
+
+        Ok(archive)
     }
 
     pub fn from_jar(data: Vec<u8>, id: String, main_class_name: String, additional_files: BTreeMap<String, Vec<u8>>) -> Self {
         Self {
             jar: data,
-            id,
-            main_class_name,
+            metadata: ArchiveMetadata::new(&id, &main_class_name, ArchivePlatform::Ktf),
             additional_files,
         }
     }
+
+    fn find_icon(&self) -> anyhow::Result<Option<Vec<u8>>> {
+        let path = if let Some(x) = self.metadata.property(&["Icon", "IconFile"]) {
+            x.trim_start_matches('/')
+        } else {
+            return Ok(None);
+        };
+
+        if let Some(x) = self.additional_files.get(&format!("P/{}", path)) {
+            return Ok(Some(x.clone()));
+        }
+
+        read_zip_file(&self.jar, path)
+    }
+}
+
+pub struct KtfArchiveLoader;
+
+impl ArchiveLoader for KtfArchiveLoader {
+    fn name(&self) -> &'static str {
+        "KTF"
+    }
+
+    fn description(&self) -> &'static str {
+        "__adf__ descriptor with <AID>.jar"
+    }
+
+    fn probe(&self, files: &ArchiveFiles) -> bool {
+        KtfArchive::is_ktf_archive(files)
+    }
+
+    fn load(&self, files: ArchiveFiles) -> anyhow::Result<Box<dyn Archive>> {
+        Ok(Box::new(KtfArchive::from_zip(files)?))
+    }
 }
 
 impl Archive for KtfArchive {
-    fn id(&self) -> String {
-        self.id.to_owned()
+    fn metadata(&self) -> &ArchiveMetadata {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ArchiveMetadata {
+        &mut self.metadata
     }
 
     fn load_app(self: Box<Self>, platform: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>> {
-        let system = System::new(platform, Box::new(KtfContext::new()));
+        let encoding = self.metadata.app_encoding(platform.profile(), EUC_KR);
+        let heap_size = self.metadata.native_heap_size(platform.profile());
+        let system = System::new(platform, Box::new(KtfContext::new()), encoding);
+
+        system.handle().app_properties_mut().extend(self.metadata.app_properties(encoding));
 
         system.handle().resource_mut().mount_zip(&self.jar)?;
 
@@ -56,31 +104,27 @@ impl Archive for KtfArchive {
             system.handle().resource_mut().add(path, data.clone());
         }
 
-        Ok(Box::new(KtfApp::new(&self.main_class_name, system)?))
+        Ok(Box::new(KtfApp::new(&self.metadata.main_class, heap_size, system)?))
     }
 }
 
-struct KtfAdf {
-    aid: String,
-    mclass: String,
-}
-
-impl KtfAdf {
-    pub fn parse(data: &[u8]) -> Self {
-        let mut aid = String::new();
-        let mut mclass = String::new();
-
-        let mut lines = data.split(|x| *x == b'\n');
-
-        for line in &mut lines {
-            if line.starts_with(b"AID:") {
-                aid = String::from_utf8_lossy(&line[4..]).into();
-            } else if line.starts_with(b"MClass:") {
-                mclass = String::from_utf8_lossy(&line[7..]).into();
-            }
-            // TODO load name, it's in euc-kr..
-        }
-
-        Self { aid, mclass }
-    }
+fn parse_adf(data: &[u8], encoding: &'static Encoding) -> ArchiveMetadata {
+    let properties = ArchiveMetadata::parse_properties(data, encoding);
+
+    let aid = properties.get("AID").map(|x| x.as_str()).unwrap_or_default();
+    let mclass = properties.get("MClass").map(|x| x.as_str()).unwrap_or_default();
+
+    let mut metadata = ArchiveMetadata::new(aid, mclass, ArchivePlatform::Ktf);
+    metadata.properties = properties;
+    // only AID and MClass are known to be in every adf. these keys are the best guesses without a spec of the other ones,
+    // and fields whose keys don't exist are left empty.
+    metadata.fill_from_properties(
+        &["Name", "AppName"],
+        &["Vendor", "CPName"],
+        &["Ver", "Version", "AppVer"],
+        &["HeapSize", "Heap"],
+        &["LCDSize", "ScreenSize"],
+    );
+
+    metadata
 }
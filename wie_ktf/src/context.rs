@@ -1,22 +1,33 @@
-use alloc::rc::Rc;
+use alloc::{boxed::Box, collections::BTreeMap, rc::Rc};
+use core::cell::RefMut;
 
-use wie_backend::SystemHandle;
+use wie_backend::{AbortHandle, Database, SystemHandle};
 
 use jvm::Jvm;
 
 pub struct KtfContext {
     jvm: Option<Rc<Jvm>>,
+    // wipi c timers which are set, keyed by MC_TIMER address
+    timers: BTreeMap<u32, AbortHandle>,
+    // wipi c databases which are open, keyed by handle
+    databases: BTreeMap<u32, Box<dyn Database>>,
 }
 
 impl KtfContext {
     pub fn new() -> Self {
-        Self { jvm: None }
+        Self {
+            jvm: None,
+            timers: BTreeMap::new(),
+            databases: BTreeMap::new(),
+        }
     }
 }
 
 pub trait KtfContextExt {
     fn jvm(&mut self) -> Rc<Jvm>;
     fn set_jvm(&mut self, jvm: Jvm);
+    fn timers(&mut self) -> RefMut<'_, BTreeMap<u32, AbortHandle>>;
+    fn databases(&mut self) -> RefMut<'_, BTreeMap<u32, Box<dyn Database>>>;
 }
 
 impl KtfContextExt for SystemHandle {
@@ -33,4 +44,12 @@ impl KtfContextExt for SystemHandle {
 
         context.jvm = Some(Rc::new(jvm))
     }
+
+    fn timers(&mut self) -> RefMut<'_, BTreeMap<u32, AbortHandle>> {
+        RefMut::map(self.context(), |x| &mut x.downcast_mut::<KtfContext>().unwrap().timers)
+    }
+
+    fn databases(&mut self) -> RefMut<'_, BTreeMap<u32, Box<dyn Database>>> {
+        RefMut::map(self.context(), |x| &mut x.downcast_mut::<KtfContext>().unwrap().databases)
+    }
 }
@@ -6,4 +6,4 @@ mod archive;
 mod context;
 mod runtime;
 
-pub use archive::KtfArchive;
+pub use archive::{KtfArchive, KtfArchiveLoader};
@@ -111,6 +111,7 @@ async fn register_class(core: &mut ArmCore, system: &mut SystemHandle, ptr_class
     if system.jvm().has_class(&class.name()?) {
         return Ok(());
     }
+    class.add_symbols()?;
 
     system.jvm().register_class(Box::new(class), None).await?;
 
@@ -187,7 +187,7 @@ mod test {
 
     use jvm::{runtime::JavaLangString, Jvm};
 
-    use wie_backend::{System, SystemHandle};
+    use wie_backend::{encoding::EUC_KR, System, SystemHandle};
     use wie_core_arm::{Allocator, ArmCore};
 
     use crate::{context::KtfContext, runtime::java::jvm_support::KtfJvmSupport};
@@ -196,7 +196,7 @@ mod test {
 
     async fn init_jvm(system: &mut SystemHandle) -> anyhow::Result<Rc<Jvm>> {
         let mut core = ArmCore::new(system.clone())?;
-        Allocator::init(&mut core)?;
+        Allocator::init(&mut core, 0x100000)?;
 
         let mut context = core.save_context();
         let stack = Allocator::alloc(&mut core, 0x100)?;
@@ -211,7 +211,7 @@ mod test {
 
     #[futures_test::test]
     async fn test_jvm_support() -> anyhow::Result<()> {
-        let mut system = System::new(Box::new(TestPlatform), Box::new(KtfContext::new())).handle();
+        let mut system = System::new(Box::new(TestPlatform::new()), Box::new(KtfContext::new()), EUC_KR).handle();
         let jvm = init_jvm(&mut system).await?;
 
         let string1 = JavaLangString::from_rust_string(&jvm, "test1").await?;
@@ -142,10 +142,35 @@ impl JavaClassDefinition {
         )?;
 
         let result = Self::from_raw(ptr_raw, core);
+        result.add_symbols()?;
 
         Ok(result)
     }
 
+    pub fn add_symbols(&self) -> JvmResult<()> {
+        let name = self.name()?;
+
+        let mut methods = self
+            .methods()?
+            .into_iter()
+            .map(|x| Ok((x.body()? & !1, x)))
+            .collect::<JvmResult<Vec<_>>>()?;
+        methods.sort_by_key(|x| x.0);
+
+        // compiled methods of a class are laid out one after another in the image, so a body ends where the next one starts.
+        // bodies of emulated methods keep the size they were registered with.
+        for (i, (body, method)) in methods.iter().enumerate() {
+            let size = match (self.core.image_range(*body), methods.get(i + 1)) {
+                (Some(image), Some((next, _))) if image.contains(next) => next - body,
+                _ => 0,
+            };
+
+            method.add_symbol(&name, size)?;
+        }
+
+        Ok(())
+    }
+
     pub fn read_class_hierarchy(&self) -> JvmResult<Vec<JavaClassDefinition>> {
         let mut result = vec![];
 
@@ -84,8 +84,12 @@ impl KtfClassLoader {
         Allocator::free(core, ptr_name)?;
 
         if ptr_raw != 0 {
-            let class = JavaClassDefinition::from_raw(ptr_raw, core);
-            jvm.register_class(Box::new(class), Some(this.into())).await?;
+            // get_class may have registered the class already through register_class
+            if !jvm.has_class(&name) {
+                let class = JavaClassDefinition::from_raw(ptr_raw, core);
+                class.add_symbols()?;
+                jvm.register_class(Box::new(class), Some(this.into())).await?;
+            }
 
             Ok(jvm.resolve_class(&name).await?.unwrap().java_class(jvm).await?.into())
         } else {
@@ -1,4 +1,4 @@
-use alloc::{boxed::Box, string::String, vec, vec::Vec};
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
 use core::{
     fmt::{self, Debug, Formatter},
     mem::size_of,
@@ -106,6 +106,29 @@ impl JavaMethod {
         JavaFullName::from_ptr(&self.core, raw.ptr_name)
     }
 
+    /// Address of the compiled body, 0 for native methods
+    pub fn body(&self) -> JvmResult<u32> {
+        let raw: RawJavaMethod = read_generic(&self.core, self.ptr_raw)?;
+
+        Ok(raw.fn_body)
+    }
+
+    /// Names the code of this method in backtraces as `class.method`, `size` is the length of the body or 0 if unknown
+    pub fn add_symbol(&self, class_name: &str, size: u32) -> JvmResult<()> {
+        let raw: RawJavaMethod = read_generic(&self.core, self.ptr_raw)?;
+        let name = format!("{}.{}", class_name, self.name()?.name);
+
+        let mut core = self.core.clone();
+        if raw.fn_body != 0 {
+            core.add_symbol(raw.fn_body, size, &name);
+        }
+        if MethodAccessFlags::from_bits_truncate(raw.access_flags).contains(MethodAccessFlags::NATIVE) && raw.fn_body_native_or_exception_table != 0 {
+            core.add_symbol(raw.fn_body_native_or_exception_table, 0, &name);
+        }
+
+        Ok(())
+    }
+
     pub async fn run(&self, args: Box<[JavaValue]>) -> JvmResult<u32> {
         let raw: RawJavaMethod = read_generic(&self.core, self.ptr_raw)?;
 
@@ -1,10 +1,13 @@
-use alloc::{boxed::Box, vec, vec::Vec};
+use alloc::{boxed::Box, collections::BTreeMap, vec, vec::Vec};
+use core::cell::RefMut;
 
-use wie_backend::{AsyncCallable, SystemHandle};
+use wie_backend::{AbortHandle, AsyncCallable, Database, SystemHandle};
 use wie_common::util::{read_generic, write_generic, ByteRead, ByteWrite};
 use wie_core_arm::{Allocator, ArmCore, ArmEngineError, EmulatedFunction, EmulatedFunctionParam};
 use wie_wipi_c::{WIPICContext, WIPICError, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord};
 
+use crate::context::KtfContextExt;
+
 pub struct KtfWIPICContext<'a> {
     core: &'a mut ArmCore,
     system: &'a mut SystemHandle,
@@ -37,12 +40,26 @@ impl WIPICContext for KtfWIPICContext<'_> {
         Allocator::free(self.core, address)
     }
 
+    fn realloc_raw(&mut self, address: WIPICWord, size: WIPICWord) -> WIPICResult<WIPICWord> {
+        Allocator::realloc(self.core, address, size)
+    }
+
     fn data_ptr(&self, memory: WIPICMemoryId) -> WIPICResult<WIPICWord> {
         let base: WIPICWord = read_generic(self.core, memory.0)?;
 
         Ok(base + 8) // all data has offset of 8 bytes
     }
 
+    fn total_memory(&self) -> WIPICResult<WIPICWord> {
+        Ok(Allocator::stats(self.core)?.total)
+    }
+
+    fn free_memory(&self) -> WIPICResult<WIPICWord> {
+        let stats = Allocator::stats(self.core)?;
+
+        Ok(stats.total - stats.used)
+    }
+
     fn register_function(&mut self, body: WIPICMethodBody) -> WIPICResult<WIPICWord> {
         struct CMethodProxy {
             body: WIPICMethodBody,
@@ -73,6 +90,10 @@ impl WIPICContext for KtfWIPICContext<'_> {
                     .call(&mut context, vec![a0, a1, a2, a3, a4, a5, a6, a7, a8].into_boxed_slice())
                     .await
             }
+
+            fn name(&self) -> &'static str {
+                self.body.name()
+            }
         }
 
         let proxy = CMethodProxy::new(body);
@@ -88,7 +109,7 @@ impl WIPICContext for KtfWIPICContext<'_> {
         self.core.run_function(address, args).await
     }
 
-    fn spawn(&mut self, callback: WIPICMethodBody) -> WIPICResult<()> {
+    fn spawn(&mut self, name: &str, callback: WIPICMethodBody) -> WIPICResult<AbortHandle> {
         struct SpawnProxy {
             core: ArmCore,
             system: SystemHandle,
@@ -106,13 +127,24 @@ impl WIPICContext for KtfWIPICContext<'_> {
 
         let system = self.system.clone();
 
-        self.core.spawn(SpawnProxy {
-            core: self.core.clone(),
-            system,
-            callback,
-        });
+        let handle = self.core.spawn_named(
+            name,
+            SpawnProxy {
+                core: self.core.clone(),
+                system,
+                callback,
+            },
+        );
+
+        Ok(handle.abort_handle())
+    }
+
+    fn timers(&mut self) -> RefMut<'_, BTreeMap<WIPICWord, AbortHandle>> {
+        self.system.timers()
+    }
 
-        Ok(())
+    fn databases(&mut self) -> RefMut<'_, BTreeMap<WIPICWord, Box<dyn Database>>> {
+        self.system.databases()
     }
 }
 
@@ -1,31 +1,60 @@
-use alloc::string::String;
+use alloc::{format, string::String};
 
 use anyhow::Context;
 use elf::{endian::AnyEndian, ElfBytes};
 
-use wie_backend::{App, System, SystemHandle};
+use wie_backend::{App, Instant, System, SystemHandle};
 use wie_common::Event;
 use wie_core_arm::{Allocator, ArmCore};
+use wie_wipi_c::WIPICEvent;
+
+// entry points a wipi c clet exports, the platform calls them to drive the clet
+#[derive(Clone, Copy, Default)]
+struct Clet {
+    start: Option<u32>,
+    pause: Option<u32>,
+    resume: Option<u32>,
+    destroy: Option<u32>,
+    paint: Option<u32>,
+    handle_event: Option<u32>,
+}
+
+impl Clet {
+    fn set(&mut self, name: &str, address: u32) {
+        let handler = match name {
+            "startClet" => &mut self.start,
+            "pauseClet" => &mut self.pause,
+            "resumeClet" => &mut self.resume,
+            "destroyClet" => &mut self.destroy,
+            "paintClet" => &mut self.paint,
+            "handleCletEvent" => &mut self.handle_event,
+            _ => return,
+        };
+
+        *handler = Some(address);
+    }
+}
 
 pub struct LgtApp {
     core: ArmCore,
     system: System,
     entrypoint: u32,
+    clet: Clet,
     main_class_name: String,
 }
 
 impl LgtApp {
-    pub fn new(main_class_name: &str, system: System) -> anyhow::Result<Self> {
+    pub fn new(main_class_name: &str, heap_size: u32, system: System) -> anyhow::Result<Self> {
         let system_handle = system.handle();
 
         let mut core = ArmCore::new(system_handle.clone())?;
 
-        Allocator::init(&mut core)?;
+        Allocator::init(&mut core, heap_size)?;
 
         let resource = system_handle.resource();
         let data = resource.data(resource.id("binary.mod").context("Resource not found")?);
 
-        let entrypoint = Self::load(&mut core, data)?;
+        let (entrypoint, clet) = Self::load(&mut core, data)?;
 
         let main_class_name = main_class_name.replace('.', "/");
 
@@ -33,18 +62,56 @@ impl LgtApp {
             core,
             system,
             entrypoint,
+            clet,
             main_class_name,
         })
     }
 
     #[tracing::instrument(name = "start", skip_all)]
-    async fn do_start(core: &mut ArmCore, _system: &mut SystemHandle, entrypoint: u32, _main_class_name: String) -> anyhow::Result<()> {
-        core.run_function(entrypoint + 1, &[]).await?;
+    async fn do_start(core: &mut ArmCore, system: &mut SystemHandle, entrypoint: u32, clet: Clet, _main_class_name: String) -> anyhow::Result<()> {
+        // initializes the binary, the clet itself is started and driven through its entry points
+        core.run_function::<()>(entrypoint + 1, &[]).await?;
+
+        Self::call_clet(core, clet.start, &[0, 0]).await?; // argc, argv
+
+        loop {
+            match system.next_event().await {
+                Event::Pause => Self::call_clet(core, clet.pause, &[]).await?,
+                Event::Resume => Self::call_clet(core, clet.resume, &[]).await?,
+                Event::Exit => {
+                    Self::call_clet(core, clet.destroy, &[]).await?;
+                    system.exit();
+
+                    return Ok(());
+                }
+                Event::Redraw => {
+                    let (width, height) = {
+                        let mut platform = system.platform();
+                        let screen = platform.screen();
+
+                        (screen.width(), screen.height())
+                    };
+
+                    Self::call_clet(core, clet.paint, &[0, 0, width, height]).await?
+                }
+                x => {
+                    if let Some(event) = WIPICEvent::from_event(&x) {
+                        Self::call_clet(core, clet.handle_event, &[event.r#type as _, event.param1 as _, event.param2 as _]).await?
+                    }
+                }
+            }
+        }
+    }
 
-        todo!()
+    async fn call_clet(core: &mut ArmCore, handler: Option<u32>, params: &[u32]) -> anyhow::Result<()> {
+        if let Some(x) = handler {
+            core.run_function::<()>(x, params).await?;
+        }
+
+        Ok(())
     }
 
-    fn load(core: &mut ArmCore, data: &[u8]) -> anyhow::Result<u32> {
+    fn load(core: &mut ArmCore, data: &[u8]) -> anyhow::Result<(u32, Clet)> {
         let elf = ElfBytes::<AnyEndian>::minimal_parse(data)?;
 
         anyhow::ensure!(elf.ehdr.e_machine == elf::abi::EM_ARM, "Invalid machine type");
@@ -70,9 +137,25 @@ impl LgtApp {
             }
         }
 
+        let mut clet = Clet::default();
+        if let Some((symbols, strtab)) = elf.symbol_table()? {
+            for symbol in symbols.iter().filter(|x| x.st_symtype() == elf::abi::STT_FUNC && x.st_value != 0) {
+                let name = match strtab.get(symbol.st_name as usize) {
+                    Ok(x) => x,
+                    Err(err) => {
+                        tracing::warn!("Skipping symbol at {:#x}: {}", symbol.st_value, err);
+                        continue;
+                    }
+                };
+
+                core.add_symbol(symbol.st_value as u32, symbol.st_size as u32, name);
+                clet.set(name, symbol.st_value as u32);
+            }
+        }
+
         tracing::debug!("Entrypoint: {:#x}", elf.ehdr.e_entry);
 
-        Ok(elf.ehdr.e_entry as u32)
+        Ok((elf.ehdr.e_entry as u32, clet))
     }
 }
 
@@ -82,23 +165,36 @@ impl App for LgtApp {
         let mut system_handle = self.system.handle();
 
         let entrypoint = self.entrypoint;
+        let clet = self.clet;
         let main_class_name = self.main_class_name.clone();
 
         self.core
-            .spawn(move || async move { Self::do_start(&mut core, &mut system_handle, entrypoint, main_class_name).await });
+            .spawn(move || async move { Self::do_start(&mut core, &mut system_handle, entrypoint, clet, main_class_name).await });
 
         Ok(())
     }
 
     fn crash_dump(&self) -> String {
-        self.core.dump_reg_stack(0)
+        format!("{}\n{}", self.core.dump_reg_stack(0), self.system.dump_tasks())
+    }
+
+    fn leak_report(&self) -> Option<String> {
+        Allocator::leak_report(&self.core).ok()
     }
 
     fn on_event(&mut self, event: Event) {
-        self.system.handle().event_queue().push(event)
+        self.system.handle().push_event(event)
     }
 
     fn tick(&mut self) -> anyhow::Result<()> {
         self.system.tick()
     }
+
+    fn next_wakeup(&self) -> Option<Instant> {
+        self.system.next_wakeup()
+    }
+
+    fn exit_requested(&self) -> bool {
+        self.system.exit_requested()
+    }
 }
@@ -1,15 +1,17 @@
-use alloc::{borrow::ToOwned, boxed::Box, collections::BTreeMap, format, string::String, vec::Vec};
+use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec::Vec};
 
 use anyhow::Context;
 
-use wie_backend::{App, Archive, Platform, System};
+use wie_backend::{
+    encoding::{Encoding, EUC_KR},
+    read_zip_file, App, Archive, ArchiveFiles, ArchiveLoader, ArchiveMetadata, ArchivePlatform, Platform, System,
+};
 
 use crate::app::LgtApp;
 
 pub struct LgtArchive {
     jar: Vec<u8>,
-    id: String,
-    main_class_name: String,
+    metadata: ArchiveMetadata,
 }
 
 impl LgtArchive {
@@ -18,61 +20,88 @@ impl LgtArchive {
     }
 
     pub fn from_zip(mut files: BTreeMap<String, Vec<u8>>) -> anyhow::Result<Self> {
-        let app_info = files.get("app_info").context("Invalid format")?;
-        let app_info = LgtAppInfo::parse(app_info);
+        let app_info = files.remove("app_info").context("Invalid format")?;
+        let mut metadata = parse_app_info(&app_info, EUC_KR);
+        metadata.descriptor = app_info;
 
-        tracing::info!("Loading app {}, mclass {}", app_info.aid, app_info.mclass);
+        tracing::info!("Loading app {}, mclass {}", metadata.id, metadata.main_class);
 
-        let jar = files.remove(&format!("{}.jar", app_info.aid)).context("Invalid format")?;
+        let jar = files.remove(&format!("{}.jar", metadata.id)).context("Invalid format")?;
 
-        Ok(Self::from_jar(jar, &app_info.aid, &app_info.mclass))
+        if let Some(x) = metadata.property(&["Icon", "IconFile"]) {
+            metadata.icon = read_zip_file(&jar, x.trim_start_matches('/'))?;
+        }
+
+        Ok(Self { jar, metadata })
     }
 
     pub fn from_jar(data: Vec<u8>, id: &str, main_class_name: &str) -> Self {
         Self {
             jar: data,
-            id: id.into(),
-            main_class_name: main_class_name.into(),
+            metadata: ArchiveMetadata::new(id, main_class_name, ArchivePlatform::Lgt),
         }
     }
 }
 
-impl Archive for LgtArchive {
-    fn id(&self) -> String {
-        self.id.to_owned()
+pub struct LgtArchiveLoader;
+
+impl ArchiveLoader for LgtArchiveLoader {
+    fn name(&self) -> &'static str {
+        "LGT"
     }
 
-    fn load_app(self: Box<Self>, platform: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>> {
-        let system = System::new(platform, Box::new(()));
+    fn description(&self) -> &'static str {
+        "app_info descriptor with <AID>.jar"
+    }
 
-        system.handle().resource_mut().mount_zip(&self.jar)?;
+    fn probe(&self, files: &ArchiveFiles) -> bool {
+        LgtArchive::is_lgt_archive(files)
+    }
 
-        Ok(Box::new(LgtApp::new(&self.main_class_name, system)?))
+    fn load(&self, files: ArchiveFiles) -> anyhow::Result<Box<dyn Archive>> {
+        Ok(Box::new(LgtArchive::from_zip(files)?))
     }
 }
 
-// almost similar to KtfAdf.. can we merge these?
-struct LgtAppInfo {
-    aid: String,
-    mclass: String,
-}
+impl Archive for LgtArchive {
+    fn metadata(&self) -> &ArchiveMetadata {
+        &self.metadata
+    }
 
-impl LgtAppInfo {
-    pub fn parse(data: &[u8]) -> Self {
-        let mut aid = String::new();
-        let mut mclass = String::new();
+    fn metadata_mut(&mut self) -> &mut ArchiveMetadata {
+        &mut self.metadata
+    }
 
-        let mut lines = data.split(|x| *x == b'\n');
+    fn load_app(self: Box<Self>, platform: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>> {
+        let encoding = self.metadata.app_encoding(platform.profile(), EUC_KR);
+        let heap_size = self.metadata.native_heap_size(platform.profile());
+        let system = System::new(platform, Box::new(()), encoding);
 
-        for line in &mut lines {
-            if line.starts_with(b"AID:") {
-                aid = String::from_utf8_lossy(&line[4..]).into();
-            } else if line.starts_with(b"MClass:") {
-                mclass = String::from_utf8_lossy(&line[7..]).into();
-            }
-            // TODO load name, it's in euc-kr..
-        }
+        system.handle().app_properties_mut().extend(self.metadata.app_properties(encoding));
 
-        Self { aid, mclass }
+        system.handle().resource_mut().mount_zip(&self.jar)?;
+
+        Ok(Box::new(LgtApp::new(&self.metadata.main_class, heap_size, system)?))
     }
 }
+
+// same format as ktf's adf
+fn parse_app_info(data: &[u8], encoding: &'static Encoding) -> ArchiveMetadata {
+    let properties = ArchiveMetadata::parse_properties(data, encoding);
+
+    let aid = properties.get("AID").map(|x| x.as_str()).unwrap_or_default();
+    let mclass = properties.get("MClass").map(|x| x.as_str()).unwrap_or_default();
+
+    let mut metadata = ArchiveMetadata::new(aid, mclass, ArchivePlatform::Lgt);
+    metadata.properties = properties;
+    // same guessed keys as ktf's adf
+    metadata.fill_from_properties(
+        &["Name", "AppName"],
+        &["Vendor", "CPName"],
+        &["Ver", "Version", "AppVer"],
+        &["HeapSize", "Heap"],
+        &["LCDSize", "ScreenSize"],
+    );
+
+    metadata
+}
@@ -4,4 +4,4 @@ extern crate alloc;
 mod app;
 mod archive;
 
-pub use archive::LgtArchive;
+pub use archive::{LgtArchive, LgtArchiveLoader};
@@ -3,9 +3,12 @@ use alloc::{
     vec::Vec,
 };
 
-use wie_backend::{App, System, SystemHandle};
+use jvm::ClassInstanceRef;
+
+use wie_backend::{App, Instant, System, SystemHandle};
 use wie_common::Event;
 use wie_core_jvm::JvmCore;
+use wie_wipi_java::classes::org::kwis::msp::lcdui::Jlet;
 
 pub struct SktApp {
     system: System,
@@ -43,6 +46,27 @@ impl SktApp {
             }
         }
 
+        // events, including lifecycle ones, go through jlet's event queue like on ktf
+        let jlet: ClassInstanceRef<Jlet> = core
+            .jvm()
+            .invoke_static("org/kwis/msp/lcdui/Jlet", "getActiveJlet", "()Lorg/kwis/msp/lcdui/Jlet;", [])
+            .await?;
+        if jlet.is_null() {
+            tracing::warn!("{} is not a Jlet, only exit is handled", main_class_name);
+
+            loop {
+                if let Event::Exit = system.next_event().await {
+                    system.exit();
+
+                    return Ok(());
+                }
+            }
+        }
+
+        core.jvm()
+            .invoke_static("org/kwis/msp/lcdui/Main", "main", "([Ljava/lang/String;)V", [None.into()])
+            .await?;
+
         Ok(())
     }
 }
@@ -62,14 +86,22 @@ impl App for SktApp {
     }
 
     fn crash_dump(&self) -> String {
-        "".into() // TODO
+        self.system.dump_tasks()
     }
 
     fn on_event(&mut self, event: Event) {
-        self.system.handle().event_queue().push(event)
+        self.system.handle().push_event(event)
     }
 
     fn tick(&mut self) -> anyhow::Result<()> {
         self.system.tick()
     }
+
+    fn next_wakeup(&self) -> Option<Instant> {
+        self.system.next_wakeup()
+    }
+
+    fn exit_requested(&self) -> bool {
+        self.system.exit_requested()
+    }
 }
@@ -1,22 +1,17 @@
-use alloc::{
-    borrow::ToOwned,
-    boxed::Box,
-    collections::BTreeMap,
-    str,
-    string::{String, ToString},
-    vec::Vec,
-};
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec::Vec};
 
 use anyhow::Context;
 
-use wie_backend::{App, Archive, Platform, System};
+use wie_backend::{
+    encoding::{Encoding, EUC_KR},
+    read_zip_file, App, Archive, ArchiveFiles, ArchiveLoader, ArchiveMetadata, ArchivePlatform, Platform, System,
+};
 
 use crate::app::SktApp;
 
 pub struct SktArchive {
     jar: Vec<u8>,
-    id: String,
-    main_class_name: String,
+    metadata: ArchiveMetadata,
     additional_files: BTreeMap<String, Vec<u8>>,
 }
 
@@ -26,66 +21,103 @@ impl SktArchive {
     }
 
     pub fn from_zip(mut files: BTreeMap<String, Vec<u8>>) -> anyhow::Result<Self> {
-        let msd_file = files.iter().find(|x| x.0.ends_with(".msd")).unwrap();
-        let msd = SktMsd::parse(msd_file.0, msd_file.1);
+        let msd_name = files.keys().find(|x| x.ends_with(".msd")).context("Invalid format")?.clone();
+        let msd = files[&msd_name].clone();
+        let mut metadata = parse_msd(&msd_name, &msd, EUC_KR);
+        metadata.descriptor = msd;
 
-        tracing::info!("Loading app {}, mclass {}", msd.id, msd.main_class);
+        tracing::info!("Loading app {}, mclass {}", metadata.id, metadata.main_class);
 
-        let jar_name = msd_file.0.replace(".msd", ".jar");
+        let jar_name = msd_name.replace(".msd", ".jar");
         let jar = files.remove(&jar_name).context("Invalid format")?;
 
-        Ok(Self::from_jar(jar, &msd.id, &msd.main_class, files))
+        if let Some(x) = metadata.property(&["MIDlet-Icon"]) {
+            metadata.icon = read_zip_file(&jar, x.trim_start_matches('/'))?;
+        }
+
+        Ok(Self {
+            jar,
+            metadata,
+            additional_files: files,
+        })
     }
 
     pub fn from_jar(data: Vec<u8>, id: &str, main_class_name: &str, additional_files: BTreeMap<String, Vec<u8>>) -> Self {
         Self {
             jar: data,
-            id: id.into(),
-            main_class_name: main_class_name.into(),
+            metadata: ArchiveMetadata::new(id, main_class_name, ArchivePlatform::Skt),
             additional_files,
         }
     }
 }
 
+pub struct SktArchiveLoader;
+
+impl ArchiveLoader for SktArchiveLoader {
+    fn name(&self) -> &'static str {
+        "SKT"
+    }
+
+    fn description(&self) -> &'static str {
+        "<name>.msd descriptor with <name>.jar"
+    }
+
+    fn probe(&self, files: &ArchiveFiles) -> bool {
+        SktArchive::is_skt_archive(files)
+    }
+
+    fn load(&self, files: ArchiveFiles) -> anyhow::Result<Box<dyn Archive>> {
+        Ok(Box::new(SktArchive::from_zip(files)?))
+    }
+}
+
 impl Archive for SktArchive {
-    fn id(&self) -> String {
-        self.id.to_owned()
+    fn metadata(&self) -> &ArchiveMetadata {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ArchiveMetadata {
+        &mut self.metadata
     }
 
     fn load_app(self: Box<Self>, platform: Box<dyn Platform>) -> anyhow::Result<Box<dyn App>> {
-        let system = System::new(platform, Box::new(()));
+        let encoding = self.metadata.app_encoding(platform.profile(), EUC_KR);
+        let system = System::new(platform, Box::new(()), encoding);
+
+        system.handle().app_properties_mut().extend(self.metadata.app_properties(encoding));
 
         for (filename, data) in self.additional_files {
             system.handle().resource_mut().add(&filename, data)
         }
 
-        Ok(Box::new(SktApp::new(&self.main_class_name, self.jar, system)?))
-    }
-}
+        let jar = system.handle().resource().overlay_zip(&self.jar)?;
 
-struct SktMsd {
-    id: String,
-    main_class: String,
+        Ok(Box::new(SktApp::new(&self.metadata.main_class, jar, system)?))
+    }
 }
 
-impl SktMsd {
-    pub fn parse(filename: &str, data: &[u8]) -> Self {
-        let mut main_class = String::new();
-        let mut id = filename[..filename.find('.').unwrap()].into();
-
-        let mut lines = data.split(|x| *x == b'\n');
-
-        for line in &mut lines {
-            if line.starts_with(b"MIDlet-1:") {
-                let value = line[10..].split(|x| *x == b',').collect::<Vec<_>>();
-                main_class = str::from_utf8(value[2]).unwrap().trim().to_string();
-            }
-            if line.starts_with(b"DD-ProgName") {
-                id = str::from_utf8(&line[12..]).unwrap().trim().to_string();
-            }
-            // TODO load name, it's in euc-kr..
-        }
-
-        Self { id, main_class }
-    }
+fn parse_msd(filename: &str, data: &[u8], encoding: &'static Encoding) -> ArchiveMetadata {
+    let properties = ArchiveMetadata::parse_properties(data, encoding);
+
+    let id = properties
+        .get("DD-ProgName")
+        .map(|x| x.as_str())
+        .unwrap_or_else(|| filename.split_once('.').map_or(filename, |x| x.0));
+    let main_class = properties
+        .get("MIDlet-1")
+        .and_then(|x| x.split(',').nth(2))
+        .map(|x| x.trim())
+        .unwrap_or_default();
+
+    let mut metadata = ArchiveMetadata::new(id, main_class, ArchivePlatform::Skt);
+    metadata.properties = properties;
+    metadata.fill_from_properties(
+        &["MIDlet-Name"],
+        &["MIDlet-Vendor"],
+        &["MIDlet-Version"],
+        &["MIDlet-Heap-Size", "DD-HeapSize"],
+        &["MIDlet-Screen-Size", "DD-ScreenSize"],
+    );
+
+    metadata
 }
@@ -4,4 +4,4 @@ extern crate alloc;
 mod app;
 mod archive;
 
-pub use archive::SktArchive;
+pub use archive::{SktArchive, SktArchiveLoader};
@@ -9,3 +9,11 @@ pub mod uic;
 pub mod unk12;
 pub mod unk3;
 pub mod util;
+
+// M_E_* error codes returned by WIPI C functions
+pub(crate) const M_E_ERROR: i32 = -1;
+pub(crate) const M_E_NOENT: i32 = -12;
+pub(crate) const M_E_NOSPACE: i32 = -13;
+pub(crate) const M_E_SHORTBUF: i32 = -18;
+pub(crate) const M_E_ACCESS: i32 = -20;
+pub(crate) const M_E_BADRECID: i32 = -22;
@@ -1,48 +1,47 @@
-use alloc::{boxed::Box, str, string::String, vec, vec::Vec};
+use alloc::{string::String, vec, vec::Vec};
 use core::mem::size_of;
 
-use bytemuck::{Pod, Zeroable};
-
-use wie_backend::Database;
-use wie_common::util::{read_generic, write_generic};
+use wie_backend::{Database, DatabaseError, DatabaseResult};
+use wie_common::util::write_generic;
 
 use crate::{
+    api::{M_E_ACCESS, M_E_BADRECID, M_E_ERROR, M_E_NOENT, M_E_NOSPACE, M_E_SHORTBUF},
     context::{WIPICContext, WIPICMethodBody, WIPICWord},
     method::MethodImpl,
     WIPICResult,
 };
 
-#[derive(Pod, Zeroable, Copy, Clone)]
-#[repr(C)]
-struct DatabaseHandle {
-    name: [u8; 32], // TODO hardcoded max size
-}
-
 fn gen_stub(id: WIPICWord, name: &'static str) -> WIPICMethodBody {
     let body = move |_: &mut dyn WIPICContext| async move { Err::<(), _>(anyhow::anyhow!("Unimplemented database{}: {}", id, name)) };
 
-    body.into_body()
+    body.into_named_body(name)
 }
 
 async fn open_database(context: &mut dyn WIPICContext, name: String, record_size: i32, create: i32, mode: i32) -> WIPICResult<i32> {
     tracing::debug!("MC_dbOpenDataBase({}, {}, {}, {})", name, record_size, create, mode);
 
-    let name_bytes = name.as_bytes();
-    let mut handle = DatabaseHandle { name: [0; 32] };
+    let database = match context.system().platform().database_repository().open(&name, create != 0) {
+        Ok(x) => x,
+        Err(x) => return Ok(error_code(x, M_E_NOENT)),
+    };
 
-    handle.name[..name_bytes.len()].copy_from_slice(name_bytes);
+    // the handle is only an id for the database kept open in `databases`
+    let handle = context.alloc_raw(size_of::<WIPICWord>() as _)?;
+    context.databases().insert(handle, database);
 
-    let ptr_handle = context.alloc_raw(size_of::<DatabaseHandle>() as _)?;
-    write_generic(context, ptr_handle, handle)?;
+    tracing::debug!("Created database handle {:#x}", handle);
 
-    tracing::debug!("Created database handle {:#x}", ptr_handle);
-
-    Ok(ptr_handle as _)
+    Ok(handle as _)
 }
 
 async fn close_database(context: &mut dyn WIPICContext, db_id: i32) -> WIPICResult<i32> {
     tracing::debug!("MC_dbCloseDataBase({:#x})", db_id);
 
+    if context.databases().remove(&(db_id as _)).is_none() {
+        tracing::warn!("Invalid database handle {:#x}", db_id);
+
+        return Ok(M_E_NOENT);
+    }
     context.free_raw(db_id as _)?;
 
     Ok(0) // success
@@ -51,8 +50,10 @@ async fn close_database(context: &mut dyn WIPICContext, db_id: i32) -> WIPICResu
 async fn list_record(context: &mut dyn WIPICContext, db_id: i32, buf_ptr: WIPICWord, buf_len: WIPICWord) -> WIPICResult<i32> {
     tracing::debug!("MC_dbListRecords({:#x}, {:#x}, {})", db_id, buf_ptr, buf_len);
 
-    let db = get_database_from_db_id(context, db_id);
-    let ids = db.get_record_ids();
+    let ids = match with_database(context, db_id, M_E_NOENT, |db| db.get_record_ids()) {
+        Ok(x) => x,
+        Err(x) => return Ok(x),
+    };
 
     let mut cursor = 0;
     for &id in &ids {
@@ -67,59 +68,52 @@ async fn write_record_single(context: &mut dyn WIPICContext, db_id: i32, buf_ptr
     tracing::debug!("MC_db_write_record_single({:#x}, {:#x}, {})", db_id, buf_ptr, buf_len);
 
     let data = context.read_bytes(buf_ptr, buf_len)?;
-    let mut db = get_database_from_db_id(context, db_id);
-
-    db.set(1, &data);
 
-    Ok(1)
+    match with_database(context, db_id, M_E_NOENT, |db| db.set(1, &data)) {
+        Ok(_) => Ok(1),
+        Err(x) => Ok(x),
+    }
 }
 
 async fn delete_record(context: &mut dyn WIPICContext, db_id: i32, rec_id: i32) -> WIPICResult<i32> {
     tracing::debug!("MC_dbDeleteRecord({:#x}, {})", db_id, rec_id);
 
-    let mut db = get_database_from_db_id(context, db_id);
-
-    let result = db.delete(rec_id as _);
-
-    if result {
-        Ok(0) // success
-    } else {
-        Ok(-22) // M_E_BADRECID
+    match with_database(context, db_id, M_E_BADRECID, |db| db.delete(rec_id as _)) {
+        Ok(_) => Ok(0), // success
+        Err(x) => Ok(x),
     }
 }
 
 async fn read_record_single(context: &mut dyn WIPICContext, db_id: i32, buf_ptr: WIPICWord, buf_len: WIPICWord) -> WIPICResult<i32> {
     tracing::debug!("MC_db_read_record_single({:#x}, {:#x}, {})", db_id, buf_ptr, buf_len);
 
-    let db = get_database_from_db_id(context, db_id);
+    let data = match with_database(context, db_id, M_E_BADRECID, |db| db.get(1)) {
+        Ok(x) => x,
+        Err(x) => return Ok(x),
+    };
 
-    if let Some(x) = db.get(1) {
-        if buf_len < x.len() as _ {
-            return Ok(-18); // M_E_SHORTBUF
-        }
-        context.write_bytes(buf_ptr, &x)?;
-
-        Ok(0)
-    } else {
-        Ok(-22) // M_E_BADRECID
+    if buf_len < data.len() as _ {
+        return Ok(M_E_SHORTBUF);
     }
+    context.write_bytes(buf_ptr, &data)?;
+
+    Ok(0)
 }
 
 async fn select_record(context: &mut dyn WIPICContext, db_id: i32, rec_id: i32, buf_ptr: WIPICWord, buf_len: WIPICWord) -> WIPICResult<i32> {
     tracing::debug!("MC_dbSelectRecord({:#x}, {}, {:#x}, {})", db_id, rec_id, buf_ptr, buf_len);
 
-    let db = get_database_from_db_id(context, db_id);
+    let data = match with_database(context, db_id, M_E_BADRECID, |db| db.get(rec_id as _)) {
+        Ok(x) => x,
+        Err(x) => return Ok(x),
+    };
 
-    if let Some(x) = db.get(rec_id as _) {
-        if buf_len < x.len() as _ {
-            return Ok(-18); // M_E_SHORTBUF
-        }
-        context.write_bytes(buf_ptr, &x)?;
-
-        Ok(0)
-    } else {
-        Ok(-22) // M_E_BADRECID
+    if buf_len < data.len() as _ {
+        return Ok(M_E_SHORTBUF);
     }
+    context.write_bytes(buf_ptr, &data)?;
+
+    Ok(0)
 }
 
 async fn unk16(_context: &mut dyn WIPICContext) -> WIPICResult<i32> {
@@ -128,33 +122,53 @@ async fn unk16(_context: &mut dyn WIPICContext) -> WIPICResult<i32> {
     Ok(1)
 }
 
-fn get_database_from_db_id(context: &mut dyn WIPICContext, db_id: i32) -> Box<dyn Database> {
-    let handle: DatabaseHandle = read_generic(context, db_id as _).unwrap();
+// `not_found` is the code to return when the database itself or the requested record does not exist
+fn error_code(error: DatabaseError, not_found: i32) -> i32 {
+    tracing::warn!("Database error: {}", error);
 
-    let name_length = handle.name.iter().position(|&c| c == 0).unwrap_or(handle.name.len());
-    let db_name = str::from_utf8(&handle.name[..name_length]).unwrap();
+    match error {
+        DatabaseError::NotFound => not_found,
+        DatabaseError::Full => M_E_NOSPACE,
+        DatabaseError::ReadOnly => M_E_ACCESS,
+        DatabaseError::InvalidName | DatabaseError::Io(_) => M_E_ERROR,
+    }
+}
 
-    context.system().platform().database_repository().open(db_name)
+// runs `f` on the database opened as `db_id`, returning the error code on failure. an unknown handle is M_E_NOENT
+fn with_database<T>(
+    context: &mut dyn WIPICContext,
+    db_id: i32,
+    not_found: i32,
+    f: impl FnOnce(&mut dyn Database) -> DatabaseResult<T>,
+) -> Result<T, i32> {
+    let mut databases = context.databases();
+    let database = databases.get_mut(&(db_id as _)).ok_or_else(|| {
+        tracing::warn!("Invalid database handle {:#x}", db_id);
+
+        M_E_NOENT
+    })?;
+
+    f(database.as_mut()).map_err(|x| error_code(x, not_found))
 }
 
 pub fn get_database_method_table() -> Vec<WIPICMethodBody> {
     vec![
-        open_database.into_body(),
-        read_record_single.into_body(),
-        write_record_single.into_body(),
-        close_database.into_body(),
-        select_record.into_body(),
+        open_database.into_named_body("MC_dbOpenDataBase"),
+        read_record_single.into_named_body("MC_db_read_record_single"),
+        write_record_single.into_named_body("MC_db_write_record_single"),
+        close_database.into_named_body("MC_dbCloseDataBase"),
+        select_record.into_named_body("MC_dbSelectRecord"),
         gen_stub(5, "MC_dbUpdateRecord"),
-        delete_record.into_body(),
-        list_record.into_body(),
+        delete_record.into_named_body("MC_dbDeleteRecord"),
+        list_record.into_named_body("MC_dbListRecords"),
         gen_stub(8, "MC_dbSortRecords"),
         gen_stub(9, "MC_dbGetAccessMode"),
         gen_stub(10, "MC_dbGetNumberOfRecords"),
         gen_stub(11, "MC_dbGetRecordSize"),
         gen_stub(12, "MC_dbListDataBases"),
-        gen_stub(13, ""),
-        gen_stub(14, ""),
-        gen_stub(15, ""),
-        unk16.into_body(),
+        gen_stub(13, "MC_dbUnk13"),
+        gen_stub(14, "MC_dbUnk14"),
+        gen_stub(15, "MC_dbUnk15"),
+        unk16.into_named_body("MC_dbUnk16"),
     ]
 }
@@ -12,6 +12,7 @@ use wie_common::util::{read_generic, write_generic};
 
 use crate::{
     context::{WIPICContext, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord},
+    event::WIPICEvent,
     method::MethodImpl,
 };
 
@@ -21,12 +22,15 @@ use self::{
     image::WIPICImage,
 };
 
-const FRAMEBUFFER_DEPTH: u32 = 16; // XXX hardcode to 16bpp as some game requires 16bpp framebuffer
+// most profiles have 16 bit color depth, as some games require 16bpp framebuffer
+fn framebuffer_bpp(context: &mut dyn WIPICContext) -> WIPICWord {
+    context.system().platform().profile().framebuffer_bpp()
+}
 
 fn gen_stub(id: WIPICWord, name: &'static str) -> WIPICMethodBody {
     let body = move |_: &mut dyn WIPICContext| async move { Err::<(), _>(anyhow::anyhow!("Unimplemented graphics{}: {}", id, name)) };
 
-    body.into_body()
+    body.into_named_body(name)
 }
 
 async fn get_screen_framebuffer(context: &mut dyn WIPICContext, a0: WIPICWord) -> WIPICResult<WIPICMemoryId> {
@@ -38,7 +42,8 @@ async fn get_screen_framebuffer(context: &mut dyn WIPICContext, a0: WIPICWord) -
         (screen.width(), screen.height())
     };
 
-    let framebuffer = WIPICFramebuffer::new(context, width, height, FRAMEBUFFER_DEPTH)?;
+    let bpp = framebuffer_bpp(context);
+    let framebuffer = WIPICFramebuffer::new(context, width, height, bpp)?;
 
     let memory = context.alloc(size_of::<WIPICFramebuffer>() as WIPICWord)?;
     write_generic(context, context.data_ptr(memory)?, framebuffer)?;
@@ -229,18 +234,22 @@ async fn get_display_info(context: &mut dyn WIPICContext, reserved: WIPICWord, o
     assert_eq!(reserved, 0);
 
     let mut platform = context.system().platform();
+    let depth = platform.profile().color_depth;
+    let bpp = platform.profile().framebuffer_bpp();
     let screen = platform.screen();
 
+    let (red_mask, green_mask, blue_mask) = if bpp == 16 { (0xf800, 0x7e0, 0x1f) } else { (0xff0000, 0xff00, 0xff) };
+
     let info = WIPICDisplayInfo {
-        bpp: FRAMEBUFFER_DEPTH,
-        depth: 16,
+        bpp,
+        depth,
         width: screen.width(),
         height: screen.height(),
-        bpl: 2 * screen.width(),
+        bpl: screen.width() * bpp / 8,
         color_type: 1, // 1==MC_GRP_DIRECT_COLOR_TYPE
-        red_mask: 0xf800,
-        green_mask: 0x7e0,
-        blue_mask: 0x1f,
+        red_mask,
+        green_mask,
+        blue_mask,
     };
     drop(platform);
 
@@ -248,6 +257,19 @@ async fn get_display_info(context: &mut dyn WIPICContext, reserved: WIPICWord, o
     Ok(1)
 }
 
+async fn post_event(context: &mut dyn WIPICContext, r#type: i32, param1: i32, param2: i32) -> WIPICResult<()> {
+    tracing::debug!("MC_grpPostEvent({}, {}, {})", r#type, param1, param2);
+
+    let event = WIPICEvent::from_raw(r#type, param1, param2).and_then(|x| x.to_event());
+    if let Some(x) = event {
+        context.system().push_event(x);
+    } else {
+        tracing::warn!("Unsupported event {} {} {}", r#type, param1, param2);
+    }
+
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn copy_area(
     context: &mut dyn WIPICContext,
@@ -281,7 +303,8 @@ async fn copy_area(
 async fn create_offscreen_framebuffer(context: &mut dyn WIPICContext, w: i32, h: i32) -> WIPICResult<WIPICMemoryId> {
     tracing::debug!("MC_grpCreateOffScreenFrameBuffer({}, {})", w, h);
 
-    let framebuffer = WIPICFramebuffer::new(context, w as _, h as _, FRAMEBUFFER_DEPTH)?;
+    let bpp = framebuffer_bpp(context);
+    let framebuffer = WIPICFramebuffer::new(context, w as _, h as _, bpp)?;
 
     let memory = context.alloc(size_of::<WIPICFramebuffer>() as WIPICWord)?;
     write_generic(context, context.data_ptr(memory)?, framebuffer)?;
@@ -330,29 +353,29 @@ pub fn get_graphics_method_table() -> Vec<WIPICMethodBody> {
     vec![
         gen_stub(0, "MC_grpGetImageProperty"),
         gen_stub(1, "MC_grpGetImageFrameBuffer"),
-        get_screen_framebuffer.into_body(),
+        get_screen_framebuffer.into_named_body("MC_grpGetScreenFrameBuffer"),
         gen_stub(3, "MC_grpDestroyOffScreenFrameBuffer"),
-        create_offscreen_framebuffer.into_body(),
-        init_context.into_body(),
-        set_context.into_body(),
+        create_offscreen_framebuffer.into_named_body("MC_grpCreateOffScreenFrameBuffer"),
+        init_context.into_named_body("MC_grpInitContext"),
+        set_context.into_named_body("MC_grpSetContext"),
         gen_stub(7, "MC_grpGetContext"),
-        put_pixel.into_body(),
+        put_pixel.into_named_body("MC_grpPutPixel"),
         gen_stub(9, "MC_grpDrawLine"),
         gen_stub(10, "MC_grpDrawRect"),
-        fill_rect.into_body(),
-        copy_frame_buffer.into_body(),
-        draw_image.into_body(),
-        copy_area.into_body(),
+        fill_rect.into_named_body("MC_grpFillRect"),
+        copy_frame_buffer.into_named_body("MC_grpCopyFrameBuffer"),
+        draw_image.into_named_body("MC_grpDrawImage"),
+        copy_area.into_named_body("MC_grpCopyArea"),
         gen_stub(15, "MC_grpDrawArc"),
         gen_stub(16, "MC_grpFillArc"),
         gen_stub(17, "MC_grpDrawString"),
         gen_stub(18, "MC_grpDrawUnicodeString"),
         gen_stub(19, "MC_grpGetRGBPixels"),
         gen_stub(20, "MC_grpSetRGBPixels"),
-        flush.into_body(),
-        get_pixel_from_rgb.into_body(),
+        flush.into_named_body("MC_grpFlushLcd"),
+        get_pixel_from_rgb.into_named_body("MC_grpGetPixelFromRGB"),
         gen_stub(23, "MC_grpGetRGBFromPixel"),
-        get_display_info.into_body(),
+        get_display_info.into_named_body("MC_grpGetDisplayInfo"),
         gen_stub(25, "MC_grpRepaint"),
         gen_stub(26, "MC_grpGetFont"),
         gen_stub(27, "MC_grpGetFontHeight"),
@@ -360,11 +383,11 @@ pub fn get_graphics_method_table() -> Vec<WIPICMethodBody> {
         gen_stub(29, "MC_grpGetFontDescent"),
         gen_stub(30, "MC_grpGetStringWidth"),
         gen_stub(31, "MC_grpGetUnicodeStringWidth"),
-        create_image.into_body(),
+        create_image.into_named_body("MC_grpCreateImage"),
         gen_stub(33, "MC_grpDestroyImage"),
         gen_stub(34, "MC_grpDecodeNextImage"),
         gen_stub(35, "MC_grpEncodeImage"),
-        gen_stub(36, "MC_grpPostEvent"),
+        post_event.into_named_body("MC_grpPostEvent"),
         gen_stub(37, "MC_imHandleInput"),
         gen_stub(38, "MC_imSetCurrentMode"),
         gen_stub(39, "MC_imGetCurrentMode"),
@@ -388,25 +411,25 @@ pub fn get_graphics_method_table() -> Vec<WIPICMethodBody> {
         gen_stub(57, "OEMC_grpGetFontHelpLine"),
         gen_stub(58, "OEMC_grpEncodeImageEx"),
         gen_stub(59, "OEMC_grpGetImageInfo"),
-        gen_stub(60, ""),
-        gen_stub(61, ""),
-        gen_stub(62, ""),
-        gen_stub(63, ""),
-        gen_stub(64, ""),
-        gen_stub(65, ""),
-        gen_stub(66, ""),
-        gen_stub(67, ""),
-        gen_stub(68, ""),
-        gen_stub(69, ""),
-        gen_stub(70, ""),
-        gen_stub(71, ""),
-        gen_stub(72, ""),
-        gen_stub(73, ""),
-        gen_stub(74, ""),
-        gen_stub(75, ""),
-        gen_stub(76, ""),
-        gen_stub(77, ""),
-        gen_stub(78, ""),
-        gen_stub(79, ""),
+        gen_stub(60, "MC_grpUnk60"),
+        gen_stub(61, "MC_grpUnk61"),
+        gen_stub(62, "MC_grpUnk62"),
+        gen_stub(63, "MC_grpUnk63"),
+        gen_stub(64, "MC_grpUnk64"),
+        gen_stub(65, "MC_grpUnk65"),
+        gen_stub(66, "MC_grpUnk66"),
+        gen_stub(67, "MC_grpUnk67"),
+        gen_stub(68, "MC_grpUnk68"),
+        gen_stub(69, "MC_grpUnk69"),
+        gen_stub(70, "MC_grpUnk70"),
+        gen_stub(71, "MC_grpUnk71"),
+        gen_stub(72, "MC_grpUnk72"),
+        gen_stub(73, "MC_grpUnk73"),
+        gen_stub(74, "MC_grpUnk74"),
+        gen_stub(75, "MC_grpUnk75"),
+        gen_stub(76, "MC_grpUnk76"),
+        gen_stub(77, "MC_grpUnk77"),
+        gen_stub(78, "MC_grpUnk78"),
+        gen_stub(79, "MC_grpUnk79"),
     ]
 }
@@ -10,9 +10,10 @@ use core::{cell::Ref, iter};
 use bytemuck::{Pod, Zeroable};
 
 use wie_backend::Instant;
-use wie_common::util::{read_generic, read_null_terminated_string, write_generic, write_null_terminated_string};
+use wie_common::util::{read_generic, read_null_terminated_bytes, write_generic};
 
 use crate::{
+    api::{M_E_ERROR, M_E_SHORTBUF},
     context::{WIPICContext, WIPICError, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord},
     method::{MethodBody, MethodImpl},
 };
@@ -33,7 +34,7 @@ pub struct WIPICTimer {
 fn gen_stub(id: WIPICWord, name: &'static str) -> WIPICMethodBody {
     let body = move |_: &mut dyn WIPICContext| async move { Err::<(), _>(anyhow::anyhow!("Unimplemented kernel{}: {}", id, name)) };
 
-    body.into_body()
+    body.into_named_body(name)
 }
 
 async fn current_time(context: &mut dyn WIPICContext) -> WIPICResult<WIPICWord> {
@@ -42,10 +43,28 @@ async fn current_time(context: &mut dyn WIPICContext) -> WIPICResult<WIPICWord>
     Ok(context.system().platform().now().raw() as WIPICWord)
 }
 
-async fn get_system_property(_context: &mut dyn WIPICContext, id: String, p_out: WIPICWord, buf_size: WIPICWord) -> WIPICResult<i32> {
-    tracing::warn!("stub MC_knlGetSystemProperty({}, {:#x}, {})", id, p_out, buf_size);
+async fn get_system_property(context: &mut dyn WIPICContext, id: String, p_out: WIPICWord, buf_size: WIPICWord) -> WIPICResult<i32> {
+    tracing::debug!("MC_knlGetSystemProperty({}, {:#x}, {})", id, p_out, buf_size);
 
-    Ok(0)
+    let value = context.system().platform().profile().property(&id);
+    let value = if let Some(x) = value {
+        x
+    } else {
+        tracing::warn!("Unknown system property {}", id);
+
+        return Ok(M_E_ERROR);
+    };
+
+    let mut bytes = context.system().encode_str(&value);
+    bytes.push(0);
+
+    if bytes.len() > buf_size as usize {
+        return Ok(M_E_SHORTBUF);
+    }
+
+    context.write_bytes(p_out, &bytes)?;
+
+    Ok((bytes.len() - 1) as _)
 }
 
 async fn def_timer(context: &mut dyn WIPICContext, ptr_timer: WIPICWord, fn_callback: WIPICWord) -> WIPICResult<()> {
@@ -75,35 +94,104 @@ async fn set_timer(
 ) -> WIPICResult<()> {
     tracing::debug!("MC_knlSetTimer({:#x}, {:#x}, {:#x}, {:#x})", ptr_timer, timeout_low, timeout_high, param);
 
+    start_timer(context, ptr_timer, ((timeout_high as u64) << 32) | (timeout_low as u64), param, false)
+}
+
+// same as MC_knlSetTimer, but nonzero `repeat` rearms the timer with the same timeout until it is unset
+async fn set_timer_ex(
+    context: &mut dyn WIPICContext,
+    ptr_timer: WIPICWord,
+    timeout_low: WIPICWord,
+    timeout_high: WIPICWord,
+    param: WIPICWord,
+    repeat: WIPICWord,
+) -> WIPICResult<()> {
+    tracing::debug!(
+        "OEMC_knlSetTimerEx({:#x}, {:#x}, {:#x}, {:#x}, {})",
+        ptr_timer,
+        timeout_low,
+        timeout_high,
+        param,
+        repeat
+    );
+
+    start_timer(
+        context,
+        ptr_timer,
+        ((timeout_high as u64) << 32) | (timeout_low as u64),
+        param,
+        repeat != 0,
+    )
+}
+
+async fn unset_timer(context: &mut dyn WIPICContext, ptr_timer: WIPICWord) -> WIPICResult<()> {
+    tracing::debug!("MC_knlUnsetTimer({:#x})", ptr_timer);
+
+    let handle = context.timers().remove(&ptr_timer);
+    if let Some(handle) = handle {
+        handle.abort();
+    }
+
+    Ok(())
+}
+
+// setting a timer which is already set reschedules it
+fn start_timer(context: &mut dyn WIPICContext, ptr_timer: WIPICWord, timeout: u64, param: WIPICWord, repeat: bool) -> WIPICResult<()> {
     struct TimerCallback {
         ptr_timer: u32,
         wakeup: Instant,
+        timeout: u64,
         param: WIPICWord,
+        repeat: bool,
     }
 
     #[async_trait::async_trait(?Send)]
     impl MethodBody<WIPICError> for TimerCallback {
         #[tracing::instrument(name = "timer", skip_all)]
         async fn call(&self, context: &mut dyn WIPICContext, _: Box<[WIPICWord]>) -> Result<WIPICWord, WIPICError> {
-            let timer: WIPICTimer = read_generic(context, self.ptr_timer)?;
+            let mut wakeup = self.wakeup;
+
+            loop {
+                context.system().sleep(wakeup).await;
+
+                if !self.repeat {
+                    // removed before the callback, as the callback may set the timer again
+                    context.timers().remove(&self.ptr_timer);
+                }
 
-            context.system().sleep(self.wakeup).await;
+                let timer: WIPICTimer = read_generic(context, self.ptr_timer)?;
+                context.call_function(timer.fn_callback, &[self.ptr_timer, self.param]).await?;
 
-            context.call_function(timer.fn_callback, &[self.ptr_timer, self.param]).await?;
+                if !self.repeat {
+                    return Ok(0);
+                }
 
-            Ok(0)
+                // a zero timeout would never let other tasks run
+                wakeup = wakeup + self.timeout.max(1);
+            }
         }
     }
 
-    let wakeup = context.system().platform().now() + (((timeout_high as u64) << 32) | (timeout_low as u64)) as _;
-
-    context.spawn(Box::new(TimerCallback { ptr_timer, wakeup, param }))?;
+    let handle = context.timers().remove(&ptr_timer);
+    if let Some(handle) = handle {
+        handle.abort();
+    }
 
-    Ok(())
-}
+    let wakeup = context.system().platform().now() + timeout;
+    let callback = TimerCallback {
+        ptr_timer,
+        wakeup,
+        timeout,
+        param,
+        repeat,
+    };
+    let handle = context.spawn(&format!("timer {:#x}", ptr_timer), Box::new(callback))?;
+    context.timers().insert(ptr_timer, handle);
 
-async fn unset_timer(_: &mut dyn WIPICContext, a0: WIPICWord) -> WIPICResult<()> {
-    tracing::warn!("stub MC_knlUnsetTimer({:#x})", a0);
+    let mut timer: WIPICTimer = read_generic(context, ptr_timer)?;
+    timer.time = wakeup.raw();
+    timer.param = param;
+    write_generic(context, ptr_timer, timer)?;
 
     Ok(())
 }
@@ -183,21 +271,42 @@ async fn sprintk(context: &mut dyn WIPICContext, dest: WIPICWord, format: String
 
     let result = sprintf(context, &format, &[a0, a1, a2, a3])?;
 
-    write_null_terminated_string(context, dest, &result)?;
+    let mut bytes = context.system().encode_str(&result);
+    let length = bytes.len();
+    bytes.push(0);
 
-    Ok(result.len() as _)
+    context.write_bytes(dest, &bytes)?;
+
+    Ok(length as _)
+}
+
+async fn exit(context: &mut dyn WIPICContext, code: i32) -> WIPICResult<()> {
+    tracing::debug!("MC_knlExit({})", code);
+
+    context.system().exit();
+
+    Ok(())
+}
+
+async fn program_stop(context: &mut dyn WIPICContext, program_id: i32) -> WIPICResult<()> {
+    tracing::debug!("MC_knlProgramStop({})", program_id);
+
+    // we only run one program
+    context.system().exit();
+
+    Ok(())
 }
 
-async fn get_total_memory(_context: &mut dyn WIPICContext) -> WIPICResult<i32> {
-    tracing::warn!("stub MC_knlGetTotalMemory()");
+async fn get_total_memory(context: &mut dyn WIPICContext) -> WIPICResult<i32> {
+    tracing::debug!("MC_knlGetTotalMemory()");
 
-    Ok(0x100000) // TODO hardcoded
+    Ok(context.total_memory()? as _)
 }
 
-async fn get_free_memory(_context: &mut dyn WIPICContext) -> WIPICResult<i32> {
-    tracing::warn!("stub MC_knlGetFreeMemory()");
+async fn get_free_memory(context: &mut dyn WIPICContext) -> WIPICResult<i32> {
+    tracing::debug!("MC_knlGetFreeMemory()");
 
-    Ok(0x100000) // TODO hardcoded
+    Ok(context.free_memory()? as _)
 }
 
 fn sprintf(context: &mut dyn WIPICContext, format: &str, args: &[u32]) -> WIPICResult<String> {
@@ -213,9 +322,9 @@ fn sprintf(context: &mut dyn WIPICContext, format: &str, args: &[u32]) -> WIPICR
                 'd' => result += &arg_iter.next().unwrap().to_string(),
                 's' => {
                     let ptr = arg_iter.next().unwrap();
-                    let str = read_null_terminated_string(context, *ptr)?;
+                    let bytes = read_null_terminated_bytes(context, *ptr)?;
 
-                    result += &str;
+                    result += &context.system().decode_str(&bytes);
                 }
                 'c' => {
                     let byte = arg_iter.next().unwrap();
@@ -246,16 +355,16 @@ where
     M: MethodImpl<F, R, WIPICError, P>,
 {
     vec![
-        printk.into_body(),
-        sprintk.into_body(),
+        printk.into_named_body("MC_knlPrintk"),
+        sprintk.into_named_body("MC_knlSprintk"),
         gen_stub(2, "MC_knlGetExecNames"),
         gen_stub(3, "MC_knlExecute"),
         gen_stub(4, "MC_knlMExecute"),
         gen_stub(5, "MC_knlLoad"),
         gen_stub(6, "MC_knlMLoad"),
-        gen_stub(7, "MC_knlExit"),
-        gen_stub(8, "MC_knlProgramStop"),
-        get_cur_program_id.into_body(),
+        exit.into_named_body("MC_knlExit"),
+        program_stop.into_named_body("MC_knlProgramStop"),
+        get_cur_program_id.into_named_body("MC_knlGetCurProgramID"),
         gen_stub(10, "MC_knlGetParentProgramID"),
         gen_stub(11, "MC_knlGetAppManagerID"),
         gen_stub(12, "MC_knlGetProgramInfo"),
@@ -266,20 +375,20 @@ where
         gen_stub(17, "MC_knlGetSharedBuf"),
         gen_stub(18, "MC_knlGetSharedBufSize"),
         gen_stub(19, "MC_knlResizeSharedBuf"),
-        alloc.into_body(),
-        calloc.into_body(),
-        free.into_body(),
-        get_total_memory.into_body(),
-        get_free_memory.into_body(),
-        def_timer.into_body(),
-        set_timer.into_body(),
-        unset_timer.into_body(),
-        current_time.into_body(),
-        get_system_property.into_body(),
+        alloc.into_named_body("MC_knlAlloc"),
+        calloc.into_named_body("MC_knlCalloc"),
+        free.into_named_body("MC_knlFree"),
+        get_total_memory.into_named_body("MC_knlGetTotalMemory"),
+        get_free_memory.into_named_body("MC_knlGetFreeMemory"),
+        def_timer.into_named_body("MC_knlDefTimer"),
+        set_timer.into_named_body("MC_knlSetTimer"),
+        unset_timer.into_named_body("MC_knlUnsetTimer"),
+        current_time.into_named_body("MC_knlCurrentTime"),
+        get_system_property.into_named_body("MC_knlGetSystemProperty"),
         gen_stub(30, "MC_knlSetSystemProperty"),
-        get_resource_id.into_body(),
-        get_resource.into_body(),
-        reserved1.into_body(),
+        get_resource_id.into_named_body("MC_knlGetResourceID"),
+        get_resource.into_named_body("MC_knlGetResource"),
+        reserved1.into_named_body("MC_knlReserved1"),
         gen_stub(34, "MC_knlReserved2"),
         gen_stub(35, "MC_knlReserved3"),
         gen_stub(36, "MC_knlReserved4"),
@@ -291,7 +400,7 @@ where
         gen_stub(42, "MC_knlReserved10"),
         gen_stub(43, "MC_knlReserved11"),
         gen_stub(44, "OEMC_knlSendMessage"),
-        gen_stub(45, "OEMC_knlSetTimerEx"),
+        set_timer_ex.into_named_body("OEMC_knlSetTimerEx"),
         gen_stub(46, "OEMC_knlGetSystemState"),
         gen_stub(47, "OEMC_knlCreateSystemProgressBar"),
         gen_stub(48, "OEMC_knlSetSystemProgressBar"),
@@ -70,7 +70,7 @@ struct MdaClip {
 fn gen_stub(id: WIPICWord, name: &'static str) -> WIPICMethodBody {
     let body = move |_: &mut dyn WIPICContext| async move { Err::<(), _>(anyhow::anyhow!("Unimplemented media{}: {}", id, name)) };
 
-    body.into_body()
+    body.into_named_body(name)
 }
 
 async fn clip_create(context: &mut dyn WIPICContext, r#type: String, buf_size: WIPICWord, callback: WIPICWord) -> WIPICResult<WIPICWord> {
@@ -159,33 +159,33 @@ async fn record(_context: &mut dyn WIPICContext, clip: WIPICWord) -> WIPICResult
 
 pub fn get_media_method_table() -> Vec<WIPICMethodBody> {
     vec![
-        clip_create.into_body(),
+        clip_create.into_named_body("MC_mdaClipCreate"),
         gen_stub(1, "MC_mdaClipFree"),
         gen_stub(2, "MC_mdaSetWaterMark"),
-        clip_get_type.into_body(),
-        clip_put_data.into_body(),
+        clip_get_type.into_named_body("MC_mdaClipGetType"),
+        clip_put_data.into_named_body("MC_mdaClipPutData"),
         gen_stub(5, "MC_mdaClipPutDataByFile"),
         gen_stub(6, "MC_mdaClipPutToneData"),
         gen_stub(7, "MC_mdaClipPutFreqToneData"),
-        clip_get_data.into_body(),
+        clip_get_data.into_named_body("MC_mdaClipGetData"),
         gen_stub(9, "MC_mdaClipAvailableDataSize"),
         gen_stub(10, "MC_mdaClipClearData"),
-        clip_set_position.into_body(),
+        clip_set_position.into_named_body("MC_mdaClipSetPosition"),
         gen_stub(12, "MC_mdaClipGetVolume"),
         gen_stub(13, "MC_mdaClipSetVolume"),
-        play.into_body(),
-        pause.into_body(),
-        resume.into_body(),
-        stop.into_body(),
-        record.into_body(),
+        play.into_named_body("MC_mdaPlay"),
+        pause.into_named_body("MC_mdaPause"),
+        resume.into_named_body("MC_mdaResume"),
+        stop.into_named_body("MC_mdaStop"),
+        record.into_named_body("MC_mdaRecord"),
         gen_stub(19, "MC_mdaGetVolume"),
         gen_stub(20, "MC_mdaSetVolume"),
         gen_stub(21, "MC_mdaVibrator"),
         gen_stub(22, "MC_mdaReserved1"),
         gen_stub(23, "MC_mdaReserved2"),
         gen_stub(24, "MC_mdaSetMuteState"),
-        get_mute_state.into_body(),
-        clip_get_info.into_body(),
+        get_mute_state.into_named_body("MC_mdaGetMuteState"),
+        clip_get_info.into_named_body("OEMC_mdaClipGetInfo"),
         // gen_stub(27, "OEMC_mdaClipControl"),
         // gen_stub(28, "OEMC_mdaSetClipArea"),
         // gen_stub(29, "OEMC_mdaReleaseClipArea"),
@@ -8,7 +8,7 @@ use crate::{
 fn gen_stub(id: WIPICWord, name: &'static str) -> WIPICMethodBody {
     let body = move |_: &mut dyn WIPICContext| async move { Err::<(), _>(anyhow::anyhow!("Unimplemented misc{}: {}", id, name)) };
 
-    body.into_body()
+    body.into_named_body(name)
 }
 
 async fn back_light(
@@ -25,7 +25,7 @@ async fn back_light(
 
 pub fn get_misc_method_table() -> Vec<WIPICMethodBody> {
     vec![
-        back_light.into_body(),
+        back_light.into_named_body("MC_miscBackLight"),
         gen_stub(1, "MC_miscSetLed"),
         gen_stub(2, "MC_miscGetLed"),
         gen_stub(3, "MC_miscGetLedCount"),
@@ -8,7 +8,7 @@ use crate::{
 fn gen_stub(id: WIPICWord, name: &'static str) -> WIPICMethodBody {
     let body = move |_: &mut dyn WIPICContext| async move { Err::<(), _>(anyhow::anyhow!("Unimplemented net{}: {}", id, name)) };
 
-    body.into_body()
+    body.into_named_body(name)
 }
 
 async fn connect(_context: &mut dyn WIPICContext, cb: WIPICWord, param: WIPICWord) -> WIPICResult<i32> {
@@ -31,13 +31,13 @@ async fn socket_close(_context: &mut dyn WIPICContext, fd: i32) -> WIPICResult<i
 
 pub fn get_net_method_table() -> Vec<WIPICMethodBody> {
     vec![
-        connect.into_body(),
-        close.into_body(),
+        connect.into_named_body("MC_netConnect"),
+        close.into_named_body("MC_netClose"),
         gen_stub(2, "MC_netSocket"),
         gen_stub(3, "MC_netSocketConnect"),
         gen_stub(4, "MC_netSocketWrite"),
         gen_stub(5, "MC_netSocketRead"),
-        socket_close.into_body(),
+        socket_close.into_named_body("MC_netSocketClose"),
         gen_stub(7, "MC_netSocketBind"),
         gen_stub(8, "MC_netGetMaxPacketLength"),
         gen_stub(9, "MC_netSocketSendTo"),
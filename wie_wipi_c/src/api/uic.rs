@@ -9,7 +9,7 @@ use crate::{
 fn gen_stub(id: WIPICWord, name: &'static str) -> WIPICMethodBody {
     let body = move |_: &mut dyn WIPICContext| async move { Err::<(), _>(anyhow::anyhow!("Unimplemented uic{}: {}", id, name)) };
 
-    body.into_body()
+    body.into_named_body(name)
 }
 
 async fn create_application_context(_context: &mut dyn WIPICContext) -> WIPICResult<WIPICMemoryId> {
@@ -20,7 +20,7 @@ async fn create_application_context(_context: &mut dyn WIPICContext) -> WIPICRes
 
 pub fn get_uic_method_table() -> Vec<WIPICMethodBody> {
     vec![
-        create_application_context.into_body(),
+        create_application_context.into_named_body("MC_uicCreateApplicationContext"),
         gen_stub(1, "MC_uicGetClass"),
         gen_stub(2, "MC_uicCreate"),
         gen_stub(3, "MC_uicDestroy"),
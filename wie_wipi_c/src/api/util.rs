@@ -8,7 +8,7 @@ use crate::{
 fn gen_stub(id: WIPICWord, name: &'static str) -> WIPICMethodBody {
     let body = move |_: &mut dyn WIPICContext| async move { Err::<(), _>(anyhow::anyhow!("Unimplemented util{}: {}", id, name)) };
 
-    body.into_body()
+    body.into_named_body(name)
 }
 
 async fn htons(_context: &mut dyn WIPICContext, val: WIPICWord) -> WIPICResult<WIPICWord> {
@@ -20,7 +20,7 @@ async fn htons(_context: &mut dyn WIPICContext, val: WIPICWord) -> WIPICResult<W
 pub fn get_util_method_table() -> Vec<WIPICMethodBody> {
     vec![
         gen_stub(0, "MC_utilHtonl"),
-        htons.into_body(),
+        htons.into_named_body("MC_utilHtons"),
         gen_stub(2, "MC_utilNtohl"),
         gen_stub(3, "MC_utilNtohs"),
         gen_stub(4, "MC_utilInetAddrInt"),
@@ -1,9 +1,10 @@
-use alloc::{boxed::Box, string::String};
+use alloc::{boxed::Box, collections::BTreeMap, string::String};
+use core::cell::RefMut;
 
 use bytemuck::{Pod, Zeroable};
 
-use wie_backend::SystemHandle;
-use wie_common::util::{read_null_terminated_string, ByteRead, ByteWrite};
+use wie_backend::{AbortHandle, Database, SystemHandle};
+use wie_common::util::{read_null_terminated_bytes, ByteRead, ByteWrite};
 
 use crate::method::{MethodBody, TypeConverter};
 
@@ -22,11 +23,19 @@ pub trait WIPICContext: ByteRead + ByteWrite {
     fn alloc(&mut self, size: WIPICWord) -> WIPICResult<WIPICMemoryId>;
     fn free(&mut self, memory: WIPICMemoryId) -> WIPICResult<()>;
     fn free_raw(&mut self, address: WIPICWord) -> WIPICResult<()>;
+    /// Resizes memory from `alloc_raw`, the returned address differs if it had to be moved
+    fn realloc_raw(&mut self, address: WIPICWord, size: WIPICWord) -> WIPICResult<WIPICWord>;
     fn data_ptr(&self, memory: WIPICMemoryId) -> WIPICResult<WIPICWord>;
+    fn total_memory(&self) -> WIPICResult<WIPICWord>;
+    fn free_memory(&self) -> WIPICResult<WIPICWord>;
     fn register_function(&mut self, method: WIPICMethodBody) -> WIPICResult<WIPICWord>;
     async fn call_function(&mut self, address: WIPICWord, args: &[WIPICWord]) -> WIPICResult<WIPICWord>;
     fn system(&mut self) -> &mut SystemHandle;
-    fn spawn(&mut self, callback: WIPICMethodBody) -> WIPICResult<()>;
+    fn spawn(&mut self, name: &str, callback: WIPICMethodBody) -> WIPICResult<AbortHandle>;
+    /// Tasks of the timers which are set, keyed by the address of their MC_TIMER
+    fn timers(&mut self) -> RefMut<'_, BTreeMap<WIPICWord, AbortHandle>>;
+    /// Databases which are open, keyed by their handle
+    fn databases(&mut self) -> RefMut<'_, BTreeMap<WIPICWord, Box<dyn Database>>>;
 }
 
 impl TypeConverter<WIPICWord> for WIPICWord {
@@ -69,7 +78,9 @@ impl TypeConverter<()> for () {
 
 impl TypeConverter<String> for String {
     fn to_rust(context: &mut dyn WIPICContext, raw: WIPICWord) -> String {
-        read_null_terminated_string(context, raw).unwrap()
+        let bytes = read_null_terminated_bytes(context, raw).unwrap();
+
+        context.system().decode_str(&bytes)
     }
 
     fn from_rust(_: &mut dyn WIPICContext, _: String) -> WIPICWord {
@@ -0,0 +1,72 @@
+use wie_common::{Event, KeyCode};
+
+// MV_*_EVENT from the WIPI C API headers, passed to the clet's event handler as `type`
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WIPICEventType {
+    KeyPress = 1,       // MV_KEY_PRESS_EVENT
+    KeyRelease = 2,     // MV_KEY_RELEASE_EVENT
+    KeyRepeat = 3,      // MV_KEY_REPEAT_EVENT
+    PointerPress = 4,   // MV_POINTER_PRESS_EVENT
+    PointerRelease = 5, // MV_POINTER_RELEASE_EVENT
+    PointerMove = 6,    // MV_POINTER_MOVE_EVENT
+}
+
+impl WIPICEventType {
+    fn from_raw(raw: i32) -> Option<Self> {
+        Some(match raw {
+            1 => Self::KeyPress,
+            2 => Self::KeyRelease,
+            3 => Self::KeyRepeat,
+            4 => Self::PointerPress,
+            5 => Self::PointerRelease,
+            6 => Self::PointerMove,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WIPICEvent {
+    pub r#type: WIPICEventType,
+    pub param1: i32,
+    pub param2: i32,
+}
+
+impl WIPICEvent {
+    // redraw and lifecycle events are not events in wipi c, the platform calls clet's paint, pause, resume and destroy directly
+    pub fn from_event(event: &Event) -> Option<Self> {
+        let (r#type, param1, param2) = match *event {
+            Event::Keydown(x) => (WIPICEventType::KeyPress, x.raw(), 0),
+            Event::Keyup(x) => (WIPICEventType::KeyRelease, x.raw(), 0),
+            Event::Keyrepeat(x) => (WIPICEventType::KeyRepeat, x.raw(), 0),
+            Event::Pointerdown(x, y) => (WIPICEventType::PointerPress, x, y),
+            Event::Pointerdrag(x, y) => (WIPICEventType::PointerMove, x, y),
+            Event::Pointerup(x, y) => (WIPICEventType::PointerRelease, x, y),
+            Event::Redraw | Event::Pause | Event::Resume | Event::Exit => return None,
+        };
+
+        Some(Self { r#type, param1, param2 })
+    }
+
+    pub fn from_raw(r#type: i32, param1: i32, param2: i32) -> Option<Self> {
+        Some(Self {
+            r#type: WIPICEventType::from_raw(r#type)?,
+            param1,
+            param2,
+        })
+    }
+
+    pub fn to_event(self) -> Option<Event> {
+        let key = || KeyCode::from_raw(self.param1);
+
+        Some(match self.r#type {
+            WIPICEventType::KeyPress => Event::Keydown(key()?),
+            WIPICEventType::KeyRelease => Event::Keyup(key()?),
+            WIPICEventType::KeyRepeat => Event::Keyrepeat(key()?),
+            WIPICEventType::PointerPress => Event::Pointerdown(self.param1, self.param2),
+            WIPICEventType::PointerMove => Event::Pointerdrag(self.param1, self.param2),
+            WIPICEventType::PointerRelease => Event::Pointerup(self.param1, self.param2),
+        })
+    }
+}
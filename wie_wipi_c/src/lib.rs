@@ -3,6 +3,10 @@ extern crate alloc;
 
 pub mod api;
 mod context;
+mod event;
 mod method;
 
-pub use self::context::{WIPICContext, WIPICError, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord};
+pub use self::{
+    context::{WIPICContext, WIPICError, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord},
+    event::{WIPICEvent, WIPICEventType},
+};
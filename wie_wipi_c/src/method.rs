@@ -37,6 +37,10 @@ macro_rules! __impl_method_body {
 
                 Ok(R::from_rust(context, result))
             }
+
+            fn name(&self) -> &'static str {
+                core::any::type_name::<F>()
+            }
         }
     };
 }
@@ -69,6 +73,10 @@ macro_rules! methods {
         #[async_trait::async_trait(?Send)]
         pub trait MethodBody<E> {
             async fn call(&self, context: &mut dyn $context, args: Box<[$raw_type]>) -> Result<$raw_type, E>;
+
+            fn name(&self) -> &'static str {
+                core::any::type_name::<Self>()
+            }
         }
 
         trait FnHelper<'a, E, R, P> {
@@ -78,6 +86,22 @@ macro_rules! methods {
 
         struct MethodHolder<F, R, P>(pub F, PhantomData<(R, P)>);
 
+        struct NamedMethodBody<E> {
+            name: &'static str,
+            body: Box<dyn MethodBody<E>>,
+        }
+
+        #[async_trait::async_trait(?Send)]
+        impl<E> MethodBody<E> for NamedMethodBody<E> {
+            async fn call(&self, context: &mut dyn $context, args: Box<[$raw_type]>) -> Result<$raw_type, E> {
+                self.body.call(context, args).await
+            }
+
+            fn name(&self) -> &'static str {
+                self.name
+            }
+        }
+
         pub trait TypeConverter<T> {
             fn to_rust(context: &mut dyn $context, raw: $raw_type) -> T;
             fn from_rust(context: &mut dyn $context, rust: T) -> $raw_type;
@@ -85,6 +109,18 @@ macro_rules! methods {
 
         pub trait MethodImpl<F, R, E, P> {
             fn into_body(self) -> Box<dyn MethodBody<E>>;
+
+            /// Like `into_body`, but the body reports `name` (e.g. `MC_grpFlushLcd`) instead of the rust function name
+            fn into_named_body(self, name: &'static str) -> Box<dyn MethodBody<E>>
+            where
+                Self: Sized,
+                E: 'static,
+            {
+                Box::new(NamedMethodBody {
+                    name,
+                    body: self.into_body(),
+                })
+            }
         }
 
         __generate!($context, $raw_type,);
@@ -1,26 +1,77 @@
-use wie_backend::SystemHandle;
+// shared by the test binaries, each of which uses a part of it
+#![allow(dead_code)]
+
+use std::{
+    cell::{Cell, RefCell, RefMut},
+    collections::BTreeMap,
+    rc::Rc,
+};
+
+use test_utils::TestPlatform;
+use wie_backend::{encoding::EUC_KR, AbortHandle, AsyncCallable, Database, System, SystemHandle};
 use wie_common::util::{ByteRead, ByteWrite};
-use wie_wipi_c::{WIPICContext, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord};
+use wie_wipi_c::{WIPICContext, WIPICError, WIPICMemoryId, WIPICMethodBody, WIPICResult, WIPICWord};
 
+// addresses returned by register_function, outside of the test memory
+const FUNCTION_BASE: WIPICWord = 0x10000000;
+const MAX_ARGS: usize = 9;
+
+// cloned into spawned tasks, which share memory and functions
+#[derive(Clone)]
 pub struct TestContext {
-    memory: [u8; 0x10000],
-    last_alloc: usize,
+    memory: Rc<RefCell<Vec<u8>>>,
+    last_alloc: Rc<Cell<usize>>,
+    functions: Rc<RefCell<Vec<Rc<WIPICMethodBody>>>>,
+    calls: Rc<RefCell<Vec<WIPICWord>>>,
+    timers: Rc<RefCell<BTreeMap<WIPICWord, AbortHandle>>>,
+    databases: Rc<RefCell<BTreeMap<WIPICWord, Box<dyn Database>>>>,
+    clock: Rc<Cell<u64>>,
+    system: SystemHandle,
+    system_owner: Rc<RefCell<System>>,
 }
 
 impl TestContext {
     pub fn new() -> Self {
+        let platform = TestPlatform::new();
+        let clock = platform.clock();
+        let system = System::new(Box::new(platform), Box::new(()), EUC_KR);
+
         Self {
-            memory: [0; 0x10000],
-            last_alloc: 0,
+            memory: Rc::new(RefCell::new(vec![0; 0x10000])),
+            last_alloc: Rc::new(Cell::new(0)),
+            functions: Rc::new(RefCell::new(Vec::new())),
+            calls: Rc::new(RefCell::new(Vec::new())),
+            timers: Rc::new(RefCell::new(BTreeMap::new())),
+            databases: Rc::new(RefCell::new(BTreeMap::new())),
+            clock,
+            system: system.handle(),
+            system_owner: Rc::new(RefCell::new(system)),
         }
     }
+
+    /// Runs spawned tasks until none of them can make progress
+    pub fn tick(&self) -> anyhow::Result<()> {
+        self.system_owner.borrow_mut().tick()
+    }
+
+    /// Moves the platform clock to `now` epoch millis and ticks
+    pub fn advance_to(&self, now: u64) -> anyhow::Result<()> {
+        self.clock.set(now);
+
+        self.tick()
+    }
+
+    /// Number of times `address` was called with `call_function`
+    pub fn call_count(&self, address: WIPICWord) -> usize {
+        self.calls.borrow().iter().filter(|&&x| x == address).count()
+    }
 }
 
 #[async_trait::async_trait(?Send)]
 impl WIPICContext for TestContext {
     fn alloc_raw(&mut self, size: WIPICWord) -> WIPICResult<WIPICWord> {
-        let address = self.last_alloc;
-        self.last_alloc += size as usize;
+        let address = self.last_alloc.get();
+        self.last_alloc.set(address + size as usize);
 
         Ok(address as WIPICWord)
     }
@@ -37,30 +88,84 @@ impl WIPICContext for TestContext {
         Ok(())
     }
 
+    fn realloc_raw(&mut self, address: WIPICWord, size: WIPICWord) -> WIPICResult<WIPICWord> {
+        // sizes aren't tracked, so always moves and copies up to the new size
+        let new_address = Self::alloc_raw(self, size)?;
+        let end = new_address.min(address + size);
+        self.memory.borrow_mut().copy_within(address as usize..end as usize, new_address as usize);
+
+        Ok(new_address)
+    }
+
     fn data_ptr(&self, memory: WIPICMemoryId) -> WIPICResult<WIPICWord> {
         Ok(memory.0)
     }
 
-    fn register_function(&mut self, _method: WIPICMethodBody) -> WIPICResult<WIPICWord> {
-        todo!()
+    fn total_memory(&self) -> WIPICResult<WIPICWord> {
+        Ok(self.memory.borrow().len() as _)
     }
 
-    async fn call_function(&mut self, _address: WIPICWord, _args: &[WIPICWord]) -> WIPICResult<WIPICWord> {
-        todo!()
+    fn free_memory(&self) -> WIPICResult<WIPICWord> {
+        Ok((self.memory.borrow().len() - self.last_alloc.get()) as _)
+    }
+
+    fn register_function(&mut self, method: WIPICMethodBody) -> WIPICResult<WIPICWord> {
+        let mut functions = self.functions.borrow_mut();
+        functions.push(Rc::new(method));
+
+        Ok(FUNCTION_BASE + (functions.len() - 1) as WIPICWord)
+    }
+
+    async fn call_function(&mut self, address: WIPICWord, args: &[WIPICWord]) -> WIPICResult<WIPICWord> {
+        self.calls.borrow_mut().push(address);
+
+        let function = self.functions.borrow().get(address.wrapping_sub(FUNCTION_BASE) as usize).cloned();
+        let function = function.ok_or_else(|| anyhow::anyhow!("Invalid function {:#x}", address))?;
+
+        // as with registers on a device, arguments the caller didn't pass can still be read
+        let mut args = args.to_vec();
+        args.resize(args.len().max(MAX_ARGS), 0);
+
+        function.call(self, args.into()).await
     }
 
     fn system(&mut self) -> &mut SystemHandle {
-        todo!()
+        &mut self.system
+    }
+
+    fn spawn(&mut self, name: &str, callback: WIPICMethodBody) -> WIPICResult<AbortHandle> {
+        struct SpawnProxy {
+            context: TestContext,
+            callback: WIPICMethodBody,
+        }
+
+        #[async_trait::async_trait(?Send)]
+        impl AsyncCallable<WIPICWord, WIPICError> for SpawnProxy {
+            async fn call(mut self) -> Result<WIPICWord, WIPICError> {
+                self.callback.call(&mut self.context, Box::new([])).await
+            }
+        }
+
+        let proxy = SpawnProxy {
+            context: self.clone(),
+            callback,
+        };
+
+        Ok(self.system.spawn_named(name, proxy).abort_handle())
+    }
+
+    fn timers(&mut self) -> RefMut<'_, BTreeMap<WIPICWord, AbortHandle>> {
+        self.timers.borrow_mut()
     }
 
-    fn spawn(&mut self, _callback: WIPICMethodBody) -> WIPICResult<()> {
-        todo!()
+    fn databases(&mut self) -> RefMut<'_, BTreeMap<WIPICWord, Box<dyn Database>>> {
+        self.databases.borrow_mut()
     }
 }
 
 impl ByteWrite for TestContext {
     fn write_bytes(&mut self, address: u32, data: &[u8]) -> anyhow::Result<()> {
-        self.memory[address as usize..(address + data.len() as u32) as usize].copy_from_slice(data);
+        self.memory.borrow_mut()[address as usize..(address + data.len() as u32) as usize].copy_from_slice(data);
 
         Ok(())
     }
@@ -68,6 +173,6 @@ impl ByteWrite for TestContext {
 
 impl ByteRead for TestContext {
     fn read_bytes(&self, address: u32, size: u32) -> anyhow::Result<Vec<u8>> {
-        Ok(self.memory[address as usize..(address + size) as usize].to_vec())
+        Ok(self.memory.borrow()[address as usize..(address + size) as usize].to_vec())
     }
 }
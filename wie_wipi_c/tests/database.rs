@@ -0,0 +1,41 @@
+use wie_common::util::{write_null_terminated_string, ByteRead, ByteWrite};
+use wie_wipi_c::{api::database::get_database_method_table, WIPICContext, WIPICWord};
+
+mod context;
+
+const M_E_NOENT: WIPICWord = -12i32 as _;
+const M_E_BADRECID: WIPICWord = -22i32 as _;
+
+#[futures_test::test]
+async fn test_database() -> anyhow::Result<()> {
+    let mut context = context::TestContext::new();
+
+    let methods = get_database_method_table();
+
+    let name = context.alloc_raw(10)?;
+    write_null_terminated_string(&mut context, name, "save")?;
+
+    // MC_dbOpenDataBase without create
+    assert_eq!(methods[0].call(&mut context, Box::new([name, 0, 0, 0])).await?, M_E_NOENT);
+
+    let db = methods[0].call(&mut context, Box::new([name, 0, 1, 0])).await?;
+    assert_eq!(context.databases().len(), 1);
+
+    let data = context.alloc_raw(4)?;
+    context.write_bytes(data, b"test")?;
+    assert_eq!(methods[2].call(&mut context, Box::new([db, data, 4])).await?, 1); // MC_db_write_record_single
+
+    let buf = context.alloc_raw(4)?;
+    assert_eq!(methods[4].call(&mut context, Box::new([db, 1, buf, 4])).await?, 0); // MC_dbSelectRecord
+    assert_eq!(context.read_bytes(buf, 4)?, b"test");
+    assert_eq!(methods[4].call(&mut context, Box::new([db, 2, buf, 4])).await?, M_E_BADRECID);
+
+    assert_eq!(methods[3].call(&mut context, Box::new([db])).await?, 0); // MC_dbCloseDataBase
+    assert!(context.databases().is_empty());
+
+    // closed handle
+    assert_eq!(methods[7].call(&mut context, Box::new([db, buf, 4])).await?, M_E_NOENT); // MC_dbListRecords
+    assert_eq!(methods[3].call(&mut context, Box::new([db])).await?, M_E_NOENT);
+
+    Ok(())
+}
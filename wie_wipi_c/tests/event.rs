@@ -0,0 +1,14 @@
+use wie_common::{Event, KeyCode};
+use wie_wipi_c::{WIPICEvent, WIPICEventType};
+
+// ktf has no handleCletEvent path; this is the conversion lgt's handleCletEvent and MC_grpPostEvent share
+#[test]
+fn test_key_repeat_event() {
+    let event = WIPICEvent::from_event(&Event::Keyrepeat(KeyCode::UP)).unwrap();
+    assert_eq!(event.r#type, WIPICEventType::KeyRepeat);
+    assert_eq!(event.r#type as i32, 3);
+    assert_eq!(event.param1, KeyCode::UP.raw());
+
+    let event = WIPICEvent::from_raw(3, KeyCode::UP.raw(), 0).unwrap().to_event();
+    assert!(matches!(event, Some(Event::Keyrepeat(KeyCode::UP))));
+}
@@ -1,3 +1,4 @@
+use wie_backend::{Instant, TaskState};
 use wie_common::util::{read_null_terminated_string, write_null_terminated_string};
 use wie_wipi_c::{api::kernel::get_kernel_method_table, WIPICContext};
 
@@ -22,3 +23,77 @@ async fn test_sprintk() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[futures_test::test]
+async fn test_timer_rearmed_by_callback() -> anyhow::Result<()> {
+    let mut context = context::TestContext::new();
+
+    let kernel_methods = get_kernel_method_table(|_: &mut dyn WIPICContext| async { anyhow::Ok(()) });
+    let mut callback_methods = get_kernel_method_table(|_: &mut dyn WIPICContext| async { anyhow::Ok(()) });
+
+    // MC_knlSetTimer as the callback, which sets the timer again with timeout `param`
+    let callback = context.register_function(callback_methods.swap_remove(26))?;
+
+    let timer = context.alloc_raw(32)?;
+    kernel_methods[25].call(&mut context, Box::new([timer, callback])).await?; // MC_knlDefTimer
+    kernel_methods[26].call(&mut context, Box::new([timer, 0, 0, 100])).await?; // MC_knlSetTimer
+    let first_task_id = context.timers()[&timer].id();
+
+    context.tick()?;
+
+    // the timer was set again by the callback, and the new task is not aborted with the one which ran the callback
+    let task_id = context.timers()[&timer].id();
+    assert_ne!(task_id, first_task_id);
+
+    let tasks = context.system().task_list();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].id, task_id);
+    assert_eq!(tasks[0].state, TaskState::Sleeping(Instant::from_epoch_millis(100)));
+
+    kernel_methods[27].call(&mut context, Box::new([timer])).await?; // MC_knlUnsetTimer
+
+    assert!(context.timers().is_empty());
+    assert!(context.system().task_list().is_empty());
+
+    Ok(())
+}
+
+#[futures_test::test]
+async fn test_repeating_timer() -> anyhow::Result<()> {
+    let mut context = context::TestContext::new();
+
+    let kernel_methods = get_kernel_method_table(|_: &mut dyn WIPICContext| async { anyhow::Ok(()) });
+    let mut callback_methods = get_kernel_method_table(|_: &mut dyn WIPICContext| async { anyhow::Ok(()) });
+
+    // MC_knlCurrentTime as the callback, which leaves the timer alone
+    let callback = context.register_function(callback_methods.swap_remove(28))?;
+
+    let timer = context.alloc_raw(32)?;
+    kernel_methods[25].call(&mut context, Box::new([timer, callback])).await?; // MC_knlDefTimer
+    kernel_methods[45].call(&mut context, Box::new([timer, 100, 0, 0, 1])).await?; // OEMC_knlSetTimerEx, repeating
+
+    context.advance_to(100)?;
+    assert_eq!(context.call_count(callback), 1);
+
+    context.advance_to(200)?;
+    context.advance_to(300)?;
+    assert_eq!(context.call_count(callback), 3);
+
+    kernel_methods[27].call(&mut context, Box::new([timer])).await?; // MC_knlUnsetTimer
+    assert!(context.system().task_list().is_empty());
+
+    context.advance_to(400)?;
+    assert_eq!(context.call_count(callback), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_method_names() {
+    let kernel_methods = get_kernel_method_table(|_: &mut dyn WIPICContext| async { anyhow::Ok(()) });
+
+    assert_eq!(kernel_methods[1].name(), "MC_knlSprintk");
+    assert_eq!(kernel_methods[2].name(), "MC_knlGetExecNames");
+    assert_eq!(kernel_methods[33].name(), "MC_knlReserved1");
+    assert_eq!(kernel_methods[45].name(), "OEMC_knlSetTimerEx");
+}
@@ -1 +1,2 @@
+pub mod javax;
 pub mod org;
@@ -0,0 +1,2 @@
+pub mod lcdui;
+pub mod midlet;
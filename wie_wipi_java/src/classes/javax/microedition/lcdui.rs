@@ -0,0 +1,5 @@
+mod canvas;
+mod display;
+mod displayable;
+
+pub use self::{canvas::Canvas, display::Display, displayable::Displayable};
@@ -0,0 +1,170 @@
+use alloc::vec;
+
+use java_class_proto::{JavaMethodProto, JavaResult};
+use java_runtime::classes::java::lang::String;
+use jvm::{runtime::JavaLangString, ClassInstanceRef, Jvm};
+
+use wie_common::KeyCode;
+
+use crate::context::{WIPIJavaClassProto, WIPIJavaContext};
+
+// Canvas game actions
+const UP: i32 = 1;
+const LEFT: i32 = 2;
+const RIGHT: i32 = 5;
+const DOWN: i32 = 6;
+const FIRE: i32 = 8;
+const GAME_A: i32 = 9;
+const GAME_B: i32 = 10;
+const GAME_C: i32 = 11;
+const GAME_D: i32 = 12;
+
+// class javax.microedition.lcdui.Canvas
+pub struct Canvas {}
+
+impl Canvas {
+    pub fn as_proto() -> WIPIJavaClassProto {
+        WIPIJavaClassProto {
+            parent_class: Some("javax/microedition/lcdui/Displayable"),
+            interfaces: vec![],
+            methods: vec![
+                JavaMethodProto::new("<init>", "()V", Self::init, Default::default()),
+                JavaMethodProto::new("getGameAction", "(I)I", Self::get_game_action, Default::default()),
+                JavaMethodProto::new("getKeyCode", "(I)I", Self::get_key_code, Default::default()),
+                JavaMethodProto::new("getKeyName", "(I)Ljava/lang/String;", Self::get_key_name, Default::default()),
+                JavaMethodProto::new("keyPressed", "(I)V", Self::key_pressed, Default::default()),
+                JavaMethodProto::new("keyReleased", "(I)V", Self::key_released, Default::default()),
+                JavaMethodProto::new("keyRepeated", "(I)V", Self::key_repeated, Default::default()),
+                JavaMethodProto::new("hasPointerEvents", "()Z", Self::has_pointer_events, Default::default()),
+                JavaMethodProto::new("hasPointerMotionEvents", "()Z", Self::has_pointer_motion_events, Default::default()),
+                JavaMethodProto::new("pointerPressed", "(II)V", Self::pointer_pressed, Default::default()),
+                JavaMethodProto::new("pointerDragged", "(II)V", Self::pointer_dragged, Default::default()),
+                JavaMethodProto::new("pointerReleased", "(II)V", Self::pointer_released, Default::default()),
+            ],
+            fields: vec![],
+        }
+    }
+
+    async fn init(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::<init>({:?})", &this);
+
+        Ok(())
+    }
+
+    async fn get_game_action(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, key_code: i32) -> JavaResult<i32> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::getGameAction({:?}, {})", &this, key_code);
+
+        const NUM1: i32 = KeyCode::NUM1.raw();
+        const NUM2: i32 = KeyCode::NUM2.raw();
+        const NUM3: i32 = KeyCode::NUM3.raw();
+        const NUM4: i32 = KeyCode::NUM4.raw();
+        const NUM5: i32 = KeyCode::NUM5.raw();
+        const NUM6: i32 = KeyCode::NUM6.raw();
+        const NUM7: i32 = KeyCode::NUM7.raw();
+        const NUM8: i32 = KeyCode::NUM8.raw();
+        const NUM9: i32 = KeyCode::NUM9.raw();
+
+        Ok(match key_code {
+            -1 | NUM2 => UP,
+            -2 | NUM8 => DOWN,
+            -3 | NUM4 => LEFT,
+            -4 | NUM6 => RIGHT,
+            -5 | NUM5 => FIRE,
+            NUM1 => GAME_A,
+            NUM3 => GAME_B,
+            NUM7 => GAME_C,
+            NUM9 => GAME_D,
+            _ => 0,
+        })
+    }
+
+    async fn get_key_code(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, game_action: i32) -> JavaResult<i32> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::getKeyCode({:?}, {})", &this, game_action);
+
+        let key_code = match game_action {
+            UP => KeyCode::UP,
+            DOWN => KeyCode::DOWN,
+            LEFT => KeyCode::LEFT,
+            RIGHT => KeyCode::RIGHT,
+            FIRE => KeyCode::OK,
+            GAME_A => KeyCode::NUM1,
+            GAME_B => KeyCode::NUM3,
+            GAME_C => KeyCode::NUM7,
+            GAME_D => KeyCode::NUM9,
+            _ => return Err(anyhow::anyhow!("java.lang.IllegalArgumentException: invalid game action {}", game_action)),
+        };
+
+        Ok(key_code.raw())
+    }
+
+    async fn get_key_name(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, key_code: i32) -> JavaResult<ClassInstanceRef<String>> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::getKeyName({:?}, {})", &this, key_code);
+
+        let name = match key_code {
+            -1 => "UP",
+            -2 => "DOWN",
+            -3 => "LEFT",
+            -4 => "RIGHT",
+            -5 => "SELECT",
+            -6 => "SOFT1",
+            -7 => "SOFT2",
+            -8 => "CLEAR",
+            -10 => "SEND",
+            -11 => "END",
+            35 => "#",
+            42 => "*",
+            48..=57 => ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"][(key_code - 48) as usize],
+            _ => return Err(anyhow::anyhow!("java.lang.IllegalArgumentException: invalid key code {}", key_code)),
+        };
+
+        Ok(JavaLangString::from_rust_string(jvm, name).await?.into())
+    }
+
+    async fn key_pressed(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, key_code: i32) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::keyPressed({:?}, {})", &this, key_code);
+
+        Ok(())
+    }
+
+    async fn key_released(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, key_code: i32) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::keyReleased({:?}, {})", &this, key_code);
+
+        Ok(())
+    }
+
+    async fn key_repeated(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, key_code: i32) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::keyRepeated({:?}, {})", &this, key_code);
+
+        Ok(())
+    }
+
+    async fn has_pointer_events(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<bool> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::hasPointerEvents({:?})", &this);
+
+        Ok(true)
+    }
+
+    async fn has_pointer_motion_events(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<bool> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::hasPointerMotionEvents({:?})", &this);
+
+        Ok(true)
+    }
+
+    async fn pointer_pressed(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, x: i32, y: i32) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::pointerPressed({:?}, {}, {})", &this, x, y);
+
+        Ok(())
+    }
+
+    async fn pointer_dragged(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, x: i32, y: i32) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::pointerDragged({:?}, {}, {})", &this, x, y);
+
+        Ok(())
+    }
+
+    async fn pointer_released(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, x: i32, y: i32) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.lcdui.Canvas::pointerReleased({:?}, {}, {})", &this, x, y);
+
+        Ok(())
+    }
+}
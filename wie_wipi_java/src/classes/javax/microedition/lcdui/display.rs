@@ -0,0 +1,132 @@
+use alloc::vec;
+
+use java_class_proto::{JavaFieldProto, JavaMethodProto, JavaResult};
+use java_constants::{FieldAccessFlags, MethodAccessFlags};
+use jvm::{ClassInstanceRef, Jvm};
+
+use wie_common::{Event, KeyCode};
+
+use crate::{
+    classes::javax::microedition::{lcdui::Displayable, midlet::MIDlet},
+    context::{WIPIJavaClassProto, WIPIJavaContext},
+};
+
+// class javax.microedition.lcdui.Display
+pub struct Display {}
+
+impl Display {
+    pub fn as_proto() -> WIPIJavaClassProto {
+        WIPIJavaClassProto {
+            parent_class: Some("java/lang/Object"),
+            interfaces: vec![],
+            methods: vec![
+                JavaMethodProto::new("<init>", "()V", Self::init, Default::default()),
+                JavaMethodProto::new(
+                    "getDisplay",
+                    "(Ljavax/microedition/midlet/MIDlet;)Ljavax/microedition/lcdui/Display;",
+                    Self::get_display,
+                    MethodAccessFlags::STATIC,
+                ),
+                JavaMethodProto::new(
+                    "getCurrent",
+                    "()Ljavax/microedition/lcdui/Displayable;",
+                    Self::get_current,
+                    Default::default(),
+                ),
+                JavaMethodProto::new(
+                    "setCurrent",
+                    "(Ljavax/microedition/lcdui/Displayable;)V",
+                    Self::set_current,
+                    Default::default(),
+                ),
+            ],
+            fields: vec![
+                JavaFieldProto::new("current", "Ljavax/microedition/lcdui/Displayable;", Default::default()),
+                JavaFieldProto::new("display", "Ljavax/microedition/lcdui/Display;", FieldAccessFlags::STATIC),
+            ],
+        }
+    }
+
+    async fn init(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.lcdui.Display::<init>({:?})", &this);
+
+        Ok(())
+    }
+
+    async fn get_display(jvm: &Jvm, _: &mut WIPIJavaContext, midlet: ClassInstanceRef<MIDlet>) -> JavaResult<ClassInstanceRef<Display>> {
+        tracing::debug!("javax.microedition.lcdui.Display::getDisplay({:?})", &midlet);
+
+        let display: ClassInstanceRef<Display> = jvm
+            .get_static_field("javax/microedition/lcdui/Display", "display", "Ljavax/microedition/lcdui/Display;")
+            .await?;
+
+        if !display.is_null() {
+            return Ok(display);
+        }
+
+        let display = jvm.new_class("javax/microedition/lcdui/Display", "()V", []).await?;
+        jvm.put_static_field(
+            "javax/microedition/lcdui/Display",
+            "display",
+            "Ljavax/microedition/lcdui/Display;",
+            display.clone(),
+        )
+        .await?;
+
+        Ok(display.into())
+    }
+
+    async fn get_current(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<ClassInstanceRef<Displayable>> {
+        tracing::debug!("javax.microedition.lcdui.Display::getCurrent({:?})", &this);
+
+        jvm.get_field(&this, "current", "Ljavax/microedition/lcdui/Displayable;")
+    }
+
+    async fn set_current(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        displayable: ClassInstanceRef<Displayable>,
+    ) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.lcdui.Display::setCurrent({:?}, {:?})", &this, &displayable);
+
+        jvm.put_field(&mut this, "current", "Ljavax/microedition/lcdui/Displayable;", displayable)?;
+
+        Ok(())
+    }
+
+    // delivers `event` to the current displayable, which should be a Canvas
+    pub async fn handle_event(jvm: &Jvm, event: Event) -> JavaResult<()> {
+        let display: ClassInstanceRef<Display> = jvm
+            .get_static_field("javax/microedition/lcdui/Display", "display", "Ljavax/microedition/lcdui/Display;")
+            .await?;
+        if display.is_null() {
+            return Ok(());
+        }
+
+        let current: ClassInstanceRef<Displayable> = jvm.get_field(&display, "current", "Ljavax/microedition/lcdui/Displayable;")?;
+        if current.is_null() {
+            return Ok(());
+        }
+
+        match event {
+            Event::Keydown(x) => Self::key_event(jvm, &current, "keyPressed", x).await,
+            Event::Keyup(x) => Self::key_event(jvm, &current, "keyReleased", x).await,
+            Event::Keyrepeat(x) => Self::key_event(jvm, &current, "keyRepeated", x).await,
+            Event::Pointerdown(x, y) => jvm.invoke_virtual(&current, "pointerPressed", "(II)V", (x, y)).await,
+            Event::Pointerdrag(x, y) => jvm.invoke_virtual(&current, "pointerDragged", "(II)V", (x, y)).await,
+            Event::Pointerup(x, y) => jvm.invoke_virtual(&current, "pointerReleased", "(II)V", (x, y)).await,
+            Event::Redraw => Ok(()),                              // TODO
+            Event::Pause | Event::Resume | Event::Exit => Ok(()), // handled by midlet
+        }
+    }
+
+    async fn key_event(jvm: &Jvm, current: &ClassInstanceRef<Displayable>, method: &str, key: KeyCode) -> JavaResult<()> {
+        // volume and side keys are not delivered to midlets
+        if matches!(key, KeyCode::VOLUMEUP | KeyCode::VOLUMEDOWN | KeyCode::SIDE) {
+            return Ok(());
+        }
+
+        jvm.invoke_virtual(current, method, "(I)V", (key.raw(),)).await
+    }
+}
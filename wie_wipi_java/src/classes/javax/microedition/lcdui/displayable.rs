@@ -0,0 +1,26 @@
+use alloc::vec;
+
+use java_class_proto::{JavaMethodProto, JavaResult};
+use jvm::{ClassInstanceRef, Jvm};
+
+use crate::context::{WIPIJavaClassProto, WIPIJavaContext};
+
+// class javax.microedition.lcdui.Displayable
+pub struct Displayable {}
+
+impl Displayable {
+    pub fn as_proto() -> WIPIJavaClassProto {
+        WIPIJavaClassProto {
+            parent_class: Some("java/lang/Object"),
+            interfaces: vec![],
+            methods: vec![JavaMethodProto::new("<init>", "()V", Self::init, Default::default())],
+            fields: vec![],
+        }
+    }
+
+    async fn init(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.lcdui.Displayable::<init>({:?})", &this);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+mod midlet;
+
+pub use self::midlet::MIDlet;
@@ -0,0 +1,117 @@
+use alloc::vec;
+
+use java_class_proto::{JavaFieldProto, JavaMethodProto, JavaResult};
+use java_runtime::classes::java::lang::String;
+use jvm::{runtime::JavaLangString, ClassInstanceRef, Jvm};
+
+use wie_common::Event;
+
+use crate::context::{WIPIJavaClassProto, WIPIJavaContext};
+
+// class javax.microedition.midlet.MIDlet
+pub struct MIDlet {}
+
+impl MIDlet {
+    pub fn as_proto() -> WIPIJavaClassProto {
+        WIPIJavaClassProto {
+            parent_class: Some("java/lang/Object"),
+            interfaces: vec![],
+            methods: vec![
+                JavaMethodProto::new("<init>", "()V", Self::init, Default::default()),
+                JavaMethodProto::new(
+                    "getAppProperty",
+                    "(Ljava/lang/String;)Ljava/lang/String;",
+                    Self::get_app_property,
+                    Default::default(),
+                ),
+                JavaMethodProto::new("notifyDestroyed", "()V", Self::notify_destroyed, Default::default()),
+                JavaMethodProto::new("notifyPaused", "()V", Self::notify_paused, Default::default()),
+                JavaMethodProto::new("resumeRequest", "()V", Self::resume_request, Default::default()),
+            ],
+            // set while paused, by the platform or by notifyPaused
+            fields: vec![JavaFieldProto::new("midletPaused", "Z", Default::default())],
+        }
+    }
+
+    /// Calls `pauseApp` unless the midlet is already paused
+    pub async fn pause(jvm: &Jvm, this: &ClassInstanceRef<Self>) -> JavaResult<()> {
+        if Self::paused(jvm, this)? {
+            return Ok(());
+        }
+
+        jvm.invoke_virtual(this, "pauseApp", "()V", []).await?;
+
+        Self::set_paused(jvm, this, true)
+    }
+
+    /// Calls `startApp` if the midlet is paused
+    pub async fn resume(jvm: &Jvm, this: &ClassInstanceRef<Self>) -> JavaResult<()> {
+        if !Self::paused(jvm, this)? {
+            return Ok(());
+        }
+
+        Self::set_paused(jvm, this, false)?;
+
+        jvm.invoke_virtual(this, "startApp", "()V", []).await
+    }
+
+    fn paused(jvm: &Jvm, this: &ClassInstanceRef<Self>) -> JavaResult<bool> {
+        jvm.get_field(this, "midletPaused", "Z")
+    }
+
+    fn set_paused(jvm: &Jvm, this: &ClassInstanceRef<Self>, paused: bool) -> JavaResult<()> {
+        let mut this = this.clone();
+
+        jvm.put_field(&mut this, "midletPaused", "Z", paused)
+    }
+
+    async fn init(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.midlet.MIDlet::<init>({:?})", &this);
+
+        Ok(())
+    }
+
+    async fn get_app_property(
+        jvm: &Jvm,
+        context: &mut WIPIJavaContext,
+        this: ClassInstanceRef<Self>,
+        key: ClassInstanceRef<String>,
+    ) -> JavaResult<ClassInstanceRef<String>> {
+        tracing::debug!("javax.microedition.midlet.MIDlet::getAppProperty({:?}, {:?})", &this, &key);
+
+        let key = JavaLangString::to_rust_string(jvm, key.into())?;
+        let value = context.system().app_properties().get(&key).cloned();
+
+        if let Some(x) = value {
+            Ok(JavaLangString::from_rust_string(jvm, &x).await?.into())
+        } else {
+            Ok(None.into())
+        }
+    }
+
+    async fn notify_destroyed(_: &Jvm, context: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.midlet.MIDlet::notifyDestroyed({:?})", &this);
+
+        context.system().exit();
+
+        Ok(())
+    }
+
+    async fn notify_paused(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.midlet.MIDlet::notifyPaused({:?})", &this);
+
+        // the midlet paused itself, so pauseApp isn't called
+        Self::set_paused(jvm, &this, true)
+    }
+
+    async fn resume_request(jvm: &Jvm, context: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<()> {
+        tracing::debug!("javax.microedition.midlet.MIDlet::resumeRequest({:?})", &this);
+
+        // handled by the midlet's event loop, which calls `startApp`
+        if Self::paused(jvm, &this)? {
+            context.system().push_event(Event::Resume);
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
-use alloc::{boxed::Box, vec};
+use alloc::{boxed::Box, vec, vec::Vec};
 
 use bytemuck::cast_vec;
-use wie_backend::Database;
+use wie_backend::{Database, DatabaseError, DatabaseRepository, DatabaseResult};
 
 use java_class_proto::{JavaFieldProto, JavaMethodProto, JavaResult};
 use java_constants::MethodAccessFlags;
@@ -19,66 +19,149 @@ impl DataBase {
             parent_class: Some("java/lang/Object"),
             interfaces: vec![],
             methods: vec![
-                JavaMethodProto::new("<init>", "(Ljava/lang/String;)V", Self::init, Default::default()),
+                JavaMethodProto::new("<init>", "(Ljava/lang/String;I)V", Self::init, Default::default()),
                 JavaMethodProto::new(
                     "openDataBase",
                     "(Ljava/lang/String;IZ)Lorg/kwis/msp/db/DataBase;",
                     Self::open_data_base,
                     MethodAccessFlags::STATIC,
                 ),
+                JavaMethodProto::new(
+                    "deleteDataBase",
+                    "(Ljava/lang/String;)V",
+                    Self::delete_data_base,
+                    MethodAccessFlags::STATIC,
+                ),
+                JavaMethodProto::new("listDataBase", "()[Ljava/lang/String;", Self::list_data_base, MethodAccessFlags::STATIC),
                 JavaMethodProto::new("getNumberOfRecords", "()I", Self::get_number_of_records, Default::default()),
+                JavaMethodProto::new("getRecordSize", "()I", Self::get_record_size, Default::default()),
                 JavaMethodProto::new("closeDataBase", "()V", Self::close_data_base, Default::default()),
+                JavaMethodProto::new("insertRecord", "([B)I", Self::insert_record_all, Default::default()),
                 JavaMethodProto::new("insertRecord", "([BII)I", Self::insert_record, Default::default()),
                 JavaMethodProto::new("selectRecord", "(I)[B", Self::select_record, Default::default()),
+                JavaMethodProto::new("updateRecord", "(I[B)V", Self::update_record_all, Default::default()),
+                JavaMethodProto::new("updateRecord", "(I[BII)V", Self::update_record, Default::default()),
+                JavaMethodProto::new("deleteRecord", "(I)V", Self::delete_record, Default::default()),
+                JavaMethodProto::new("listRecord", "()[I", Self::list_record, Default::default()),
+            ],
+            fields: vec![
+                JavaFieldProto::new("dbName", "Ljava/lang/String;", Default::default()),
+                JavaFieldProto::new("recordSize", "I", Default::default()),
             ],
-            fields: vec![JavaFieldProto::new("dbName", "Ljava/lang/String;", Default::default())],
         }
     }
-    async fn init(jvm: &Jvm, _: &mut WIPIJavaContext, mut this: ClassInstanceRef<Self>, data_base_name: ClassInstanceRef<String>) -> JavaResult<()> {
-        tracing::warn!("stub org.kwis.msp.db.DataBase::<init>({:?}, {:?})", &this, &data_base_name);
+
+    async fn init(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        mut this: ClassInstanceRef<Self>,
+        data_base_name: ClassInstanceRef<String>,
+        record_size: i32,
+    ) -> JavaResult<()> {
+        tracing::debug!("org.kwis.msp.db.DataBase::<init>({:?}, {:?}, {})", &this, &data_base_name, record_size);
 
         jvm.put_field(&mut this, "dbName", "Ljava/lang/String;", data_base_name)?;
+        jvm.put_field(&mut this, "recordSize", "I", record_size)?;
 
         Ok(())
     }
 
     async fn open_data_base(
         jvm: &Jvm,
-        _: &mut WIPIJavaContext,
+        context: &mut WIPIJavaContext,
         data_base_name: ClassInstanceRef<String>,
         record_size: i32,
         create: bool,
     ) -> JavaResult<ClassInstanceRef<DataBase>> {
-        tracing::warn!(
-            "stub org.kwis.msp.db.DataBase::openDataBase({:?}, {}, {})",
+        tracing::debug!(
+            "org.kwis.msp.db.DataBase::openDataBase({:?}, {}, {})",
             &data_base_name,
             record_size,
             create
         );
 
+        let db_name = JavaLangString::to_rust_string(jvm, data_base_name.clone().into())?;
+        let result = Self::repository(context, |x| match x.open(&db_name, create) {
+            Err(DatabaseError::NotFound) => {
+                // apps create the database when opening it fails, but the exception can't be thrown, so it's created here
+                tracing::warn!("Database {} doesn't exist, creating it", db_name);
+
+                x.open(&db_name, true)
+            }
+            x => x,
+        });
+        Self::check(result, "openDataBase");
+
         let instance = jvm
-            .new_class("org/kwis/msp/db/DataBase", "(Ljava/lang/String;)V", (data_base_name,))
+            .new_class("org/kwis/msp/db/DataBase", "(Ljava/lang/String;I)V", (data_base_name, record_size))
             .await?;
 
         Ok(instance.into())
     }
 
+    async fn delete_data_base(jvm: &Jvm, context: &mut WIPIJavaContext, data_base_name: ClassInstanceRef<String>) -> JavaResult<()> {
+        tracing::debug!("org.kwis.msp.db.DataBase::deleteDataBase({:?})", &data_base_name);
+
+        let db_name = JavaLangString::to_rust_string(jvm, data_base_name.into())?;
+        Self::check(Self::repository(context, |x| x.delete(&db_name)), "deleteDataBase");
+
+        Ok(())
+    }
+
+    async fn list_data_base(jvm: &Jvm, context: &mut WIPIJavaContext) -> JavaResult<ClassInstanceRef<Array<String>>> {
+        tracing::debug!("org.kwis.msp.db.DataBase::listDataBase()");
+
+        let names = Self::check(Self::repository(context, |x| x.list()), "listDataBase").unwrap_or_default();
+
+        let mut strings = Vec::with_capacity(names.len());
+        for name in names {
+            let string: ClassInstanceRef<String> = JavaLangString::from_rust_string(jvm, &name).await?.into();
+            strings.push(string);
+        }
+
+        let mut array = jvm.instantiate_array("Ljava/lang/String;", strings.len()).await?;
+        jvm.store_array(&mut array, 0, strings)?;
+
+        Ok(array.into())
+    }
+
     async fn get_number_of_records(jvm: &Jvm, context: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<i32> {
         tracing::debug!("org.kwis.msp.db.DataBase::getNumberOfRecords({:?})", &this);
 
-        let database = Self::get_database(jvm, context, &this)?;
-
-        let count = database.get_record_ids().len();
+        let count = Self::get_database(jvm, context, &this)?
+            .and_then(|x| Self::check(x.get_record_ids(), "getNumberOfRecords"))
+            .map(|x| x.len())
+            .unwrap_or(0);
 
         Ok(count as _)
     }
 
+    async fn get_record_size(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<i32> {
+        tracing::debug!("org.kwis.msp.db.DataBase::getRecordSize({:?})", &this);
+
+        Ok(jvm.get_field(&this, "recordSize", "I")?)
+    }
+
     async fn close_data_base(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<DataBase>) -> JavaResult<()> {
-        tracing::warn!("stub org.kwis.msp.db.DataBase::closeDataBase({:?})", &this);
+        tracing::debug!("org.kwis.msp.db.DataBase::closeDataBase({:?})", &this);
 
+        // every operation opens the database on its own, so there's nothing to release here
         Ok(())
     }
 
+    async fn insert_record_all(
+        jvm: &Jvm,
+        context: &mut WIPIJavaContext,
+        this: ClassInstanceRef<Self>,
+        data: ClassInstanceRef<Array<i8>>,
+    ) -> JavaResult<i32> {
+        tracing::debug!("org.kwis.msp.db.DataBase::insertRecord({:?}, {:?})", &this, &data);
+
+        let length = jvm.array_length(&data)?;
+
+        Self::insert_record(jvm, context, this, data, 0, length as _).await
+    }
+
     async fn insert_record(
         jvm: &Jvm,
         context: &mut WIPIJavaContext,
@@ -95,14 +178,17 @@ impl DataBase {
             num_bytes
         );
 
-        let mut database = Self::get_database(jvm, context, &this)?;
+        let mut database = match Self::get_database(jvm, context, &this)? {
+            Some(x) => x,
+            None => return Ok(-1),
+        };
 
         let data = jvm.load_byte_array(&data, offset as _, num_bytes as _)?;
         let data_raw = cast_vec(data);
 
-        let id = database.add(&data_raw);
+        let id = Self::check(database.add(&data_raw), "insertRecord").map(|x| x as i32).unwrap_or(-1);
 
-        Ok(id as _)
+        Ok(id)
     }
 
     async fn select_record(
@@ -110,12 +196,14 @@ impl DataBase {
         context: &mut WIPIJavaContext,
         this: ClassInstanceRef<Self>,
         record_id: i32,
-    ) -> JavaResult<ClassInstanceRef<i8>> {
+    ) -> JavaResult<ClassInstanceRef<Array<i8>>> {
         tracing::debug!("org.kwis.msp.db.DataBase::selectRecord({:?}, {})", &this, record_id);
 
-        let database = Self::get_database(jvm, context, &this)?;
-
-        let data = database.get(record_id as _).unwrap();
+        let data = Self::get_database(jvm, context, &this)?.and_then(|x| Self::check(x.get(record_id as _), "selectRecord"));
+        let data = match data {
+            Some(x) => x,
+            None => return Ok(None.into()),
+        };
 
         let mut array = jvm.instantiate_array("B", data.len() as _).await?;
         jvm.store_byte_array(&mut array, 0, cast_vec(data))?;
@@ -123,10 +211,172 @@ impl DataBase {
         Ok(array.into())
     }
 
-    fn get_database(jvm: &Jvm, context: &mut WIPIJavaContext, this: &ClassInstanceRef<Self>) -> JavaResult<Box<dyn Database>> {
+    async fn update_record_all(
+        jvm: &Jvm,
+        context: &mut WIPIJavaContext,
+        this: ClassInstanceRef<Self>,
+        record_id: i32,
+        data: ClassInstanceRef<Array<i8>>,
+    ) -> JavaResult<()> {
+        tracing::debug!("org.kwis.msp.db.DataBase::updateRecord({:?}, {}, {:?})", &this, record_id, &data);
+
+        let length = jvm.array_length(&data)?;
+
+        Self::update_record(jvm, context, this, record_id, data, 0, length as _).await
+    }
+
+    async fn update_record(
+        jvm: &Jvm,
+        context: &mut WIPIJavaContext,
+        this: ClassInstanceRef<Self>,
+        record_id: i32,
+        data: ClassInstanceRef<Array<i8>>,
+        offset: i32,
+        num_bytes: i32,
+    ) -> JavaResult<()> {
+        tracing::debug!(
+            "org.kwis.msp.db.DataBase::updateRecord({:?}, {}, {:?}, {}, {})",
+            &this,
+            record_id,
+            &data,
+            offset,
+            num_bytes
+        );
+
+        let mut database = match Self::get_database(jvm, context, &this)? {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+
+        // updating a record that doesn't exist is an error, not an insert
+        if Self::check(database.get(record_id as _), "updateRecord").is_none() {
+            return Ok(());
+        }
+
+        let data = jvm.load_byte_array(&data, offset as _, num_bytes as _)?;
+        let data_raw = cast_vec(data);
+
+        Self::check(database.set(record_id as _, &data_raw), "updateRecord");
+
+        Ok(())
+    }
+
+    async fn delete_record(jvm: &Jvm, context: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, record_id: i32) -> JavaResult<()> {
+        tracing::debug!("org.kwis.msp.db.DataBase::deleteRecord({:?}, {})", &this, record_id);
+
+        if let Some(mut database) = Self::get_database(jvm, context, &this)? {
+            Self::check(database.delete(record_id as _), "deleteRecord");
+        }
+
+        Ok(())
+    }
+
+    async fn list_record(jvm: &Jvm, context: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<ClassInstanceRef<Array<i32>>> {
+        tracing::debug!("org.kwis.msp.db.DataBase::listRecord({:?})", &this);
+
+        let ids = Self::get_database(jvm, context, &this)?
+            .and_then(|x| Self::check(x.get_record_ids(), "listRecord"))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| x as i32)
+            .collect::<Vec<_>>();
+
+        let mut array = jvm.instantiate_array("I", ids.len()).await?;
+        jvm.store_array(&mut array, 0, ids)?;
+
+        Ok(array.into())
+    }
+
+    fn get_database(jvm: &Jvm, context: &mut WIPIJavaContext, this: &ClassInstanceRef<Self>) -> JavaResult<Option<Box<dyn Database>>> {
         let db_name = jvm.get_field(this, "dbName", "Ljava/lang/String;")?;
         let db_name_str = JavaLangString::to_rust_string(jvm, db_name)?;
 
-        Ok(context.system().platform().database_repository().open(&db_name_str))
+        Ok(Self::check(Self::repository(context, |x| x.open(&db_name_str, false)), "open"))
+    }
+
+    fn repository<T, F>(context: &mut WIPIJavaContext, f: F) -> T
+    where
+        F: FnOnce(&dyn DatabaseRepository) -> T,
+    {
+        let platform = context.system().platform();
+
+        f(platform.database_repository())
+    }
+
+    // TODO throw DataBaseException and DataBaseRecordException when the jvm can unwind into java exception handlers.
+    // until then errors are logged and the call returns an empty result, so the app keeps running
+    fn check<T>(result: DatabaseResult<T>, operation: &str) -> Option<T> {
+        match result {
+            Ok(x) => Some(x),
+            Err(err) => {
+                tracing::warn!("org.kwis.msp.db.DataBase::{} failed: {}", operation, err);
+
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::boxed::Box;
+    use core::future::ready;
+
+    use java_runtime::classes::java::lang::String;
+    use jvm::{runtime::JavaLangString, Array, ClassInstanceRef, Jvm};
+    use jvm_rust::ClassDefinitionImpl;
+
+    use test_utils::test_jvm;
+
+    use crate::{classes::org::kwis::msp::db::DataBase, context::test::TestContext, register};
+
+    async fn init_jvm() -> anyhow::Result<Jvm> {
+        let jvm = test_jvm().await?;
+        let context = TestContext::new();
+
+        register(&jvm, |name, proto| {
+            ready(Box::new(ClassDefinitionImpl::from_class_proto(name, proto, Box::new(context.clone()) as Box<_>)) as Box<_>)
+        })
+        .await?;
+
+        Ok(jvm)
+    }
+
+    async fn open(jvm: &Jvm, name: &str, create: bool) -> anyhow::Result<ClassInstanceRef<DataBase>> {
+        let name: ClassInstanceRef<String> = JavaLangString::from_rust_string(jvm, name).await?.into();
+
+        jvm.invoke_static(
+            "org/kwis/msp/db/DataBase",
+            "openDataBase",
+            "(Ljava/lang/String;IZ)Lorg/kwis/msp/db/DataBase;",
+            (name, 0, create),
+        )
+        .await
+    }
+
+    #[futures_test::test]
+    async fn test_missing_database() -> anyhow::Result<()> {
+        let jvm = init_jvm().await?;
+
+        // created instead of failing, as the exception can't be caught
+        let database = open(&jvm, "missing", false).await?;
+        let count: i32 = jvm.invoke_virtual(&database, "getNumberOfRecords", "()I", ()).await?;
+        assert_eq!(count, 0);
+
+        Ok(())
+    }
+
+    #[futures_test::test]
+    async fn test_missing_record() -> anyhow::Result<()> {
+        let jvm = init_jvm().await?;
+
+        let database = open(&jvm, "test", true).await?;
+
+        let record: ClassInstanceRef<Array<i8>> = jvm.invoke_virtual(&database, "selectRecord", "(I)[B", (1,)).await?;
+        assert!(record.is_null());
+
+        let _: () = jvm.invoke_virtual(&database, "deleteRecord", "(I)V", (1,)).await?;
+
+        Ok(())
     }
 }
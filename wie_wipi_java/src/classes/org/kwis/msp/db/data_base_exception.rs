@@ -1,6 +1,10 @@
 use alloc::vec;
 
-use crate::context::WIPIJavaClassProto;
+use java_class_proto::{JavaMethodProto, JavaResult};
+use java_runtime::classes::java::lang::String;
+use jvm::{ClassInstanceRef, Jvm};
+
+use crate::context::{WIPIJavaClassProto, WIPIJavaContext};
 
 // class org.kwis.msp.db.DataBaseException
 pub struct DataBaseException {}
@@ -10,8 +14,33 @@ impl DataBaseException {
         WIPIJavaClassProto {
             parent_class: Some("java/lang/Exception"),
             interfaces: vec![],
-            methods: vec![],
+            methods: vec![
+                JavaMethodProto::new("<init>", "()V", Self::init, Default::default()),
+                JavaMethodProto::new("<init>", "(Ljava/lang/String;)V", Self::init_with_message, Default::default()),
+            ],
             fields: vec![],
         }
     }
+
+    async fn init(jvm: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<()> {
+        tracing::debug!("org.kwis.msp.db.DataBaseException::<init>({:?})", &this);
+
+        jvm.invoke_special(&this, "java/lang/Exception", "<init>", "()V", []).await?;
+
+        Ok(())
+    }
+
+    async fn init_with_message(
+        jvm: &Jvm,
+        _: &mut WIPIJavaContext,
+        this: ClassInstanceRef<Self>,
+        message: ClassInstanceRef<String>,
+    ) -> JavaResult<()> {
+        tracing::debug!("org.kwis.msp.db.DataBaseException::<init>({:?}, {:?})", &this, &message);
+
+        jvm.invoke_special(&this, "java/lang/Exception", "<init>", "(Ljava/lang/String;)V", (message,))
+            .await?;
+
+        Ok(())
+    }
 }
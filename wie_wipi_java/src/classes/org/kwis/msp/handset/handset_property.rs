@@ -25,11 +25,18 @@ impl HandsetProperty {
         }
     }
 
-    async fn get_system_property(jvm: &Jvm, _: &mut WIPIJavaContext, name: ClassInstanceRef<String>) -> JavaResult<ClassInstanceRef<String>> {
+    async fn get_system_property(jvm: &Jvm, context: &mut WIPIJavaContext, name: ClassInstanceRef<String>) -> JavaResult<ClassInstanceRef<String>> {
         let name = JavaLangString::to_rust_string(jvm, name.into())?;
-        tracing::warn!("stub org.kwis.msp.handset.HandsetProperty::getSystemProperty({})", name);
+        tracing::debug!("org.kwis.msp.handset.HandsetProperty::getSystemProperty({})", name);
 
-        let result = JavaLangString::from_rust_string(jvm, "").await?;
+        let value = context.system().platform().profile().property(&name);
+        let value = value.unwrap_or_else(|| {
+            tracing::warn!("Unknown system property {}", name);
+
+            "".into()
+        });
+
+        let result = JavaLangString::from_rust_string(jvm, &value).await?;
         Ok(result.into())
     }
 }
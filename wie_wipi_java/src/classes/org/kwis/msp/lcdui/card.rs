@@ -26,6 +26,7 @@ impl Card {
                 JavaMethodProto::new("repaint", "(IIII)V", Self::repaint_with_area, Default::default()),
                 JavaMethodProto::new("repaint", "()V", Self::repaint, Default::default()),
                 JavaMethodProto::new("serviceRepaints", "()V", Self::service_repaints, Default::default()),
+                JavaMethodProto::new("pointerNotify", "(III)Z", Self::pointer_notify, Default::default()),
             ],
             fields: vec![
                 JavaFieldProto::new("display", "Lorg/kwis/msp/lcdui/Display;", Default::default()),
@@ -128,6 +129,12 @@ impl Card {
         Ok(())
     }
 
+    async fn pointer_notify(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Card>, r#type: i32, x: i32, y: i32) -> JavaResult<bool> {
+        tracing::warn!("stub org.kwis.msp.lcdui.Card::pointerNotify({:?}, {}, {}, {})", &this, r#type, x, y);
+
+        Ok(false)
+    }
+
     async fn service_repaints(_: &Jvm, context: &mut WIPIJavaContext, this: ClassInstanceRef<Card>) -> JavaResult<()> {
         tracing::warn!("stub org.kwis.msp.lcdui.Card::serviceRepaints({:?})", &this);
 
@@ -180,6 +180,7 @@ impl Display {
         tracing::debug!("org.kwis.msp.lcdui.Display::callSerially({:?}, {:?})", &this, &r);
 
         // TODO this method have to queue runnable in event queue, but for now we'll spawn new task
+        //      which runs after the current task yields
 
         struct SpawnProxy {
             runnable: ClassInstanceRef<Runnable>,
@@ -188,8 +189,7 @@ impl Display {
         #[async_trait::async_trait(?Send)]
         impl MethodBody<JavaError, WIPIJavaContext> for SpawnProxy {
             async fn call(&self, jvm: &Jvm, context: &mut WIPIJavaContext, _: Box<[JavaValue]>) -> Result<JavaValue, JavaError> {
-                let until = context.system().platform().now() + 16; // TODO
-                context.system().sleep(until).await;
+                context.system().yield_now().await;
 
                 jvm.invoke_virtual(&self.runnable, "run", "()V", ()).await?;
 
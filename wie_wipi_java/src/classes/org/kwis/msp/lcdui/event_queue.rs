@@ -3,8 +3,6 @@ use alloc::{vec, vec::Vec};
 use java_class_proto::{JavaMethodProto, JavaResult};
 use jvm::{Array, ClassInstanceRef, Jvm};
 
-use wie_common::KeyCode;
-
 use crate::{
     classes::org::kwis::msp::lcdui::{Card, Display, Image, Jlet},
     context::{WIPIJavaClassProto, WIPIJavaContext},
@@ -13,12 +11,19 @@ use crate::{
 #[repr(i32)]
 enum EventQueueEvent {
     KeyEvent = 1,
+    PointerEvent = 2,
     RepaintEvent = 41,
 }
 
 impl EventQueueEvent {
-    fn from_raw(raw: i32) -> Self {
-        unsafe { core::mem::transmute(raw) }
+    // events come from the app's dispatchEvent argument, so unknown values are possible
+    fn from_raw(raw: i32) -> Option<Self> {
+        match raw {
+            1 => Some(Self::KeyEvent),
+            2 => Some(Self::PointerEvent),
+            41 => Some(Self::RepaintEvent),
+            _ => None,
+        }
     }
 }
 
@@ -33,54 +38,33 @@ enum KeyboardEventType {
 }
 
 impl KeyboardEventType {
-    fn from_raw(raw: i32) -> Self {
-        unsafe { core::mem::transmute(raw) }
+    fn from_raw(raw: i32) -> Option<Self> {
+        match raw {
+            1 => Some(Self::KeyPressed),
+            2 => Some(Self::KeyReleased),
+            3 => Some(Self::KeyRepeated),
+            4 => Some(Self::KeyTyped),
+            _ => None,
+        }
     }
 }
 
 #[repr(i32)]
-#[allow(clippy::upper_case_acronyms)]
-enum WIPIKeyCode {
-    UP = -1,
-    DOWN = -2,
-    LEFT = -3,
-    RIGHT = -4,
-    FIRE = -5, // Ok
-
-    NUM0 = 48,
-    NUM1 = 49,
-    NUM2 = 50,
-    NUM3 = 51,
-    NUM4 = 52,
-    NUM5 = 53,
-    NUM6 = 54,
-    NUM7 = 55,
-    NUM8 = 56,
-    NUM9 = 57,
-    HASH = 35, // #
-    STAR = 42, // *
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+enum PointerEventType {
+    PointerPressed = 1,
+    PointerReleased = 2,
+    PointerDragged = 3,
 }
 
-impl WIPIKeyCode {
-    fn from_key_code(keycode: KeyCode) -> Self {
-        match keycode {
-            KeyCode::UP => Self::UP,
-            KeyCode::DOWN => Self::DOWN,
-            KeyCode::LEFT => Self::LEFT,
-            KeyCode::RIGHT => Self::RIGHT,
-            KeyCode::OK => Self::FIRE,
-            KeyCode::NUM0 => Self::NUM0,
-            KeyCode::NUM1 => Self::NUM1,
-            KeyCode::NUM2 => Self::NUM2,
-            KeyCode::NUM3 => Self::NUM3,
-            KeyCode::NUM4 => Self::NUM4,
-            KeyCode::NUM5 => Self::NUM5,
-            KeyCode::NUM6 => Self::NUM6,
-            KeyCode::NUM7 => Self::NUM7,
-            KeyCode::NUM8 => Self::NUM8,
-            KeyCode::NUM9 => Self::NUM9,
-            KeyCode::HASH => Self::HASH,
-            KeyCode::STAR => Self::STAR,
+impl PointerEventType {
+    fn from_raw(raw: i32) -> Option<Self> {
+        match raw {
+            1 => Some(Self::PointerPressed),
+            2 => Some(Self::PointerReleased),
+            3 => Some(Self::PointerDragged),
+            _ => None,
         }
     }
 }
@@ -116,34 +100,40 @@ impl EventQueue {
     ) -> JavaResult<()> {
         tracing::debug!("org.kwis.msp.lcdui.EventQueue::getNextEvent({:?}, {:?})", &this, &event);
 
-        loop {
-            let maybe_event = context.system().event_queue().pop();
-
-            if let Some(x) = maybe_event {
-                let event_data = match x {
-                    wie_common::Event::Redraw => vec![EventQueueEvent::RepaintEvent as _, 0, 0, 0],
-                    wie_common::Event::Keydown(x) => vec![
-                        EventQueueEvent::KeyEvent as _,
-                        KeyboardEventType::KeyPressed as _,
-                        WIPIKeyCode::from_key_code(x) as _,
-                        0,
-                    ],
-                    wie_common::Event::Keyup(x) => vec![
-                        EventQueueEvent::KeyEvent as _,
-                        KeyboardEventType::KeyReleased as _,
-                        WIPIKeyCode::from_key_code(x) as _,
-                        0,
-                    ],
-                };
-
-                jvm.store_array(&mut event, 0, event_data)?;
-
-                break;
-            } else {
-                let until = context.system().platform().now() + 16;
-                context.system().sleep(until).await; // TODO we need to wait for events
-            }
-        }
+        // lifecycle events have no event id to hand to the app, so they're handled here while waiting for the next event
+        let event_data = loop {
+            break match context.system().next_event().await {
+                wie_common::Event::Pause => {
+                    let jlet = Self::get_active_jlet(jvm).await?;
+                    jvm.invoke_virtual(&jlet, "pauseApp", "()V", []).await?;
+
+                    continue;
+                }
+                wie_common::Event::Resume => {
+                    let jlet = Self::get_active_jlet(jvm).await?;
+                    jvm.invoke_virtual(&jlet, "resumeApp", "()V", []).await?;
+
+                    continue;
+                }
+                wie_common::Event::Exit => {
+                    let jlet = Self::get_active_jlet(jvm).await?;
+                    jvm.invoke_virtual(&jlet, "destroyApp", "(Z)V", (true,)).await?;
+
+                    context.system().exit();
+
+                    continue;
+                }
+                wie_common::Event::Redraw => vec![EventQueueEvent::RepaintEvent as _, 0, 0, 0],
+                wie_common::Event::Keydown(x) => vec![EventQueueEvent::KeyEvent as _, KeyboardEventType::KeyPressed as _, x.raw(), 0],
+                wie_common::Event::Keyup(x) => vec![EventQueueEvent::KeyEvent as _, KeyboardEventType::KeyReleased as _, x.raw(), 0],
+                wie_common::Event::Keyrepeat(x) => vec![EventQueueEvent::KeyEvent as _, KeyboardEventType::KeyRepeated as _, x.raw(), 0],
+                wie_common::Event::Pointerdown(x, y) => vec![EventQueueEvent::PointerEvent as _, PointerEventType::PointerPressed as _, x, y],
+                wie_common::Event::Pointerdrag(x, y) => vec![EventQueueEvent::PointerEvent as _, PointerEventType::PointerDragged as _, x, y],
+                wie_common::Event::Pointerup(x, y) => vec![EventQueueEvent::PointerEvent as _, PointerEventType::PointerReleased as _, x, y],
+            };
+        };
+
+        jvm.store_array(&mut event, 0, event_data)?;
 
         Ok(())
     }
@@ -158,17 +148,37 @@ impl EventQueue {
 
         let event = jvm.load_array(&event, 0, 4)?;
 
-        match EventQueueEvent::from_raw(event[0]) {
-            EventQueueEvent::RepaintEvent => {
-                Self::repaint(jvm, context).await?;
+        let event_type = match EventQueueEvent::from_raw(event[0]) {
+            Some(x) => x,
+            None => {
+                tracing::warn!("Unknown event {}", event[0]);
+
+                return Ok(());
             }
-            EventQueueEvent::KeyEvent => {
-                let event_type = KeyboardEventType::from_raw(event[1]);
-                let code = event[2];
+        };
 
-                tracing::debug!("KeyEvent {:?} {}", event_type, code);
-                Self::key_event(jvm, event_type, code).await?;
+        match event_type {
+            EventQueueEvent::RepaintEvent => {
+                Self::repaint(jvm, context).await?;
             }
+            EventQueueEvent::KeyEvent => match KeyboardEventType::from_raw(event[1]) {
+                Some(event_type) => {
+                    let code = event[2];
+
+                    tracing::debug!("KeyEvent {:?} {}", event_type, code);
+                    Self::key_event(jvm, event_type, code).await?;
+                }
+                None => tracing::warn!("Unknown key event type {}", event[1]),
+            },
+            EventQueueEvent::PointerEvent => match PointerEventType::from_raw(event[1]) {
+                Some(event_type) => {
+                    let (x, y) = (event[2], event[3]);
+
+                    tracing::debug!("PointerEvent {:?} {} {}", event_type, x, y);
+                    Self::pointer_event(jvm, event_type, x, y).await?;
+                }
+                None => tracing::warn!("Unknown pointer event type {}", event[1]),
+            },
         }
 
         Ok(())
@@ -190,6 +200,22 @@ impl EventQueue {
         Ok(())
     }
 
+    async fn pointer_event(jvm: &Jvm, event_type: PointerEventType, x: i32, y: i32) -> JavaResult<()> {
+        let display = Self::get_current_display(jvm).await?;
+        if display.is_null() {
+            return Ok(());
+        }
+
+        let card = Self::get_top_card(jvm, &display)?;
+        if card.is_null() {
+            return Ok(());
+        }
+
+        let _: bool = jvm.invoke_virtual(&card, "pointerNotify", "(III)Z", (event_type as i32, x, y)).await?;
+
+        Ok(())
+    }
+
     async fn repaint(jvm: &Jvm, context: &mut WIPIJavaContext) -> JavaResult<()> {
         let display = Self::get_current_display(jvm).await?;
         if display.is_null() {
@@ -228,10 +254,13 @@ impl EventQueue {
         Ok(())
     }
 
+    async fn get_active_jlet(jvm: &Jvm) -> JavaResult<ClassInstanceRef<Jlet>> {
+        jvm.invoke_static("org/kwis/msp/lcdui/Jlet", "getActiveJlet", "()Lorg/kwis/msp/lcdui/Jlet;", [])
+            .await
+    }
+
     async fn get_current_display(jvm: &Jvm) -> JavaResult<ClassInstanceRef<Display>> {
-        let jlet = jvm
-            .invoke_static("org/kwis/msp/lcdui/Jlet", "getActiveJlet", "()Lorg/kwis/msp/lcdui/Jlet;", [])
-            .await?;
+        let jlet = Self::get_active_jlet(jvm).await?;
 
         jvm.get_field(&jlet, "dis", "Lorg/kwis/msp/lcdui/Display;")
     }
@@ -248,3 +277,20 @@ impl EventQueue {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{EventQueueEvent, KeyboardEventType, PointerEventType};
+
+    #[test]
+    fn test_from_raw() {
+        assert!(matches!(EventQueueEvent::from_raw(2), Some(EventQueueEvent::PointerEvent)));
+        assert!(EventQueueEvent::from_raw(3).is_none());
+
+        assert!(matches!(KeyboardEventType::from_raw(3), Some(KeyboardEventType::KeyRepeated)));
+        assert!(KeyboardEventType::from_raw(0).is_none());
+
+        assert!(matches!(PointerEventType::from_raw(3), Some(PointerEventType::PointerDragged)));
+        assert!(PointerEventType::from_raw(-1).is_none());
+    }
+}
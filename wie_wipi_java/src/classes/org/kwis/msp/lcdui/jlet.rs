@@ -32,6 +32,10 @@ impl Jlet {
                     Self::get_event_queue,
                     Default::default(),
                 ),
+                JavaMethodProto::new("pauseApp", "()V", Self::pause_app, Default::default()),
+                JavaMethodProto::new("resumeApp", "()V", Self::resume_app, Default::default()),
+                JavaMethodProto::new("destroyApp", "(Z)V", Self::destroy_app, Default::default()),
+                JavaMethodProto::new("notifyDestroyed", "()V", Self::notify_destroyed, Default::default()),
                 JavaMethodProto::new(
                     "getAppProperty",
                     "(Ljava/lang/String;)Ljava/lang/String;",
@@ -90,15 +94,48 @@ impl Jlet {
         Ok(eq)
     }
 
+    async fn pause_app(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<()> {
+        tracing::debug!("org.kwis.msp.lcdui.Jlet::pauseApp({:?})", &this);
+
+        Ok(())
+    }
+
+    async fn resume_app(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<()> {
+        tracing::debug!("org.kwis.msp.lcdui.Jlet::resumeApp({:?})", &this);
+
+        Ok(())
+    }
+
+    async fn destroy_app(_: &Jvm, _: &mut WIPIJavaContext, this: ClassInstanceRef<Self>, unconditional: bool) -> JavaResult<()> {
+        tracing::debug!("org.kwis.msp.lcdui.Jlet::destroyApp({:?}, {})", &this, unconditional);
+
+        Ok(())
+    }
+
+    async fn notify_destroyed(_: &Jvm, context: &mut WIPIJavaContext, this: ClassInstanceRef<Self>) -> JavaResult<()> {
+        tracing::debug!("org.kwis.msp.lcdui.Jlet::notifyDestroyed({:?})", &this);
+
+        context.system().exit();
+
+        Ok(())
+    }
+
     async fn get_app_property(
         jvm: &Jvm,
-        _: &mut WIPIJavaContext,
+        context: &mut WIPIJavaContext,
         this: ClassInstanceRef<Self>,
         key: ClassInstanceRef<String>,
     ) -> JavaResult<ClassInstanceRef<String>> {
-        tracing::warn!("stub org.kwis.msp.lcdui.Jlet::getAppProperty({:?}, {:?})", &this, &key);
+        tracing::debug!("org.kwis.msp.lcdui.Jlet::getAppProperty({:?}, {:?})", &this, &key);
 
-        Ok(JavaLangString::from_rust_string(jvm, "").await?.into())
+        let key = JavaLangString::to_rust_string(jvm, key.into())?;
+        let value = context.system().app_properties().get(&key).cloned();
+
+        if let Some(x) = value {
+            Ok(JavaLangString::from_rust_string(jvm, &x).await?.into())
+        } else {
+            Ok(None.into())
+        }
     }
 
     pub async fn start(jvm: &Jvm, context: &mut WIPIJavaContext, main_class_name: &str) -> JavaResult<()> {
@@ -22,7 +22,8 @@ pub mod test {
 
     use java_class_proto::{JavaResult, MethodBody};
 
-    use wie_backend::SystemHandle;
+    use test_utils::TestPlatform;
+    use wie_backend::{encoding::EUC_KR, System, SystemHandle};
 
     use crate::context::WIPIJavaContextBase;
 
@@ -38,4 +39,28 @@ pub mod test {
             todo!()
         }
     }
+
+    // context backed by a system on the test platform, for classes that use the platform
+    #[derive(Clone)]
+    pub struct TestContext {
+        system: SystemHandle,
+    }
+
+    impl TestContext {
+        pub fn new() -> Self {
+            let system = System::new(Box::new(TestPlatform::new()), Box::new(()), EUC_KR);
+
+            Self { system: system.handle() }
+        }
+    }
+
+    impl WIPIJavaContextBase for TestContext {
+        fn system(&mut self) -> &mut SystemHandle {
+            &mut self.system
+        }
+
+        fn spawn(&mut self, _callback: Box<dyn MethodBody<anyhow::Error, dyn WIPIJavaContextBase>>) -> JavaResult<()> {
+            todo!()
+        }
+    }
 }
@@ -20,6 +20,22 @@ where
 {
     // superclass should come before subclass
     let classes = [
+        (
+            "javax/microedition/lcdui/Displayable",
+            crate::classes::javax::microedition::lcdui::Displayable::as_proto(),
+        ),
+        (
+            "javax/microedition/lcdui/Canvas",
+            crate::classes::javax::microedition::lcdui::Canvas::as_proto(),
+        ),
+        (
+            "javax/microedition/lcdui/Display",
+            crate::classes::javax::microedition::lcdui::Display::as_proto(),
+        ),
+        (
+            "javax/microedition/midlet/MIDlet",
+            crate::classes::javax::microedition::midlet::MIDlet::as_proto(),
+        ),
         ("org/kwis/msf/io/Network", crate::classes::org::kwis::msf::io::Network::as_proto()),
         ("org/kwis/msp/db/DataBase", crate::classes::org::kwis::msp::db::DataBase::as_proto()),
         (